@@ -0,0 +1,616 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use serde_json::json;
+use std::{collections::HashMap, fmt::Write, net::SocketAddr, path::PathBuf};
+use tracing::warn;
+
+use crate::{
+    error::Result,
+    raindrop::{
+        client::{MAX_PER_PAGE, RaindropClient},
+        types::{
+            Bookmark, CollectionRef, NewBookmark, SearchParams, TRASH_COLLECTION_ID,
+            UNSORTED_COLLECTION_ID,
+        },
+    },
+};
+
+// Widest a table cell gets before it is cut short
+const MAX_CELL_CHARS: usize = 60;
+
+/// Bookmarks `bookmarks search` lists unless `--limit` says otherwise.
+pub const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Profile from the config file to act as by default
+    #[arg(long, env = "RAINDROP_PROFILE", global = true)]
+    pub profile: Option<String>,
+
+    /// Config file (default: $RAINDROP_CONFIG or ~/.config/raindrop-mcp-server/config.toml)
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// How command results are printed
+    #[arg(long, short, value_enum, default_value_t = OutputMode::Table, global = true)]
+    pub output: OutputMode,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Serve MCP over stdio (the default)
+    Serve {
+        /// Serve streamable HTTP on this address instead, each session authenticating
+        /// with its own Raindrop token as a bearer token
+        #[arg(long, value_name = "ADDR")]
+        http: Option<SocketAddr>,
+    },
+    /// Authorize the profile's OAuth app in the browser and save its tokens
+    Auth,
+    #[command(flatten)]
+    Api(ApiCommand),
+}
+
+/// Commands run directly against the Raindrop API.
+#[derive(Debug, Subcommand)]
+pub enum ApiCommand {
+    /// Browse collections
+    #[command(subcommand)]
+    Collections(CollectionsCommand),
+    /// Search and save bookmarks
+    #[command(subcommand)]
+    Bookmarks(BookmarksCommand),
+    /// List and rename tags
+    #[command(subcommand)]
+    Tags(TagsCommand),
+    /// Write bookmarks to stdout as Markdown, CSV or JSON
+    Export(ExportArgs),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CollectionsCommand {
+    /// List every collection, nested ones included
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BookmarksCommand {
+    /// Find bookmarks by text, tag or collection
+    Search {
+        /// Raindrop search query
+        query: Option<String>,
+        /// Only bookmarks with this tag (repeat to require several)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Collection name or ID to search in, instead of all bookmarks
+        #[arg(long)]
+        collection: Option<String>,
+        /// Most bookmarks to list
+        #[arg(long, default_value_t = DEFAULT_SEARCH_LIMIT)]
+        limit: usize,
+    },
+    /// Save a link, taking its title and excerpt from the page unless given
+    Add {
+        url: String,
+        #[arg(long)]
+        title: Option<String>,
+        /// Tag to add (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Collection name or ID (default: the profile's default collection)
+        #[arg(long)]
+        collection: Option<String>,
+        #[arg(long)]
+        note: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TagsCommand {
+    /// List tags with their bookmark counts
+    List {
+        /// Collection name or ID, instead of all bookmarks
+        #[arg(long)]
+        collection: Option<String>,
+    },
+    /// Rename a tag on every bookmark that has it
+    Rename {
+        from: String,
+        to: String,
+        /// Collection name or ID, instead of all bookmarks
+        #[arg(long)]
+        collection: Option<String>,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    #[arg(long, value_enum, default_value_t = ExportFormat::Md)]
+    pub format: ExportFormat,
+    /// Collection name or ID, instead of all bookmarks
+    #[arg(long)]
+    pub collection: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputMode {
+    /// Aligned columns for reading
+    Table,
+    /// The API objects as JSON, for scripts
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// Markdown link list, one section per collection
+    Md,
+    Csv,
+    Json,
+}
+
+/// Runs `command` as `client`, returning what to print on stdout.
+pub async fn execute(
+    client: &RaindropClient,
+    command: &ApiCommand,
+    output: OutputMode,
+) -> Result<String> {
+    match command {
+        ApiCommand::Collections(CollectionsCommand::List) => {
+            let collections = client.get_all_collections().await?;
+            render(output, &collections, || {
+                table(
+                    &["ID", "TITLE", "COUNT", "PARENT"],
+                    collections
+                        .iter()
+                        .map(|c| {
+                            vec![
+                                c.id.to_string(),
+                                c.title.clone(),
+                                c.count.to_string(),
+                                c.parent
+                                    .as_ref()
+                                    .map_or_else(|| "-".to_string(), |p| p.id.to_string()),
+                            ]
+                        })
+                        .collect(),
+                )
+            })
+        }
+        ApiCommand::Bookmarks(BookmarksCommand::Search {
+            query,
+            tags,
+            collection,
+            limit,
+        }) => {
+            let collection = resolve_collection(client, collection.as_deref()).await?;
+            let per_page = (*limit).clamp(1, MAX_PER_PAGE as usize);
+            let mut bookmarks = vec![];
+            let mut page = 0;
+            while bookmarks.len() < *limit {
+                let result = client
+                    .get_bookmarks(SearchParams {
+                        collection: Some(collection.unwrap_or(0)),
+                        search: query.clone(),
+                        tags: (!tags.is_empty()).then(|| tags.clone()),
+                        page: Some(page),
+                        per_page: Some(per_page as i32),
+                        ..Default::default()
+                    })
+                    .await?;
+                let done = result.items.len() < per_page;
+                bookmarks.extend(result.items);
+                if done {
+                    break;
+                }
+                page += 1;
+            }
+            bookmarks.truncate(*limit);
+            render(output, &bookmarks, || bookmark_table(&bookmarks))
+        }
+        ApiCommand::Bookmarks(BookmarksCommand::Add {
+            url,
+            title,
+            tags,
+            collection,
+            note,
+        }) => {
+            let collection = resolve_collection(client, collection.as_deref())
+                .await?
+                .unwrap_or_else(|| client.default_collection());
+            let mut bookmark = NewBookmark {
+                link: url.clone(),
+                collection: Some(CollectionRef { id: collection }),
+                title: title.clone(),
+                note: note.clone(),
+                tags: (!tags.is_empty()).then(|| tags.clone()),
+                ..Default::default()
+            };
+            if bookmark.title.is_none() {
+                match client.parse_url(url).await {
+                    Ok(parsed) if parsed.title.is_some() => bookmark.apply_metadata(&parsed),
+                    // Let Raindrop fill in the page details in the background
+                    Ok(_) => bookmark.please_parse = Some(json!({})),
+                    Err(e) => {
+                        warn!("Could not parse metadata for {}: {}", url, e);
+                        bookmark.please_parse = Some(json!({}));
+                    }
+                }
+            }
+            let bookmark = client.create_bookmark_from(bookmark).await?;
+            render(output, &bookmark, || {
+                bookmark_table(std::slice::from_ref(&bookmark))
+            })
+        }
+        ApiCommand::Tags(TagsCommand::List { collection }) => {
+            let collection = resolve_collection(client, collection.as_deref()).await?;
+            let tags = client.get_tags(collection).await?;
+            render(output, &tags, || {
+                table(
+                    &["TAG", "COUNT"],
+                    tags.iter()
+                        .map(|t| vec![t.id.clone(), t.count.to_string()])
+                        .collect(),
+                )
+            })
+        }
+        ApiCommand::Tags(TagsCommand::Rename {
+            from,
+            to,
+            collection,
+        }) => {
+            let collection = resolve_collection(client, collection.as_deref()).await?;
+            client
+                .rename_tag(from.clone(), to.clone(), collection)
+                .await?;
+            render(output, &json!({"from": from, "to": to}), || {
+                format!("Renamed tag '{from}' to '{to}'\n")
+            })
+        }
+        ApiCommand::Export(args) => {
+            let collection = resolve_collection(client, args.collection.as_deref()).await?;
+            let bookmarks = client
+                .get_all_bookmarks(SearchParams {
+                    collection: Some(collection.unwrap_or(0)),
+                    ..Default::default()
+                })
+                .await?;
+            match args.format {
+                ExportFormat::Md => {
+                    let mut titles: HashMap<i64, String> = client
+                        .get_all_collections()
+                        .await?
+                        .into_iter()
+                        .map(|c| (c.id, c.title))
+                        .collect();
+                    titles.insert(UNSORTED_COLLECTION_ID, "Unsorted".to_string());
+                    titles.insert(TRASH_COLLECTION_ID, "Trash".to_string());
+                    Ok(export_markdown(&bookmarks, &titles))
+                }
+                ExportFormat::Csv => Ok(export_csv(&bookmarks)),
+                ExportFormat::Json => Ok(serde_json::to_string_pretty(&bookmarks)? + "\n"),
+            }
+        }
+    }
+}
+
+// Collection IDs are taken as they are; anything else is looked up by name
+async fn resolve_collection(client: &RaindropClient, arg: Option<&str>) -> Result<Option<i64>> {
+    match arg.map(str::trim) {
+        None => Ok(None),
+        Some(arg) => match arg.parse::<i64>() {
+            Ok(id) => Ok(Some(id)),
+            Err(_) => Ok(Some(client.find_collection(arg).await?.id)),
+        },
+    }
+}
+
+fn render<T: Serialize + ?Sized>(
+    output: OutputMode,
+    value: &T,
+    table: impl FnOnce() -> String,
+) -> Result<String> {
+    match output {
+        OutputMode::Table => Ok(table()),
+        OutputMode::Json => Ok(serde_json::to_string_pretty(value)? + "\n"),
+    }
+}
+
+fn bookmark_table(bookmarks: &[Bookmark]) -> String {
+    table(
+        &["ID", "TITLE", "TAGS", "LINK"],
+        bookmarks
+            .iter()
+            .map(|b| {
+                vec![
+                    b.id.to_string(),
+                    b.title.clone(),
+                    b.tags.join(","),
+                    b.link.clone(),
+                ]
+            })
+            .collect(),
+    )
+}
+
+/// Left-aligned columns under a header row, with long cells cut short.
+pub fn table(headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    let rows: Vec<Vec<String>> = rows
+        .into_iter()
+        .map(|row| row.iter().map(|cell| clip(cell)).collect())
+        .collect();
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    let header = headers.iter().map(|h| h.to_string()).collect::<Vec<_>>();
+    for row in std::iter::once(&header).chain(&rows) {
+        let mut line = String::new();
+        for (i, (cell, width)) in row.iter().zip(&widths).enumerate() {
+            if i + 1 == row.len() {
+                line.push_str(cell);
+            } else {
+                let _ = write!(line, "{cell:<width$}  ");
+            }
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn clip(cell: &str) -> String {
+    let cell = cell.replace(['\n', '\t'], " ");
+    if cell.chars().count() <= MAX_CELL_CHARS {
+        return cell;
+    }
+    let mut clipped: String = cell.chars().take(MAX_CELL_CHARS - 1).collect();
+    clipped.push('…');
+    clipped
+}
+
+/// Bookmarks as Markdown links under a heading per collection, in first-seen order.
+pub fn export_markdown(bookmarks: &[Bookmark], collection_titles: &HashMap<i64, String>) -> String {
+    let mut sections: Vec<(i64, Vec<&Bookmark>)> = vec![];
+    for bookmark in bookmarks {
+        let id = bookmark.collection.id;
+        match sections.iter_mut().find(|(section, _)| *section == id) {
+            Some((_, items)) => items.push(bookmark),
+            None => sections.push((id, vec![bookmark])),
+        }
+    }
+
+    let mut out = String::new();
+    for (id, items) in sections {
+        let title = collection_titles
+            .get(&id)
+            .cloned()
+            .unwrap_or_else(|| format!("Collection {id}"));
+        let _ = writeln!(out, "## {title}\n");
+        for bookmark in items {
+            let title = bookmark.title.replace('[', "\\[").replace(']', "\\]");
+            let _ = write!(out, "- [{title}]({})", bookmark.link);
+            for tag in &bookmark.tags {
+                let _ = write!(out, " `#{tag}`");
+            }
+            out.push('\n');
+            if let Some(note) = bookmark.note.as_deref().filter(|n| !n.trim().is_empty()) {
+                let _ = writeln!(out, "  > {}", note.trim().replace('\n', "\n  > "));
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Bookmarks as CSV with a header row, tags joined by commas.
+pub fn export_csv(bookmarks: &[Bookmark]) -> String {
+    let mut out = String::from("id,title,link,tags,collection,created,note\n");
+    for b in bookmarks {
+        let fields = [
+            b.id.to_string(),
+            b.title.clone(),
+            b.link.clone(),
+            b.tags.join(","),
+            b.collection.id.to_string(),
+            b.created.clone(),
+            b.note.clone().unwrap_or_default(),
+        ];
+        let line = fields
+            .iter()
+            .map(|f| csv_field(f))
+            .collect::<Vec<_>>()
+            .join(",");
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RaindropMcpError;
+    use pretty_assertions::assert_eq;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_json, method, path, query_param},
+    };
+
+    fn bookmark(id: i64, title: &str, collection: i64, tags: &[&str]) -> Bookmark {
+        serde_json::from_value(json!({
+            "_id": id,
+            "title": title,
+            "excerpt": "",
+            "note": "",
+            "type": "link",
+            "tags": tags,
+            "cover": "",
+            "link": format!("https://example.com/{id}"),
+            "domain": "example.com",
+            "created": "2024-01-01T00:00:00Z",
+            "lastUpdate": "2024-01-01T00:00:00Z",
+            "user": { "$id": 1 },
+            "collection": { "$id": collection },
+            "important": false,
+            "broken": false
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let cli = Cli::try_parse_from([
+            "raindrop-mcp-server",
+            "bookmarks",
+            "search",
+            "async",
+            "--tag",
+            "rust",
+            "--tag",
+            "tokio",
+            "-o",
+            "json",
+        ])
+        .unwrap();
+        assert_eq!(cli.output, OutputMode::Json);
+        let Some(Command::Api(ApiCommand::Bookmarks(BookmarksCommand::Search {
+            query,
+            tags,
+            limit,
+            ..
+        }))) = cli.command
+        else {
+            panic!("unexpected command: {:?}", cli.command);
+        };
+        assert_eq!(query.as_deref(), Some("async"));
+        assert_eq!(tags, ["rust", "tokio"]);
+        assert_eq!(limit, DEFAULT_SEARCH_LIMIT);
+
+        let cli = Cli::try_parse_from(["raindrop-mcp-server", "export", "--format", "md"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Api(ApiCommand::Export(ExportArgs {
+                format: ExportFormat::Md,
+                collection: None
+            })))
+        ));
+        let cli = Cli::try_parse_from(["raindrop-mcp-server", "serve"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Serve { http: None })));
+        assert!(Cli::try_parse_from(["raindrop-mcp-server", "tags", "rename", "a"]).is_err());
+    }
+
+    #[test]
+    fn test_table_and_exports() {
+        let long = "x".repeat(80);
+        assert_eq!(
+            table(
+                &["ID", "TITLE", "LINK"],
+                vec![
+                    vec!["1".into(), "Rust".into(), "https://rust-lang.org".into()],
+                    vec!["22".into(), long, "-".into()],
+                ],
+            ),
+            format!(
+                "ID  TITLE{}  LINK\n1   Rust{}  https://rust-lang.org\n22  {}…  -\n",
+                " ".repeat(55),
+                " ".repeat(56),
+                "x".repeat(59)
+            )
+        );
+
+        let mut bookmarks = vec![
+            bookmark(1, "Tokio [guide]", 10, &["rust", "async"]),
+            bookmark(2, "Notes, part 2", UNSORTED_COLLECTION_ID, &[]),
+            bookmark(3, "Serde", 10, &["rust"]),
+        ];
+        bookmarks[1].note = Some("read \"later\"".to_string());
+        let titles = HashMap::from([
+            (10, "Rust".to_string()),
+            (UNSORTED_COLLECTION_ID, "Unsorted".to_string()),
+        ]);
+        assert_eq!(
+            export_markdown(&bookmarks, &titles),
+            "## Rust\n\n\
+             - [Tokio \\[guide\\]](https://example.com/1) `#rust` `#async`\n\
+             - [Serde](https://example.com/3) `#rust`\n\n\
+             ## Unsorted\n\n\
+             - [Notes, part 2](https://example.com/2)\n  > read \"later\"\n\n"
+        );
+        assert_eq!(
+            export_csv(&bookmarks[..2]),
+            "id,title,link,tags,collection,created,note\n\
+             1,Tokio [guide],https://example.com/1,\"rust,async\",10,2024-01-01T00:00:00Z,\n\
+             2,\"Notes, part 2\",https://example.com/2,,-1,2024-01-01T00:00:00Z,\"read \"\"later\"\"\"\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_commands() {
+        let server = MockServer::start().await;
+        let client = RaindropClient::with_token(server.uri(), "test-token").unwrap();
+
+        Mock::given(method("GET"))
+            .and(path("/raindrops/0"))
+            .and(query_param("tag", "rust"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "result": true,
+                "items": [bookmark(1, "Tokio", 10, &["rust"])],
+                "count": 1
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/tags"))
+            .and(body_json(json!({"replace": "rust", "tag": "rustlang"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"result": true})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let search = ApiCommand::Bookmarks(BookmarksCommand::Search {
+            query: None,
+            tags: vec!["rust".to_string()],
+            collection: None,
+            limit: 10,
+        });
+        let output = execute(&client, &search, OutputMode::Json).await.unwrap();
+        let found: Vec<Bookmark> = serde_json::from_str(&output).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].title, "Tokio");
+
+        let rename = ApiCommand::Tags(TagsCommand::Rename {
+            from: "rust".to_string(),
+            to: "rustlang".to_string(),
+            collection: None,
+        });
+        assert_eq!(
+            execute(&client, &rename, OutputMode::Table).await.unwrap(),
+            "Renamed tag 'rust' to 'rustlang'\n"
+        );
+
+        // Errors keep their kind, so the binary can exit with a matching code
+        let read_only = client.clone().with_read_only(true);
+        let err = execute(&read_only, &rename, OutputMode::Table)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RaindropMcpError::ReadOnly(_)));
+        assert_eq!(err.exit_code(), 77);
+    }
+}
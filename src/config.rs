@@ -0,0 +1,334 @@
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::error::{RaindropMcpError, Result};
+
+/// Profile used when the config file defines none, built from environment variables alone.
+pub const DEFAULT_PROFILE: &str = "default";
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Seconds completion candidates are reused unless a profile sets `cache.completion_ttl_secs`.
+pub const DEFAULT_COMPLETION_TTL_SECS: u64 = 60;
+
+/// Contents of `config.toml`: named profiles, one per Raindrop account.
+///
+/// ```toml
+/// default_profile = "personal"
+///
+/// [profiles.personal]
+/// token_env = "RAINDROP_PERSONAL_TOKEN"
+/// default_collection = 12345
+///
+/// [profiles.work]
+/// token = "..."
+/// read_only = true
+/// timeout_secs = 30
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Profile used when neither `--profile` nor `RAINDROP_PROFILE` picks one
+    #[serde(default)]
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Settings for one Raindrop account.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// Raindrop access token
+    pub token: Option<String>,
+    /// Environment variable holding the token, to keep it out of the file
+    pub token_env: Option<String>,
+    /// API base URL, defaulting to `RAINDROP_BASE_URL` or the public API
+    pub base_url: Option<String>,
+    /// Collection new bookmarks go to when none is given, instead of Unsorted
+    pub default_collection: Option<i64>,
+    /// Rejects every change to the account
+    pub read_only: bool,
+    /// Limit for a whole Raindrop API request
+    pub timeout_secs: Option<u64>,
+    /// Limit for connecting to the Raindrop API
+    pub connect_timeout_secs: Option<u64>,
+    pub cache: CacheSettings,
+    /// Authenticate through an OAuth app instead of a static token
+    pub oauth: Option<OAuthSettings>,
+}
+
+/// A registered Raindrop app, for profiles authorized with the `auth` command.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OAuthSettings {
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    /// Environment variable holding the client secret, to keep it out of the file
+    #[serde(default)]
+    pub client_secret_env: Option<String>,
+    /// Loopback URL registered with the app (default: `http://localhost:8976/callback`)
+    #[serde(default)]
+    pub redirect_uri: Option<String>,
+    /// Where tokens are kept (default: `oauth/<profile>.json` in the user's data directory)
+    #[serde(default)]
+    pub token_file: Option<PathBuf>,
+    #[serde(default)]
+    pub authorize_url: Option<String>,
+    #[serde(default)]
+    pub token_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheSettings {
+    /// Keep fetched web pages on disk to revalidate them with ETags
+    pub pages: bool,
+    /// Where fetched pages are kept, instead of the user's data directory
+    pub page_dir: Option<PathBuf>,
+    /// How long collections, tags and bookmarks are reused for argument completion
+    pub completion_ttl_secs: u64,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            pages: true,
+            page_dir: None,
+            completion_ttl_secs: DEFAULT_COMPLETION_TTL_SECS,
+        }
+    }
+}
+
+impl Config {
+    /// Reads `path`, else `RAINDROP_CONFIG`, else `config.toml` in the user's config directory.
+    ///
+    /// Only an explicitly named file has to exist; otherwise a missing file means no profiles.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, explicit) = match path {
+            Some(path) => (Some(path.to_path_buf()), true),
+            None => match env::var("RAINDROP_CONFIG") {
+                Ok(path) if !path.is_empty() => (Some(PathBuf::from(path)), true),
+                _ => (
+                    default_config_dir().map(|dir| dir.join(CONFIG_FILE_NAME)),
+                    false,
+                ),
+            },
+        };
+        match path {
+            Some(path) if explicit || path.exists() => Self::read(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).map_err(|e| {
+            RaindropMcpError::Config(format!("Cannot read {}: {e}", path.display()))
+        })?;
+        Self::parse(&text).map_err(|e| RaindropMcpError::Config(format!("{}: {e}", path.display())))
+    }
+
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        let config: Self = toml::from_str(text).map_err(|e| e.to_string())?;
+        if let Some(name) = &config.default_profile {
+            if !config.profiles.contains_key(name) {
+                return Err(format!("default_profile '{name}' is not defined"));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Every profile by name; without a config file, a single one from the environment.
+    pub fn profiles(&self) -> BTreeMap<String, Profile> {
+        if self.profiles.is_empty() {
+            BTreeMap::from([(DEFAULT_PROFILE.to_string(), Profile::default())])
+        } else {
+            self.profiles.clone()
+        }
+    }
+
+    /// Name of the profile to use: `selected`, the configured default, or the only choice.
+    pub fn primary_profile(&self, selected: Option<&str>) -> Result<String> {
+        let profiles = self.profiles();
+        let name = match selected.or(self.default_profile.as_deref()) {
+            Some(name) => name.to_string(),
+            None if profiles.contains_key(DEFAULT_PROFILE) => DEFAULT_PROFILE.to_string(),
+            None if profiles.len() == 1 => profiles.keys().next().cloned().unwrap_or_default(),
+            None => {
+                return Err(RaindropMcpError::Config(format!(
+                    "Several profiles are defined ({}); pick one with --profile or default_profile",
+                    profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+                )));
+            }
+        };
+        if profiles.contains_key(&name) {
+            Ok(name)
+        } else {
+            Err(RaindropMcpError::Config(format!(
+                "Unknown profile '{name}'; defined profiles: {}",
+                profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            )))
+        }
+    }
+}
+
+impl Profile {
+    /// The token from the file, the named variable, or `RAINDROP_ACCESS_TOKEN`.
+    pub fn access_token(&self) -> Result<String> {
+        if let Some(token) = self.token.as_ref().filter(|t| !t.is_empty()) {
+            return Ok(token.clone());
+        }
+        let var = self.token_env.as_deref().unwrap_or("RAINDROP_ACCESS_TOKEN");
+        env::var(var).map_err(|_| RaindropMcpError::EnvironmentVariable(format!(
+            "{var} environment variable is required. Please check your .env file, environment settings or config file."
+        )))
+    }
+
+    pub fn base_url(&self) -> Option<String> {
+        self.base_url
+            .clone()
+            .or_else(|| env::var("RAINDROP_BASE_URL").ok())
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_secs.map(Duration::from_secs)
+    }
+
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout_secs.map(Duration::from_secs)
+    }
+
+    /// OAuth app to authenticate with, unless a static token is configured.
+    pub fn oauth(&self) -> Option<&OAuthSettings> {
+        if self.token.is_some() || self.token_env.is_some() {
+            return None;
+        }
+        self.oauth.as_ref()
+    }
+}
+
+impl OAuthSettings {
+    pub fn client_secret(&self) -> Result<String> {
+        if let Some(secret) = self.client_secret.as_ref().filter(|s| !s.is_empty()) {
+            return Ok(secret.clone());
+        }
+        let var = self.client_secret_env.as_deref().ok_or_else(|| {
+            RaindropMcpError::Config("oauth needs client_secret or client_secret_env".to_string())
+        })?;
+        env::var(var).map_err(|_| {
+            RaindropMcpError::EnvironmentVariable(format!("{var} environment variable is required"))
+        })
+    }
+}
+
+/// Per-user data directory for this server (`$XDG_DATA_HOME`, `~/.local/share`, or `%APPDATA%`).
+pub fn default_data_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_DATA_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(base.join("raindrop-mcp-server"))
+}
+
+/// Per-user configuration directory for this server (`$XDG_CONFIG_HOME`, `~/.config`, or `%APPDATA%`).
+pub fn default_config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("raindrop-mcp-server"))
+}
+
+/// File for `profile` beside the default profile's file at `path`, e.g. `journal-work.json`
+/// next to `journal.json`. The default profile keeps `path` itself.
+pub fn profile_path(path: &Path, profile: &str) -> PathBuf {
+    if profile == DEFAULT_PROFILE {
+        return path.to_path_buf();
+    }
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}-{profile}.{}", extension.to_string_lossy()),
+        None => format!("{stem}-{profile}"),
+    };
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_profiles() {
+        let config = Config::parse(
+            r#"
+default_profile = "work"
+
+[profiles.personal]
+token = "personal-token"
+default_collection = 42
+
+[profiles.work]
+token = "work-token"
+base_url = "http://localhost:9000"
+read_only = true
+timeout_secs = 5
+
+[profiles.work.cache]
+pages = false
+completion_ttl_secs = 10
+"#,
+        )
+        .unwrap();
+
+        let work = &config.profiles["work"];
+        assert!(work.read_only);
+        assert_eq!(work.timeout(), Some(Duration::from_secs(5)));
+        assert_eq!(work.access_token().unwrap(), "work-token");
+        assert_eq!(
+            work.cache,
+            CacheSettings {
+                pages: false,
+                page_dir: None,
+                completion_ttl_secs: 10,
+            }
+        );
+        assert_eq!(config.profiles["personal"].cache, CacheSettings::default());
+        assert_eq!(config.profiles["personal"].default_collection, Some(42));
+
+        assert_eq!(config.primary_profile(None).unwrap(), "work");
+        assert_eq!(
+            config.primary_profile(Some("personal")).unwrap(),
+            "personal"
+        );
+        assert!(config.primary_profile(Some("other")).is_err());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(
+            Config::parse("default_profile = \"missing\"")
+                .unwrap_err()
+                .contains("not defined")
+        );
+        assert!(Config::parse("[profiles.a]\ntokn = \"x\"").is_err());
+
+        // Without a file there is a single profile driven by environment variables
+        let config = Config::default();
+        assert_eq!(config.primary_profile(None).unwrap(), DEFAULT_PROFILE);
+
+        let config = Config::parse("[profiles.a]\n[profiles.b]\n").unwrap();
+        assert!(config.primary_profile(None).is_err());
+    }
+}
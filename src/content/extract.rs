@@ -0,0 +1,232 @@
+use ego_tree::{NodeId, NodeRef};
+use scraper::{ElementRef, Html, Node, Selector};
+use std::collections::HashMap;
+
+use super::{
+    ContentFormat, ConvertedContent, approx_tokens, document_title, render_with, truncate_to_tokens,
+};
+
+// Class/id fragments that suggest the main article body
+const POSITIVE_HINTS: &[&str] = &[
+    "article", "body", "content", "entry", "main", "page", "post", "text", "blog", "story",
+];
+
+// Class/id fragments that suggest page chrome rather than content
+const NEGATIVE_HINTS: &[&str] = &[
+    "comment",
+    "meta",
+    "footer",
+    "footnote",
+    "sidebar",
+    "sponsor",
+    "share",
+    "social",
+    "nav",
+    "menu",
+    "related",
+    "promo",
+    "header",
+    "widget",
+    "banner",
+    "cookie",
+    "subscribe",
+    "advert",
+];
+
+const BOILERPLATE_ELEMENTS: &[&str] = &["nav", "header", "footer", "aside"];
+
+// Paragraphs shorter than this do not contribute to scoring
+const MIN_PARAGRAPH_CHARS: usize = 25;
+
+/// Extracts the main article of a page, readability-style, and fits it into `max_tokens`.
+pub fn extract_article(html: &str, format: ContentFormat, max_tokens: usize) -> ConvertedContent {
+    let document = Html::parse_document(html);
+    let title = document_title(&document);
+
+    let root = main_content(&document).unwrap_or_else(|| document.tree.root());
+    let rendered = render_with(root, format, true);
+    let (content, truncated) = truncate_to_tokens(&rendered, max_tokens);
+
+    ConvertedContent {
+        title,
+        format,
+        approx_tokens: approx_tokens(&content),
+        content,
+        truncated,
+    }
+}
+
+/// Short extractive summary: the leading sentences of the article, up to `max_chars`.
+pub fn lead_summary(article_text: &str, max_chars: usize) -> String {
+    let mut summary = String::new();
+    for paragraph in article_text.split("\n\n") {
+        let paragraph = paragraph.trim();
+        // Skip headings, list items and other short fragments
+        if paragraph.chars().count() < MIN_PARAGRAPH_CHARS * 2 {
+            continue;
+        }
+        for sentence in paragraph.split_inclusive(['.', '!', '?']) {
+            let sentence = sentence.trim();
+            if summary.chars().count() + sentence.chars().count() + 1 > max_chars {
+                if summary.is_empty() {
+                    let cut: String = sentence.chars().take(max_chars.saturating_sub(1)).collect();
+                    return format!("{}…", cut.trim_end());
+                }
+                return summary;
+            }
+            if !summary.is_empty() {
+                summary.push(' ');
+            }
+            summary.push_str(sentence);
+        }
+    }
+    summary
+}
+
+// Picks the element with the highest readability score
+fn main_content(document: &Html) -> Option<NodeRef<'_, Node>> {
+    let paragraphs = Selector::parse("p, pre, td, blockquote").ok()?;
+    let mut scores: HashMap<NodeId, f64> = HashMap::new();
+
+    for paragraph in document.select(&paragraphs) {
+        let text_len = paragraph.text().map(str::len).sum::<usize>();
+        if text_len < MIN_PARAGRAPH_CHARS {
+            continue;
+        }
+        let commas = paragraph
+            .text()
+            .map(|t| t.matches(',').count())
+            .sum::<usize>();
+        let score = 1.0 + commas as f64 + (text_len as f64 / 100.0).min(3.0);
+
+        if let Some(parent) = paragraph.parent().and_then(ElementRef::wrap) {
+            *scores
+                .entry(parent.id())
+                .or_insert_with(|| class_weight(parent)) += score;
+
+            if let Some(grandparent) = parent.parent().and_then(ElementRef::wrap) {
+                *scores
+                    .entry(grandparent.id())
+                    .or_insert_with(|| class_weight(grandparent)) += score / 2.0;
+            }
+        }
+    }
+
+    let best = scores
+        .into_iter()
+        .filter_map(|(id, score)| {
+            let element = ElementRef::wrap(document.tree.get(id)?)?;
+            Some((element, score * (1.0 - link_density(element))))
+        })
+        .filter(|(_, score)| *score > 0.0)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(element, _)| *element);
+
+    best.or_else(|| {
+        let fallback = Selector::parse("article, main, body").ok()?;
+        document.select(&fallback).next().map(|element| *element)
+    })
+}
+
+fn class_weight(element: ElementRef<'_>) -> f64 {
+    let value = element.value();
+    let hints = format!(
+        "{} {}",
+        value.attr("class").unwrap_or_default(),
+        value.id().unwrap_or_default()
+    )
+    .to_lowercase();
+
+    let mut weight = 0.0;
+    if POSITIVE_HINTS.iter().any(|hint| hints.contains(hint)) {
+        weight += 25.0;
+    }
+    if NEGATIVE_HINTS.iter().any(|hint| hints.contains(hint)) {
+        weight -= 25.0;
+    }
+    if matches!(value.name(), "article" | "main") {
+        weight += 10.0;
+    }
+    weight
+}
+
+fn link_density(element: ElementRef<'_>) -> f64 {
+    let total = element.text().map(str::len).sum::<usize>();
+    if total == 0 {
+        return 0.0;
+    }
+    let links = Selector::parse("a").expect("valid selector");
+    let linked = element
+        .select(&links)
+        .flat_map(|link| link.text())
+        .map(str::len)
+        .sum::<usize>();
+    linked as f64 / total as f64
+}
+
+/// Whether an element is page chrome that should be dropped from extracted articles.
+pub(crate) fn is_boilerplate(element: &scraper::node::Element) -> bool {
+    if BOILERPLATE_ELEMENTS.contains(&element.name()) {
+        return true;
+    }
+    let hints = format!(
+        "{} {}",
+        element.attr("class").unwrap_or_default(),
+        element.id().unwrap_or_default()
+    )
+    .to_lowercase();
+    NEGATIVE_HINTS.iter().any(|hint| hints.contains(hint))
+        && !POSITIVE_HINTS.iter().any(|hint| hints.contains(hint))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const ARTICLE: &str = r#"<html><head><title>Rust 2024</title></head><body>
+        <nav class="site-nav"><a href="/">Home</a> <a href="/blog">Blog</a></nav>
+        <div class="sidebar"><p>Subscribe to our newsletter, it is great, really, truly.</p></div>
+        <div id="post-content" class="entry">
+          <h2>What changed</h2>
+          <p>The 2024 edition stabilizes several long-awaited features, including async closures,
+             new prelude additions, and changes to temporary lifetimes.</p>
+          <p>Most crates can migrate automatically with cargo fix, although some manual work,
+             especially around macros, may still be needed.</p>
+          <div class="share-buttons"><a href="https://x.com">Share on X</a></div>
+        </div>
+        <footer><p>Copyright 2024, Example Corp, all rights reserved.</p></footer>
+        </body></html>"#;
+
+    #[test]
+    fn test_extracts_main_content_only() {
+        let article = extract_article(ARTICLE, ContentFormat::Markdown, 1000);
+        assert_eq!(article.title.as_deref(), Some("Rust 2024"));
+        assert!(article.content.starts_with("## What changed"));
+        assert!(article.content.contains("async closures"));
+        assert!(!article.content.contains("newsletter"));
+        assert!(!article.content.contains("Share on X"));
+        assert!(!article.content.contains("Copyright"));
+        assert!(!article.content.contains("Home"));
+    }
+
+    #[test]
+    fn test_falls_back_to_body() {
+        let article = extract_article(
+            "<html><body><span>Tiny page</span></body></html>",
+            ContentFormat::Text,
+            100,
+        );
+        assert_eq!(article.content, "Tiny page");
+    }
+
+    #[test]
+    fn test_lead_summary() {
+        let text = "Heading\n\nThe first sentence is here. The second one follows it. \
+                    A third sentence would be too long for the limit.";
+        assert_eq!(
+            lead_summary(text, 60),
+            "The first sentence is here. The second one follows it."
+        );
+    }
+}
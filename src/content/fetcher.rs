@@ -0,0 +1,570 @@
+use reqwest::{
+    Client, Response, StatusCode,
+    dns::{Addrs, Name, Resolve, Resolving},
+    header, redirect,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tracing::{debug, warn};
+use url::{Host, Url};
+
+use crate::{
+    config::default_data_dir,
+    error::{RaindropMcpError, Result},
+};
+
+/// Largest page body that will be downloaded.
+pub const MAX_PAGE_BYTES: usize = 5 * 1024 * 1024;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+
+const PAGE_CACHE_DIR: &str = "page-cache";
+
+const MAX_REDIRECTS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedPage {
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>,
+    fetched_at: String,
+    html: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct FetchedPage {
+    pub url: String,
+    pub html: String,
+    /// True when the server answered 304 and the local copy was used
+    pub from_cache: bool,
+}
+
+/// Downloads web pages without Raindrop credentials, caching them by URL and ETag.
+#[derive(Debug, Clone)]
+pub struct PageFetcher {
+    client: Client,
+    cache_dir: Option<PathBuf>,
+    /// Refuses hosts on loopback, private and link-local networks
+    public_only: bool,
+}
+
+impl PageFetcher {
+    pub fn new(cache_dir: Option<PathBuf>) -> Result<Self> {
+        Ok(Self {
+            client: Self::client(false)?,
+            cache_dir,
+            public_only: false,
+        })
+    }
+
+    /// Refuses pages on this machine or its networks, for servers whose callers are remote.
+    ///
+    /// Hosts are checked before each request, and again when connecting and redirecting so
+    /// a name cannot resolve to a private address only once it is fetched.
+    pub fn public_only(self) -> Result<Self> {
+        Ok(Self {
+            client: Self::client(true)?,
+            public_only: true,
+            ..self
+        })
+    }
+
+    fn client(public_only: bool) -> Result<Client> {
+        let mut builder = Client::builder()
+            .user_agent(concat!(
+                env!("CARGO_PKG_NAME"),
+                "/",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .timeout(FETCH_TIMEOUT);
+        if public_only {
+            builder =
+                builder
+                    .dns_resolver(Arc::new(PublicResolver))
+                    .redirect(redirect::Policy::custom(|attempt| {
+                        if attempt.previous().len() >= MAX_REDIRECTS {
+                            attempt.error("too many redirects")
+                        } else if literal_ip(attempt.url()).is_some_and(|ip| !is_public(ip)) {
+                            attempt.error("redirect to a private address")
+                        } else {
+                            attempt.follow()
+                        }
+                    }));
+        }
+        builder.build().map_err(RaindropMcpError::HttpRequest)
+    }
+
+    /// Directory fetched pages are cached in, if any.
+    pub fn cache_dir(&self) -> Option<&Path> {
+        self.cache_dir.as_deref()
+    }
+
+    /// Caches pages under the user's data directory, or not at all if there is none.
+    pub fn with_default_cache() -> Result<Self> {
+        Self::new(default_data_dir().map(|dir| dir.join(PAGE_CACHE_DIR)))
+    }
+
+    pub async fn fetch(&self, url: &str) -> Result<FetchedPage> {
+        let parsed = url::Url::parse(url)
+            .map_err(|e| RaindropMcpError::InvalidParameter(format!("Invalid URL '{url}': {e}")))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(RaindropMcpError::InvalidParameter(format!(
+                "Only http and https pages can be fetched, got '{url}'"
+            )));
+        }
+        self.ensure_allowed(&parsed).await?;
+
+        let cached = self.load(url);
+        let mut request = self.client.get(parsed);
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        debug!("Fetching page: {}", url);
+        let response = request.send().await.map_err(|e| {
+            if e.is_timeout() {
+                RaindropMcpError::Timeout(format!("Timed out fetching {url}"))
+            } else {
+                RaindropMcpError::HttpRequest(e)
+            }
+        })?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_MODIFIED {
+            if let Some(cached) = cached {
+                debug!("Page not modified, using cached copy: {}", url);
+                return Ok(FetchedPage {
+                    url: url.to_string(),
+                    html: cached.html,
+                    from_cache: true,
+                });
+            }
+        }
+        if !status.is_success() {
+            return Err(RaindropMcpError::ServiceUnavailable(format!(
+                "{url} returned HTTP {status}"
+            )));
+        }
+
+        if let Some(content_type) = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+        {
+            let content_type = content_type.to_ascii_lowercase();
+            if !(content_type.contains("html") || content_type.starts_with("text/")) {
+                return Err(RaindropMcpError::InvalidParameter(format!(
+                    "{url} is not a web page (content type: {content_type})"
+                )));
+            }
+        }
+        if response
+            .content_length()
+            .is_some_and(|len| len > MAX_PAGE_BYTES as u64)
+        {
+            return Err(RaindropMcpError::InvalidParameter(format!(
+                "{url} is larger than {MAX_PAGE_BYTES} bytes"
+            )));
+        }
+
+        let header_value = |name: header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header_value(header::ETAG);
+        let last_modified = header_value(header::LAST_MODIFIED);
+
+        // A cut-off page must not be cached, where its ETag would keep it forever
+        let (bytes, truncated) = read_limited(response, MAX_PAGE_BYTES).await?;
+        if truncated {
+            return Err(RaindropMcpError::InvalidParameter(format!(
+                "{url} is larger than {MAX_PAGE_BYTES} bytes"
+            )));
+        }
+        let html = String::from_utf8_lossy(&bytes).into_owned();
+
+        // Without validators a cached copy could never be revalidated
+        if etag.is_some() || last_modified.is_some() {
+            self.store(&CachedPage {
+                url: url.to_string(),
+                etag,
+                last_modified,
+                fetched_at: chrono::Utc::now().to_rfc3339(),
+                html: html.clone(),
+            });
+        }
+
+        Ok(FetchedPage {
+            url: url.to_string(),
+            html,
+            from_cache: false,
+        })
+    }
+
+    /// Fetches a small JSON document, such as an oEmbed response. Not cached.
+    pub async fn fetch_json(&self, url: &str) -> Result<serde_json::Value> {
+        debug!("Fetching JSON: {}", url);
+        let parsed = Url::parse(url)
+            .map_err(|e| RaindropMcpError::InvalidParameter(format!("Invalid URL '{url}': {e}")))?;
+        self.ensure_allowed(&parsed).await?;
+        let response = self
+            .client
+            .get(parsed)
+            .header(header::ACCEPT, "application/json")
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(RaindropMcpError::ServiceUnavailable(format!(
+                "{url} returned HTTP {status}"
+            )));
+        }
+        let (bytes, truncated) = read_limited(response, MAX_PAGE_BYTES).await?;
+        if truncated {
+            return Err(RaindropMcpError::InvalidParameter(format!(
+                "{url} is larger than {MAX_PAGE_BYTES} bytes"
+            )));
+        }
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    // Names a private host up front, rather than failing obscurely when connecting
+    async fn ensure_allowed(&self, url: &Url) -> Result<()> {
+        if !self.public_only {
+            return Ok(());
+        }
+        let refused = || {
+            RaindropMcpError::InvalidParameter(format!(
+                "{url} is on a private network and cannot be fetched by this server"
+            ))
+        };
+        let addrs: Vec<IpAddr> = match (literal_ip(url), url.host_str()) {
+            (Some(ip), _) => vec![ip],
+            (None, Some(host)) => tokio::net::lookup_host((host, 0))
+                .await?
+                .map(|addr| addr.ip())
+                .collect(),
+            (None, None) => return Err(refused()),
+        };
+        if addrs.is_empty() || addrs.into_iter().any(|ip| !is_public(ip)) {
+            return Err(refused());
+        }
+        Ok(())
+    }
+
+    fn cache_path(&self, url: &str) -> Option<PathBuf> {
+        self.cache_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{:016x}.json", fnv1a(url.as_bytes()))))
+    }
+
+    fn load(&self, url: &str) -> Option<CachedPage> {
+        let text = fs::read_to_string(self.cache_path(url)?).ok()?;
+        serde_json::from_str::<CachedPage>(&text)
+            .ok()
+            // Guard against hash collisions
+            .filter(|page| page.url == url)
+    }
+
+    fn store(&self, page: &CachedPage) {
+        let Some(path) = self.cache_path(&page.url) else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map(fs::create_dir_all)
+            .transpose()
+            .and_then(|_| {
+                let data = serde_json::to_vec(page).map_err(std::io::Error::other)?;
+                fs::write(&path, data)
+            });
+        if let Err(e) = result {
+            warn!("Failed to cache page {}: {}", page.url, e);
+        }
+    }
+}
+
+// Reads at most `limit` bytes of the body, returning whether any of it was left unread
+async fn read_limited(mut response: Response, limit: usize) -> Result<(Vec<u8>, bool)> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        let room = limit - body.len();
+        if chunk.len() > room {
+            body.extend_from_slice(&chunk[..room]);
+            return Ok((body, true));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok((body, false))
+}
+
+// Resolves names to their public addresses only, failing when there are none
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn literal_ip(url: &Url) -> Option<IpAddr> {
+    match url.host()? {
+        Host::Ipv4(ip) => Some(IpAddr::V4(ip)),
+        Host::Ipv6(ip) => Some(IpAddr::V6(ip)),
+        Host::Domain(_) => None,
+    }
+}
+
+/// Whether `ip` is routable on the internet, rather than on this machine or a local network.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public_v4(v4),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        // Carrier-grade NAT, 100.64.0.0/10
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments, 192.0.0.0/24
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking, 198.18.0.0/15
+        || (a == 198 && (18..20).contains(&b))
+        // Reserved, 240.0.0.0/4
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local, fc00::/7
+        || (first & 0xfe00) == 0xfc00
+        // Link-local, fe80::/10
+        || (first & 0xffc0) == 0xfe80
+        // NAT64, 64:ff9b::/96, reaches IPv4 addresses that may be private
+        || ip.segments()[..6] == [0x64, 0xff9b, 0, 0, 0, 0])
+}
+
+// Stable across Rust releases, unlike the std hashers
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{header as header_eq, method, path},
+    };
+
+    fn temp_cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "raindrop-page-cache-test-{}-{name}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_revalidates_with_etag() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/article"))
+            .and(header_eq("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/article"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/html; charset=utf-8")
+                    .insert_header("etag", "\"v1\"")
+                    .set_body_string("<p>Hello</p>"),
+            )
+            .mount(&server)
+            .await;
+
+        let fetcher = PageFetcher::new(Some(temp_cache_dir("etag"))).unwrap();
+        let url = format!("{}/article", server.uri());
+
+        let first = fetcher.fetch(&url).await.unwrap();
+        assert!(!first.from_cache);
+        assert_eq!(first.html, "<p>Hello</p>");
+
+        let second = fetcher.fetch(&url).await.unwrap();
+        assert!(second.from_cache);
+        assert_eq!(second.html, "<p>Hello</p>");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_errors_and_non_html() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/file.pdf"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "application/pdf")
+                    .set_body_bytes(b"%PDF-1.7".to_vec()),
+            )
+            .mount(&server)
+            .await;
+
+        let fetcher = PageFetcher::new(None).unwrap();
+        let missing = fetcher
+            .fetch(&format!("{}/missing", server.uri()))
+            .await
+            .unwrap_err();
+        assert!(matches!(missing, RaindropMcpError::ServiceUnavailable(_)));
+
+        let pdf = fetcher
+            .fetch(&format!("{}/file.pdf", server.uri()))
+            .await
+            .unwrap_err();
+        assert!(matches!(pdf, RaindropMcpError::InvalidParameter(_)));
+
+        let scheme = fetcher.fetch("file:///etc/passwd").await.unwrap_err();
+        assert!(matches!(scheme, RaindropMcpError::InvalidParameter(_)));
+    }
+
+    #[tokio::test]
+    async fn test_stops_reading_oversized_bodies() {
+        // A body that never ends, without a length to reject it by up front
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            use tokio::io::AsyncWriteExt;
+            let chunk: Arc<str> =
+                format!("{:x}\r\n{}\r\n", 64 * 1024, "a".repeat(64 * 1024)).into();
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let chunk = chunk.clone();
+                tokio::spawn(async move {
+                    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\
+                                ETag: \"v1\"\r\nTransfer-Encoding: chunked\r\n\r\n";
+                    if stream.write_all(head.as_bytes()).await.is_err() {
+                        return;
+                    }
+                    while stream.write_all(chunk.as_bytes()).await.is_ok() {}
+                });
+            }
+        });
+
+        let cache_dir = temp_cache_dir("endless");
+        let fetcher = PageFetcher::new(Some(cache_dir.clone())).unwrap();
+        let url = format!("http://{address}/endless");
+        let limited = Duration::from_secs(30);
+
+        let page = tokio::time::timeout(limited, fetcher.fetch(&url))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(page.to_string().contains("larger than"), "{page}");
+        // Nothing was cached under the page's ETag
+        assert!(!cache_dir.exists());
+
+        let json = tokio::time::timeout(limited, fetcher.fetch_json(&url))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(json.to_string().contains("larger than"), "{json}");
+    }
+
+    #[tokio::test]
+    async fn test_public_only_refuses_private_hosts() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/html")
+                    .set_body_string("<p>internal</p>"),
+            )
+            .mount(&server)
+            .await;
+        let port = server.address().port();
+
+        let fetcher = PageFetcher::new(None).unwrap().public_only().unwrap();
+        for url in [
+            format!("http://127.0.0.1:{port}/admin"),
+            format!("http://localhost:{port}/admin"),
+            format!("http://[::ffff:127.0.0.1]:{port}/admin"),
+            "http://169.254.169.254/latest/meta-data/".to_string(),
+            "http://10.0.0.1/".to_string(),
+        ] {
+            let err = fetcher.fetch(&url).await.unwrap_err();
+            assert!(err.to_string().contains("private network"), "{url}: {err}");
+            let err = fetcher.fetch_json(&url).await.unwrap_err();
+            assert!(err.to_string().contains("private network"), "{url}: {err}");
+        }
+        assert!(server.received_requests().await.unwrap().is_empty());
+
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in ["93.184.216.34", "1.1.1.1", "2606:4700:4700::1111"] {
+            assert!(is_public(ip.parse().unwrap()), "{ip}");
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use scraper::{Html, Selector};
+use url::Url;
+
+use super::{collapse_whitespace, document_title};
+use crate::raindrop::types::{BookmarkType, Media, MediaType, ParsedUrl};
+
+/// Metadata read from a page's OpenGraph, Twitter card and standard tags.
+#[derive(Debug, Clone, Default)]
+pub struct PageMetadata {
+    pub parsed: ParsedUrl,
+    /// oEmbed JSON endpoint advertised by the page, if any
+    pub oembed_url: Option<Url>,
+}
+
+/// Parses link-preview metadata from a page, resolving relative URLs against `page_url`.
+pub fn parse_metadata(html: &str, page_url: &Url) -> PageMetadata {
+    let document = Html::parse_document(html);
+    let meta = |keys: &[&str]| keys.iter().find_map(|key| meta_content(&document, key));
+    let resolve = |href: String| page_url.join(href.trim()).ok().map(String::from);
+
+    let title = meta(&["og:title", "twitter:title"]).or_else(|| document_title(&document));
+    let excerpt = meta(&["og:description", "twitter:description", "description"]);
+    let cover = meta(&[
+        "og:image",
+        "og:image:url",
+        "twitter:image",
+        "twitter:image:src",
+    ])
+    .or_else(|| link_href(&document, "image_src"))
+    .and_then(resolve);
+    let video = meta(&["og:video", "og:video:url", "og:video:secure_url"]).and_then(resolve);
+
+    let og_type = meta(&["og:type"]).unwrap_or_default().to_lowercase();
+    let card = meta(&["twitter:card"]).unwrap_or_default().to_lowercase();
+    let bookmark_type = if og_type.starts_with("video") || card == "player" || video.is_some() {
+        Some(BookmarkType::Video)
+    } else if og_type.starts_with("music") {
+        Some(BookmarkType::Audio)
+    } else if og_type == "article" {
+        Some(BookmarkType::Article)
+    } else if card == "photo" {
+        Some(BookmarkType::Image)
+    } else {
+        None
+    };
+
+    let mut media = vec![];
+    if let Some(cover) = &cover {
+        media.push(Media {
+            link: cover.clone(),
+            media_type: MediaType::Image,
+            width: meta(&["og:image:width"]).and_then(|w| w.parse().ok()),
+            height: meta(&["og:image:height"]).and_then(|h| h.parse().ok()),
+        });
+    }
+    if let Some(video) = video {
+        media.push(Media {
+            link: video,
+            media_type: MediaType::Video,
+            width: meta(&["og:video:width"]).and_then(|w| w.parse().ok()),
+            height: meta(&["og:video:height"]).and_then(|h| h.parse().ok()),
+        });
+    }
+
+    let oembed_url = Selector::parse(r#"link[type="application/json+oembed"]"#)
+        .ok()
+        .and_then(|selector| {
+            document
+                .select(&selector)
+                .find_map(|link| link.value().attr("href"))
+                .and_then(|href| page_url.join(href).ok())
+        });
+
+    PageMetadata {
+        parsed: ParsedUrl {
+            title,
+            excerpt,
+            cover,
+            media,
+            bookmark_type,
+        },
+        oembed_url,
+    }
+}
+
+/// Fills gaps in `parsed` from an oEmbed JSON response.
+pub fn apply_oembed(parsed: &mut ParsedUrl, oembed: &serde_json::Value) {
+    let text = |key: &str| {
+        oembed
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(clean)
+            .filter(|v| !v.is_empty())
+    };
+
+    let thumbnail = text("thumbnail_url");
+    let bookmark_type = match text("type").as_deref() {
+        Some("video") => Some(BookmarkType::Video),
+        Some("photo") => Some(BookmarkType::Image),
+        _ => None,
+    };
+    if parsed.media.is_empty() {
+        if let Some(thumbnail) = &thumbnail {
+            parsed.media.push(Media {
+                link: thumbnail.clone(),
+                media_type: MediaType::Image,
+                width: oembed
+                    .get("thumbnail_width")
+                    .and_then(|v| v.as_i64())
+                    .map(|w| w as i32),
+                height: oembed
+                    .get("thumbnail_height")
+                    .and_then(|v| v.as_i64())
+                    .map(|h| h as i32),
+            });
+        }
+    }
+
+    parsed.merge(ParsedUrl {
+        title: text("title"),
+        excerpt: text("description"),
+        cover: thumbnail,
+        media: vec![],
+        bookmark_type,
+    });
+}
+
+// Matches both `property` (OpenGraph) and `name` (Twitter, standard) attributes
+fn meta_content(document: &Html, key: &str) -> Option<String> {
+    let selector =
+        Selector::parse(&format!(r#"meta[property="{key}"], meta[name="{key}"]"#)).ok()?;
+    document
+        .select(&selector)
+        .filter_map(|element| element.value().attr("content"))
+        .map(clean)
+        .find(|content| !content.is_empty())
+}
+
+fn clean(text: &str) -> String {
+    collapse_whitespace(text).trim().to_string()
+}
+
+fn link_href(document: &Html, rel: &str) -> Option<String> {
+    let selector = Selector::parse(&format!(r#"link[rel="{rel}"]"#)).ok()?;
+    document
+        .select(&selector)
+        .find_map(|element| element.value().attr("href"))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parses_opengraph_and_twitter_tags() {
+        let html = r#"<html><head>
+            <title>Fallback title</title>
+            <meta property="og:title" content="Async closures  in Rust">
+            <meta name="twitter:description" content="What the 2024 edition brings.">
+            <meta property="og:type" content="article">
+            <meta property="og:image" content="/img/cover.png">
+            <meta property="og:image:width" content="1200">
+            <link rel="alternate" type="application/json+oembed" href="/oembed?format=json">
+            </head><body></body></html>"#;
+        let page_url = Url::parse("https://blog.example.com/posts/async").unwrap();
+
+        let metadata = parse_metadata(html, &page_url);
+        let parsed = metadata.parsed;
+        assert_eq!(parsed.title.as_deref(), Some("Async closures in Rust"));
+        assert_eq!(
+            parsed.excerpt.as_deref(),
+            Some("What the 2024 edition brings.")
+        );
+        assert_eq!(
+            parsed.cover.as_deref(),
+            Some("https://blog.example.com/img/cover.png")
+        );
+        assert_eq!(parsed.media[0].width, Some(1200));
+        assert!(matches!(parsed.bookmark_type, Some(BookmarkType::Article)));
+        assert_eq!(
+            metadata.oembed_url.map(String::from).as_deref(),
+            Some("https://blog.example.com/oembed?format=json")
+        );
+    }
+
+    #[test]
+    fn test_oembed_fills_gaps_only() {
+        let page_url = Url::parse("https://video.example.com/watch/1").unwrap();
+        let mut parsed =
+            parse_metadata("<html><head><title>Page</title></head></html>", &page_url).parsed;
+        apply_oembed(
+            &mut parsed,
+            &serde_json::json!({
+                "type": "video",
+                "title": "Video title",
+                "thumbnail_url": "https://video.example.com/thumb.jpg",
+            }),
+        );
+
+        assert_eq!(parsed.title.as_deref(), Some("Page"));
+        assert_eq!(
+            parsed.cover.as_deref(),
+            Some("https://video.example.com/thumb.jpg")
+        );
+        assert!(matches!(parsed.bookmark_type, Some(BookmarkType::Video)));
+    }
+}
@@ -0,0 +1,469 @@
+pub mod extract;
+pub mod fetcher;
+pub mod metadata;
+
+use ego_tree::NodeRef;
+use schemars::JsonSchema;
+use scraper::{Html, Node, Selector};
+use serde::{Deserialize, Serialize};
+
+/// Rough number of characters per LLM token, used for budgeting output size.
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Default token budget for page content returned by tools.
+pub const DEFAULT_MAX_TOKENS: usize = 4000;
+
+const TRUNCATION_MARKER: &str = "\n\n[... truncated]";
+
+// Elements whose content is never part of the readable text
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "script", "style", "noscript", "template", "head", "svg", "canvas", "iframe", "form", "button",
+    "select", "input", "textarea",
+];
+
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "main",
+    "header",
+    "footer",
+    "aside",
+    "nav",
+    "figure",
+    "figcaption",
+    "details",
+    "summary",
+    "dl",
+    "dt",
+    "dd",
+    "address",
+    "hr",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFormat {
+    #[default]
+    Markdown,
+    Text,
+}
+
+/// Page content converted for an LLM, possibly truncated to a token budget.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertedContent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub format: ContentFormat,
+    pub content: String,
+    pub truncated: bool,
+    pub approx_tokens: usize,
+}
+
+/// Converts a full HTML document and fits it into `max_tokens`.
+pub fn convert_html(html: &str, format: ContentFormat, max_tokens: usize) -> ConvertedContent {
+    let document = Html::parse_document(html);
+    let title = document_title(&document);
+    let rendered = render_node(document.tree.root(), format);
+    let (content, truncated) = truncate_to_tokens(&rendered, max_tokens);
+
+    ConvertedContent {
+        title,
+        format,
+        approx_tokens: approx_tokens(&content),
+        content,
+        truncated,
+    }
+}
+
+pub fn html_to_markdown(html: &str) -> String {
+    render_node(
+        Html::parse_document(html).tree.root(),
+        ContentFormat::Markdown,
+    )
+}
+
+pub fn html_to_text(html: &str) -> String {
+    render_node(Html::parse_document(html).tree.root(), ContentFormat::Text)
+}
+
+pub fn document_title(document: &Html) -> Option<String> {
+    let selector = Selector::parse("title").ok()?;
+    let title = document
+        .select(&selector)
+        .next()?
+        .text()
+        .collect::<String>();
+    let title = collapse_whitespace(&title).trim().to_string();
+    (!title.is_empty()).then_some(title)
+}
+
+pub fn approx_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Cuts `text` to roughly `max_tokens`, preferring a paragraph or word boundary.
+pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> (String, bool) {
+    let max_chars = max_tokens.saturating_mul(CHARS_PER_TOKEN);
+    if text.chars().count() <= max_chars {
+        return (text.to_string(), false);
+    }
+
+    let budget = max_chars.saturating_sub(TRUNCATION_MARKER.len());
+    let cut = text
+        .char_indices()
+        .nth(budget)
+        .map(|(idx, _)| idx)
+        .unwrap_or(text.len());
+    let head = &text[..cut];
+    // Only back off to a boundary if that keeps most of the budget
+    let boundary = head
+        .rfind("\n\n")
+        .filter(|&idx| idx >= cut / 2)
+        .or_else(|| {
+            head.rfind(char::is_whitespace)
+                .filter(|&idx| idx >= cut / 2)
+        })
+        .unwrap_or(cut);
+
+    (
+        format!("{}{TRUNCATION_MARKER}", head[..boundary].trim_end()),
+        true,
+    )
+}
+
+pub(crate) fn render_node(node: NodeRef<'_, Node>, format: ContentFormat) -> String {
+    render_with(node, format, false)
+}
+
+// Renders `node`, optionally dropping navigation, sidebars and similar page chrome
+pub(crate) fn render_with(
+    node: NodeRef<'_, Node>,
+    format: ContentFormat,
+    strip_boilerplate: bool,
+) -> String {
+    let mut renderer = Renderer {
+        markdown: format == ContentFormat::Markdown,
+        strip_boilerplate,
+        lists: vec![],
+    };
+    let mut out = String::new();
+    renderer.render_children(node, &mut out);
+    normalize(&out)
+}
+
+struct Renderer {
+    markdown: bool,
+    strip_boilerplate: bool,
+    // (ordered, next item number) for each open list
+    lists: Vec<(bool, usize)>,
+}
+
+impl Renderer {
+    fn render_children(&mut self, node: NodeRef<'_, Node>, out: &mut String) {
+        for child in node.children() {
+            self.render(child, out);
+        }
+    }
+
+    fn render_inline(&mut self, node: NodeRef<'_, Node>) -> String {
+        let mut inner = String::new();
+        self.render_children(node, &mut inner);
+        inner
+    }
+
+    fn render(&mut self, node: NodeRef<'_, Node>, out: &mut String) {
+        let element = match node.value() {
+            Node::Text(text) => {
+                push_text(out, &collapse_whitespace(text));
+                return;
+            }
+            Node::Element(element) => element,
+            Node::Document | Node::Fragment => {
+                self.render_children(node, out);
+                return;
+            }
+            _ => return,
+        };
+
+        let name = element.name();
+        if SKIPPED_ELEMENTS.contains(&name)
+            || (self.strip_boilerplate && extract::is_boilerplate(element))
+        {
+            return;
+        }
+
+        match name {
+            "br" => out.push('\n'),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let text = self.render_inline(node);
+                let text = text.trim();
+                if !text.is_empty() {
+                    let level = name[1..].parse::<usize>().unwrap_or(1);
+                    out.push_str("\n\n");
+                    if self.markdown {
+                        out.push_str(&"#".repeat(level));
+                        out.push(' ');
+                    }
+                    out.push_str(text);
+                    out.push_str("\n\n");
+                }
+            }
+            "pre" => {
+                let code = node
+                    .descendants()
+                    .filter_map(|n| n.value().as_text().map(|t| t.to_string()))
+                    .collect::<String>();
+                out.push_str("\n\n");
+                if self.markdown {
+                    out.push_str("```\n");
+                    out.push_str(code.trim_end());
+                    out.push_str("\n```");
+                } else {
+                    out.push_str(code.trim_end());
+                }
+                out.push_str("\n\n");
+            }
+            "code" | "kbd" | "samp" if self.markdown => {
+                let text = self.render_inline(node);
+                let text = text.trim();
+                if !text.is_empty() {
+                    push_text(out, &format!("`{text}`"));
+                }
+            }
+            "strong" | "b" if self.markdown => self.wrap_inline(node, "**", out),
+            "em" | "i" if self.markdown => self.wrap_inline(node, "*", out),
+            "a" => {
+                let text = self.render_inline(node);
+                let text = text.trim();
+                let href = element.attr("href").unwrap_or_default();
+                if self.markdown
+                    && !text.is_empty()
+                    && !href.is_empty()
+                    && !href.starts_with('#')
+                    && !href.starts_with("javascript:")
+                {
+                    push_text(out, &format!("[{text}]({href})"));
+                } else {
+                    push_text(out, text);
+                }
+            }
+            "img" => {
+                if self.markdown {
+                    let src = element.attr("src").unwrap_or_default();
+                    let alt = element.attr("alt").unwrap_or_default().trim();
+                    if !src.is_empty() && !src.starts_with("data:") {
+                        push_text(out, &format!("![{alt}]({src})"));
+                    }
+                }
+            }
+            "ul" | "ol" => {
+                self.lists.push((name == "ol", 1));
+                out.push('\n');
+                self.render_children(node, out);
+                self.lists.pop();
+                out.push_str("\n\n");
+            }
+            "li" => {
+                let depth = self.lists.len().saturating_sub(1);
+                let marker = match self.lists.last_mut() {
+                    Some((true, number)) => {
+                        let marker = format!("{number}. ");
+                        *number += 1;
+                        marker
+                    }
+                    _ => "- ".to_string(),
+                };
+                out.push('\n');
+                out.push_str(&"  ".repeat(depth));
+                out.push_str(&marker);
+                let item = self.render_inline(node);
+                out.push_str(item.trim());
+            }
+            "blockquote" => {
+                let quoted = normalize(&self.render_inline(node));
+                out.push_str("\n\n");
+                for line in quoted.lines() {
+                    if self.markdown {
+                        out.push_str("> ");
+                    }
+                    out.push_str(line);
+                    out.push('\n');
+                }
+                out.push('\n');
+            }
+            "table" => self.render_table(node, out),
+            _ if BLOCK_ELEMENTS.contains(&name) => {
+                out.push_str("\n\n");
+                self.render_children(node, out);
+                out.push_str("\n\n");
+            }
+            _ => self.render_children(node, out),
+        }
+    }
+
+    fn wrap_inline(&mut self, node: NodeRef<'_, Node>, marker: &str, out: &mut String) {
+        let text = self.render_inline(node);
+        let text = text.trim();
+        if !text.is_empty() {
+            push_text(out, &format!("{marker}{text}{marker}"));
+        }
+    }
+
+    fn render_table(&mut self, node: NodeRef<'_, Node>, out: &mut String) {
+        let rows: Vec<Vec<String>> = node
+            .descendants()
+            .filter(|n| n.value().as_element().is_some_and(|e| e.name() == "tr"))
+            .map(|row| {
+                row.children()
+                    .filter(|c| {
+                        c.value()
+                            .as_element()
+                            .is_some_and(|e| matches!(e.name(), "td" | "th"))
+                    })
+                    .map(|cell| {
+                        normalize(&self.render_inline(cell))
+                            .replace('\n', " ")
+                            .replace('|', "\\|")
+                    })
+                    .collect()
+            })
+            .filter(|cells: &Vec<String>| !cells.is_empty())
+            .collect();
+
+        out.push_str("\n\n");
+        for (index, cells) in rows.iter().enumerate() {
+            if self.markdown {
+                out.push_str(&format!("| {} |\n", cells.join(" | ")));
+                if index == 0 {
+                    out.push_str(&format!("|{}\n", " --- |".repeat(cells.len())));
+                }
+            } else {
+                out.push_str(&cells.join("\t"));
+                out.push('\n');
+            }
+        }
+        out.push('\n');
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last_was_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !last_was_space {
+                result.push(' ');
+            }
+            last_was_space = true;
+        } else {
+            result.push(c);
+            last_was_space = false;
+        }
+    }
+    result
+}
+
+// Appends inline text without doubling spaces or indenting new lines
+fn push_text(out: &mut String, text: &str) {
+    if out.is_empty() || out.ends_with(['\n', ' ']) {
+        out.push_str(text.trim_start());
+    } else {
+        out.push_str(text);
+    }
+}
+
+// Trims trailing spaces and collapses runs of blank lines
+fn normalize(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut blank_lines = 0;
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        if !result.is_empty() {
+            result.push_str(if blank_lines > 0 { "\n\n" } else { "\n" });
+        }
+        result.push_str(line);
+        blank_lines = 0;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const PAGE: &str = r#"<!doctype html>
+        <html>
+          <head><title> Example   Page </title><style>body { color: red }</style></head>
+          <body>
+            <h1>Heading</h1>
+            <p>Some <strong>bold</strong> and <em>italic</em> text with a
+               <a href="https://example.com/next">link</a>.</p>
+            <script>alert("hidden")</script>
+            <ul><li>First</li><li>Second <code>item</code></li></ul>
+            <pre>fn main() {
+    println!("hi");
+}</pre>
+            <blockquote><p>Quoted words</p></blockquote>
+          </body>
+        </html>"#;
+
+    #[test]
+    fn test_html_to_markdown() {
+        let markdown = html_to_markdown(PAGE);
+        assert_eq!(
+            markdown,
+            "# Heading\n\n\
+             Some **bold** and *italic* text with a [link](https://example.com/next).\n\n\
+             - First\n\
+             - Second `item`\n\n\
+             ```\nfn main() {\n    println!(\"hi\");\n}\n```\n\n\
+             > Quoted words"
+        );
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let text = html_to_text(PAGE);
+        assert!(text.starts_with("Heading\n\nSome bold and italic text with a link."));
+        assert!(!text.contains("alert"));
+        assert!(!text.contains("**"));
+    }
+
+    #[test]
+    fn test_convert_html_extracts_title() {
+        let converted = convert_html(PAGE, ContentFormat::Markdown, DEFAULT_MAX_TOKENS);
+        assert_eq!(converted.title.as_deref(), Some("Example Page"));
+        assert!(!converted.truncated);
+    }
+
+    #[test]
+    fn test_ordered_list_and_table() {
+        let html = "<ol><li>One</li><li>Two</li></ol>\
+                    <table><tr><th>Name</th><th>Value</th></tr><tr><td>a</td><td>1</td></tr></table>";
+        assert_eq!(
+            html_to_markdown(html),
+            "1. One\n2. Two\n\n| Name | Value |\n| --- | --- |\n| a | 1 |"
+        );
+    }
+
+    #[test]
+    fn test_truncate_to_tokens() {
+        let text = "word ".repeat(100);
+        let (short, truncated) = truncate_to_tokens(&text, 10);
+        assert!(truncated);
+        assert!(short.ends_with("[... truncated]"));
+        assert!(short.chars().count() <= 10 * CHARS_PER_TOKEN);
+
+        let (same, truncated) = truncate_to_tokens("short", 10);
+        assert_eq!(same, "short");
+        assert!(!truncated);
+    }
+}
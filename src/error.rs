@@ -25,6 +25,9 @@ pub enum RaindropMcpError {
     #[error("Environment variable error: {0}")]
     EnvironmentVariable(String),
 
+    #[error("Configuration error: {0}")]
+    Config(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -37,6 +40,9 @@ pub enum RaindropMcpError {
     #[error("Unauthorized: {0}")]
     Unauthorized(String),
 
+    #[error("Read-only: {0}")]
+    ReadOnly(String),
+
     #[error("Rate limit exceeded: {message}")]
     RateLimitExceeded {
         message: String,
@@ -93,12 +99,31 @@ impl RaindropMcpError {
             RaindropMcpError::InvalidParameter(_) => -32602, // Invalid params
             RaindropMcpError::NotFound(_) => -32601,         // Method not found
             RaindropMcpError::Unauthorized(_) => -32603,     // Internal error (auth)
+            RaindropMcpError::ReadOnly(_) => -32600,         // Invalid request
             RaindropMcpError::RateLimitExceeded { .. } => -32604, // Rate limit
             RaindropMcpError::ServiceUnavailable(_) => -32605, // Service unavailable
             RaindropMcpError::Timeout(_) => -32606,          // Timeout
             _ => -32603,                                     // Generic internal error
         }
     }
+
+    /// Process exit status for the command line, following BSD `sysexits.h`
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            RaindropMcpError::InvalidParameter(_) => 64,  // EX_USAGE
+            RaindropMcpError::JsonSerialization(_) => 65, // EX_DATAERR
+            RaindropMcpError::NotFound(_) => 66,          // EX_NOINPUT
+            RaindropMcpError::Io(_) => 74,                // EX_IOERR
+            RaindropMcpError::HttpRequest(_)
+            | RaindropMcpError::RateLimitExceeded { .. }
+            | RaindropMcpError::ServiceUnavailable(_)
+            | RaindropMcpError::Timeout(_) => 75, // EX_TEMPFAIL: worth retrying
+            RaindropMcpError::RaindropApi(..) | RaindropMcpError::McpProtocol(..) => 76, // EX_PROTOCOL
+            RaindropMcpError::Unauthorized(_) | RaindropMcpError::ReadOnly(_) => 77, // EX_NOPERM
+            RaindropMcpError::Config(_) | RaindropMcpError::EnvironmentVariable(_) => 78, // EX_CONFIG
+            RaindropMcpError::Unknown(_) => 1,
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, RaindropMcpError>;
@@ -214,4 +239,24 @@ mod tests {
         };
         assert_eq!(err.to_mcp_error_code(), -32604);
     }
+
+    #[test]
+    fn test_exit_codes() {
+        let err = RaindropMcpError::NotFound("Collection not found".to_string());
+        assert_eq!(err.exit_code(), 66);
+
+        let err = RaindropMcpError::Unauthorized("Invalid token".to_string());
+        assert_eq!(err.exit_code(), 77);
+
+        // Every retryable error asks the caller to try again later
+        let err = RaindropMcpError::RateLimitExceeded {
+            message: "Too many requests".to_string(),
+            retry_after: Some(30),
+        };
+        assert!(err.is_retryable());
+        assert_eq!(err.exit_code(), 75);
+
+        let err = RaindropMcpError::Config("Unknown profile".to_string());
+        assert_eq!(err.exit_code(), 78);
+    }
 }
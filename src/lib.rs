@@ -1,3 +1,6 @@
+pub mod cli;
+pub mod config;
+pub mod content;
 pub mod error;
 pub mod mcp;
 pub mod raindrop;
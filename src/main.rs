@@ -1,18 +1,39 @@
-use anyhow::Result;
-use raindrop_mcp_server::mcp::McpServer;
+use anyhow::{Context, Result};
+use clap::Parser;
+use raindrop_mcp_server::{
+    RaindropMcpError,
+    cli::{self, ApiCommand, Cli, Command, OutputMode},
+    config::Config,
+    mcp::{
+        McpServer,
+        session::{self, MCP_PATH, SessionFactory},
+    },
+    raindrop::{
+        client::RaindropClient,
+        oauth::{self, OAuthClient, TokenStore},
+    },
+};
 use rmcp::{ServiceExt, transport::stdio};
+use std::{net::SocketAddr, process::ExitCode};
 use tracing::{error, info};
 use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     // Initialize environment variables
     dotenv::dotenv().ok();
+    let cli = Cli::parse();
+
+    // Commands print their results on stdout, so only warnings are logged unless serving
+    let default_level = match cli.command {
+        None | Some(Command::Serve { .. }) | Some(Command::Auth) => "info",
+        Some(Command::Api(_)) => "warn",
+    };
 
     // Initialize tracing to stderr
     tracing_subscriber::fmt()
         .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_level)),
         )
         .with_writer(std::io::stderr)
         .with_target(false)
@@ -21,10 +42,39 @@ async fn main() -> Result<()> {
         .with_line_number(true)
         .init();
 
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        // Failures from Raindrop or the config keep their own status; anything else is 1
+        Err(e) => match e.downcast_ref::<RaindropMcpError>() {
+            Some(error) => {
+                eprintln!("Error: {error}");
+                ExitCode::from(error.exit_code())
+            }
+            None => {
+                eprintln!("Error: {e:#}");
+                ExitCode::FAILURE
+            }
+        },
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    match cli.command.unwrap_or(Command::Serve { http: None }) {
+        Command::Serve { http: None } => serve(&config, cli.profile.as_deref()).await,
+        Command::Serve { http: Some(addr) } => {
+            serve_http(&config, cli.profile.as_deref(), addr).await
+        }
+        Command::Auth => auth(&config, cli.profile.as_deref()).await,
+        Command::Api(command) => api(&config, cli.profile.as_deref(), &command, cli.output).await,
+    }
+}
+
+async fn serve(config: &Config, profile: Option<&str>) -> Result<()> {
     info!("Starting Raindrop MCP server...");
 
     // Create MCP server and serve with STDIO transport
-    let service = McpServer::new()?
+    let service = McpServer::from_config(config, profile)?
         .serve(stdio())
         .await
         .inspect_err(|e| error!("Service error: {:?}", e))?;
@@ -37,3 +87,66 @@ async fn main() -> Result<()> {
     info!("MCP server shutdown complete");
     Ok(())
 }
+
+async fn serve_http(config: &Config, profile: Option<&str>, addr: SocketAddr) -> Result<()> {
+    let router = session::router(SessionFactory::from_config(config, profile)?);
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Cannot listen on {addr}"))?;
+    info!(
+        "Serving MCP over HTTP at http://{}{}",
+        listener.local_addr()?,
+        MCP_PATH
+    );
+
+    axum::serve(listener, router)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+
+    info!("MCP server shutdown complete");
+    Ok(())
+}
+
+async fn auth(config: &Config, profile: Option<&str>) -> Result<()> {
+    let name = config.primary_profile(profile)?;
+    let settings = config
+        .profiles()
+        .remove(&name)
+        .and_then(|profile| profile.oauth)
+        .with_context(|| format!("Profile '{name}' has no [profiles.{name}.oauth] section"))?;
+
+    let client = OAuthClient::new(&settings)?;
+    let listener = oauth::bind_redirect(client.redirect_uri()).await?;
+    info!(
+        "Waiting for the authorization redirect on {}",
+        client.redirect_uri()
+    );
+    let tokens = client
+        .authorize(listener, |url| {
+            eprintln!("Open this URL in your browser to authorize Raindrop access:\n\n  {url}\n");
+        })
+        .await?;
+
+    let store = TokenStore::for_profile(&name, &settings)?;
+    store.save(&tokens)?;
+    eprintln!(
+        "Authorized profile '{name}'; tokens saved to {}",
+        store.path().display()
+    );
+    Ok(())
+}
+
+async fn api(
+    config: &Config,
+    profile: Option<&str>,
+    command: &ApiCommand,
+    output: OutputMode,
+) -> Result<()> {
+    let name = config.primary_profile(profile)?;
+    let settings = config.profiles().remove(&name).unwrap_or_default();
+    let client = RaindropClient::from_profile(&name, &settings)?;
+    print!("{}", cli::execute(&client, command, output).await?);
+    Ok(())
+}
@@ -0,0 +1,281 @@
+use rmcp::model::{CompletionInfo, Reference};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Most values returned by one `completion/complete` request, as the MCP spec allows.
+pub const MAX_COMPLETIONS: usize = 100;
+
+/// Newest bookmarks offered when completing bookmark IDs.
+pub const RECENT_BOOKMARK_CANDIDATES: usize = 200;
+
+/// Data an argument completes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompletionSource {
+    Collections,
+    Tags,
+    RecentBookmarks,
+}
+
+/// What to complete for an argument, and whether values are IDs or titles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    CollectionId,
+    /// Collection title, for arguments that also accept names
+    CollectionName,
+    Tag,
+    BookmarkId,
+}
+
+impl CompletionKind {
+    /// Picks the kind from the prompt argument name or the resource template variable.
+    pub fn for_argument(reference: &Reference, argument: &str) -> Option<Self> {
+        match reference {
+            Reference::Prompt(_) => match argument {
+                "collectionId" | "parentId" => Some(Self::CollectionId),
+                "collection" => Some(Self::CollectionName),
+                "tag" | "tags" | "tagName" => Some(Self::Tag),
+                "bookmarkId" | "bookmark" | "raindropId" => Some(Self::BookmarkId),
+                _ => None,
+            },
+            Reference::Resource(resource) => {
+                let path = resource.uri.strip_prefix("raindrop://")?;
+                match (path.split('/').next()?, argument) {
+                    ("collection", "id") => Some(Self::CollectionId),
+                    ("bookmark", "id") => Some(Self::BookmarkId),
+                    ("tag", "name") => Some(Self::Tag),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    pub fn source(self) -> CompletionSource {
+        match self {
+            Self::CollectionId | Self::CollectionName => CompletionSource::Collections,
+            Self::Tag => CompletionSource::Tags,
+            Self::BookmarkId => CompletionSource::RecentBookmarks,
+        }
+    }
+}
+
+/// A completion value and the text it is matched by, e.g. a collection ID and its title.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub value: String,
+    pub label: String,
+}
+
+impl Candidate {
+    pub fn new(value: impl ToString, label: impl Into<String>) -> Self {
+        Self {
+            value: value.to_string(),
+            label: label.into(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedCandidates {
+    fetched: Instant,
+    candidates: Arc<Vec<Candidate>>,
+}
+
+/// Completion candidates per source, refetched once older than the TTL or after a change.
+#[derive(Debug)]
+pub struct CompletionCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<CompletionSource, CachedCandidates>>,
+}
+
+impl CompletionCache {
+    /// Cache keeping candidates for `ttl`, the profile's `cache.completion_ttl_secs`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cached candidates for `source`, unless they have expired.
+    pub fn get(&self, source: CompletionSource) -> Option<Arc<Vec<Candidate>>> {
+        self.lock()
+            .get(&source)
+            .filter(|cached| cached.fetched.elapsed() < self.ttl)
+            .map(|cached| cached.candidates.clone())
+    }
+
+    pub fn insert(
+        &self,
+        source: CompletionSource,
+        candidates: Vec<Candidate>,
+    ) -> Arc<Vec<Candidate>> {
+        let candidates = Arc::new(candidates);
+        self.lock().insert(
+            source,
+            CachedCandidates {
+                fetched: Instant::now(),
+                candidates: candidates.clone(),
+            },
+        );
+        candidates
+    }
+
+    /// Drops everything, e.g. after a tool changed the library.
+    pub fn invalidate(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<CompletionSource, CachedCandidates>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Scores `text` against `query` as a case-insensitive subsequence; `None` if it does not match.
+///
+/// Prefix matches, matches at word starts and consecutive characters score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let query: Vec<char> = query.trim().to_lowercase().chars().collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for &wanted in &query {
+        let position = next + text[next..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if position == 0 {
+            score += 8;
+        } else if !text[position - 1].is_alphanumeric() {
+            score += 4;
+        }
+        if previous.is_some_and(|p| p + 1 == position) {
+            score += 3;
+        }
+        previous = Some(position);
+        next = position + 1;
+    }
+    // Prefer shorter texts among equally good matches
+    Some(score * 100 / (text.len() as u32 + 10))
+}
+
+/// Best matches for `query` among `candidates`, matched by label or value, best first.
+pub fn complete(query: &str, candidates: &[Candidate], kind: CompletionKind) -> CompletionInfo {
+    let mut matches: Vec<(u32, &Candidate)> = candidates
+        .iter()
+        .filter_map(|candidate| {
+            let score =
+                fuzzy_score(query, &candidate.label).max(fuzzy_score(query, &candidate.value))?;
+            Some((score, candidate))
+        })
+        .collect();
+    // Stable, so ties keep the source order (e.g. newest bookmarks first)
+    matches.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let mut values: Vec<String> = vec![];
+    for (_, candidate) in &matches {
+        let value = match kind {
+            CompletionKind::CollectionName => &candidate.label,
+            _ => &candidate.value,
+        };
+        if !values.contains(value) {
+            values.push(value.clone());
+        }
+    }
+    let total = values.len();
+    values.truncate(MAX_COMPLETIONS);
+    CompletionInfo {
+        has_more: Some(total > values.len()),
+        total: Some(total as u32),
+        values,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rmcp::model::{PromptReference, ResourceReference};
+
+    #[test]
+    fn test_argument_kinds() {
+        let prompt = Reference::Prompt(PromptReference {
+            name: "bookmark-summary".to_string(),
+        });
+        let resource = |uri: &str| {
+            Reference::Resource(ResourceReference {
+                uri: uri.to_string(),
+            })
+        };
+        assert_eq!(
+            CompletionKind::for_argument(&prompt, "collectionId"),
+            Some(CompletionKind::CollectionId)
+        );
+        assert_eq!(CompletionKind::for_argument(&prompt, "maxTokens"), None);
+        assert_eq!(
+            CompletionKind::for_argument(
+                &resource("raindrop://collection/{id}/bookmarks{?page,sort}"),
+                "id"
+            ),
+            Some(CompletionKind::CollectionId)
+        );
+        assert_eq!(
+            CompletionKind::for_argument(&resource("raindrop://tag/{name}/bookmarks"), "name"),
+            Some(CompletionKind::Tag)
+        );
+        assert_eq!(
+            CompletionKind::for_argument(&resource("raindrop://search/{query}"), "query"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_completion() {
+        let collections = vec![
+            Candidate::new(10, "Reading List"),
+            Candidate::new(11, "Research"),
+            Candidate::new(12, "Recipes"),
+            Candidate::new(42, "Rust"),
+        ];
+        let info = complete("rl", &collections, CompletionKind::CollectionId);
+        assert_eq!(info.values, vec!["10"]);
+
+        let info = complete("res", &collections, CompletionKind::CollectionName);
+        assert_eq!(info.values[0], "Research");
+
+        // IDs match too
+        let info = complete("42", &collections, CompletionKind::CollectionId);
+        assert_eq!(info.values, vec!["42"]);
+
+        // Prefix matches rank above scattered ones
+        let info = complete("re", &collections, CompletionKind::CollectionName);
+        assert_eq!(info.values[..2], ["Recipes", "Research"]);
+        assert_eq!(info.total, Some(3));
+
+        let tags: Vec<Candidate> = (0..150)
+            .map(|i| Candidate::new(format!("tag{i}"), format!("tag{i}")))
+            .collect();
+        let info = complete("", &tags, CompletionKind::Tag);
+        assert_eq!(info.values.len(), MAX_COMPLETIONS);
+        assert_eq!(info.has_more, Some(true));
+    }
+
+    #[test]
+    fn test_cache_expires_and_invalidates() {
+        let cache = CompletionCache::new(Duration::from_secs(60));
+        assert!(cache.get(CompletionSource::Tags).is_none());
+        cache.insert(CompletionSource::Tags, vec![Candidate::new("rust", "rust")]);
+        assert_eq!(cache.get(CompletionSource::Tags).unwrap().len(), 1);
+        cache.invalidate();
+        assert!(cache.get(CompletionSource::Tags).is_none());
+
+        let expired = CompletionCache::new(Duration::ZERO);
+        expired.insert(CompletionSource::Tags, vec![]);
+        assert!(expired.get(CompletionSource::Tags).is_none());
+    }
+}
@@ -0,0 +1,152 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::{RaindropMcpError, Result};
+
+/// How long a confirmation token stays valid after it was issued.
+pub const DEFAULT_CONFIRMATION_TTL: Duration = Duration::from_secs(300);
+
+/// Maximum number of item titles included in a preview.
+pub const PREVIEW_TITLE_LIMIT: usize = 20;
+
+/// Summary of what a destructive or sweeping operation would affect, shown to the user
+/// before they confirm it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DestructivePreview {
+    pub operation: String,
+    pub description: String,
+    pub item_count: i64,
+    pub titles: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug)]
+struct PendingConfirmation {
+    operation: String,
+    expires_at: Instant,
+}
+
+/// Issues and redeems one-time tokens that gate destructive operations.
+///
+/// A token is bound to the operation key it was issued for (for example
+/// `delete_collection:42`), so it cannot be replayed against another target.
+#[derive(Debug)]
+pub struct ConfirmationStore {
+    pending: Mutex<HashMap<String, PendingConfirmation>>,
+    ttl: Duration,
+}
+
+impl Default for ConfirmationStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_CONFIRMATION_TTL)
+    }
+}
+
+impl ConfirmationStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Issues a new token for `operation`, pruning any expired entries.
+    pub fn issue(&self, operation: &str) -> Result<String> {
+        let token = generate_token()?;
+        let now = Instant::now();
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.retain(|_, entry| entry.expires_at > now);
+        pending.insert(
+            token.clone(),
+            PendingConfirmation {
+                operation: operation.to_string(),
+                expires_at: now + self.ttl,
+            },
+        );
+        Ok(token)
+    }
+
+    /// Consumes `token` if it was issued for `operation` and has not expired.
+    pub fn redeem(&self, token: &str, operation: &str) -> Result<()> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = pending.remove(token).ok_or_else(|| {
+            RaindropMcpError::InvalidParameter(
+                "Unknown or already used confirmation token".to_string(),
+            )
+        })?;
+
+        if entry.expires_at <= Instant::now() {
+            return Err(RaindropMcpError::InvalidParameter(
+                "Confirmation token has expired; request a new preview".to_string(),
+            ));
+        }
+
+        if entry.operation != operation {
+            // Put it back so the token can still be used for the operation it was issued for
+            let issued_for = entry.operation.clone();
+            pending.insert(token.to_string(), entry);
+            return Err(RaindropMcpError::InvalidParameter(format!(
+                "Confirmation token was issued for '{issued_for}', not '{operation}'"
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+// 128 bits from the OS random source, so tokens cannot be predicted from earlier ones
+fn generate_token() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(std::io::Error::from)?;
+    Ok(format!("{:032x}", u128::from_be_bytes(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_issue_and_redeem() {
+        let store = ConfirmationStore::default();
+        let token = store.issue("empty_trash").unwrap();
+        assert_eq!(token.len(), 32);
+        assert!(store.redeem(&token, "empty_trash").is_ok());
+        // Tokens are single use
+        assert!(store.redeem(&token, "empty_trash").is_err());
+    }
+
+    #[test]
+    fn test_token_bound_to_operation() {
+        let store = ConfirmationStore::default();
+        let token = store.issue("delete_collection:1").unwrap();
+        let err = store.redeem(&token, "delete_collection:2").unwrap_err();
+        assert!(matches!(err, RaindropMcpError::InvalidParameter(_)));
+        // Still valid for the original target
+        assert!(store.redeem(&token, "delete_collection:1").is_ok());
+    }
+
+    #[test]
+    fn test_expired_token_rejected() {
+        let store = ConfirmationStore::new(Duration::ZERO);
+        let token = store.issue("empty_trash").unwrap();
+        let err = store.redeem(&token, "empty_trash").unwrap_err();
+        assert!(err.to_string().contains("expired"));
+    }
+
+    #[test]
+    fn test_tokens_are_unique() {
+        let store = ConfirmationStore::default();
+        let a = store.issue("empty_trash").unwrap();
+        let b = store.issue("empty_trash").unwrap();
+        assert_ne!(a, b);
+    }
+}
@@ -1,3 +1,10 @@
+pub mod completion;
+pub mod confirmation;
+pub mod prompt_templates;
+pub mod prompts;
+pub mod resources;
 pub mod server;
+pub mod session;
+pub mod subscriptions;
 
 pub use server::McpServer;
@@ -0,0 +1,610 @@
+use minijinja::{Environment, UndefinedBehavior};
+use rmcp::model::{JsonObject, PromptArgument, PromptMessage, PromptMessageRole};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    config::default_config_dir,
+    content::{approx_tokens, truncate_to_tokens},
+    error::RaindropMcpError,
+};
+
+const PROMPTS_DIR: &str = "prompts";
+
+/// Items fetched by a list binding unless it sets `limit`.
+pub const DEFAULT_BINDING_LIMIT: usize = 50;
+
+/// A prompt defined in a TOML or YAML file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PromptTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<TemplateArgument>,
+    /// Data fetched before rendering, available to messages under each binding's name
+    #[serde(default)]
+    pub data: Vec<DataBinding>,
+    pub messages: Vec<TemplateMessage>,
+    /// Token budget for the rendered messages, unless the caller passes `maxTokens`
+    #[serde(default)]
+    pub max_tokens: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TemplateArgument {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+    /// Value used when the caller omits the argument
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateRole {
+    #[default]
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TemplateMessage {
+    #[serde(default)]
+    pub role: TemplateRole,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataSource {
+    /// Bookmarks filtered by `collection`, `tag` and `search`
+    Bookmarks,
+    /// A single bookmark by `bookmark` ID
+    Bookmark,
+    /// A single collection by `collection` ID or name
+    Collection,
+    /// Every collection
+    Collections,
+    /// Tags, optionally within `collection`
+    Tags,
+    /// Highlights of `bookmark`, or across the library
+    Highlights,
+}
+
+/// Data fetched for a template, e.g. "bookmarks in collection {{ collectionId }} tagged {{ tag }}".
+///
+/// Every text field is itself a template over the prompt's arguments.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct DataBinding {
+    pub name: String,
+    pub source: DataSource,
+    #[serde(default)]
+    pub collection: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub search: Option<String>,
+    #[serde(default)]
+    pub bookmark: Option<String>,
+    #[serde(default)]
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// A data binding with its arguments substituted, ready to fetch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedBinding {
+    pub name: String,
+    pub source: DataSource,
+    /// Collection ID or name
+    pub collection: Option<String>,
+    pub tag: Option<String>,
+    pub search: Option<String>,
+    pub bookmark: Option<i64>,
+    pub sort: Option<String>,
+    pub limit: usize,
+}
+
+/// Why a template file was not loaded.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateError {
+    pub file: String,
+    pub message: String,
+}
+
+/// Values available to a template: its arguments, then its data bindings.
+pub type TemplateContext = BTreeMap<String, serde_json::Value>;
+
+impl PromptTemplate {
+    /// Parses a template from a `.toml`, `.yaml` or `.yml` file's contents.
+    pub fn parse(path: &Path, text: &str) -> Result<Self, String> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(text).map_err(|e| e.to_string()),
+            Some("yaml" | "yml") => serde_yaml::from_str(text).map_err(|e| e.to_string()),
+            _ => Err("Unsupported file type; use .toml, .yaml or .yml".to_string()),
+        }
+    }
+
+    /// Checks names, binding fields and that every template only uses declared variables.
+    pub fn validate(&self, reserved: &[&str]) -> Result<(), String> {
+        let mut problems = vec![];
+        let valid_name = |name: &str| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        };
+        if !valid_name(&self.name) {
+            problems.push(format!(
+                "name '{}' must be non-empty and use only letters, digits, '-' and '_'",
+                self.name
+            ));
+        }
+        if reserved.contains(&self.name.as_str()) {
+            problems.push(format!("name '{}' is used by a built-in prompt", self.name));
+        }
+        if self.messages.is_empty() {
+            problems.push("at least one message is required".to_string());
+        }
+
+        let mut arguments = BTreeSet::new();
+        for argument in &self.arguments {
+            if !is_identifier(&argument.name) {
+                problems.push(format!(
+                    "argument '{}' is not a valid identifier",
+                    argument.name
+                ));
+            }
+            if !arguments.insert(argument.name.as_str()) {
+                problems.push(format!("argument '{}' is declared twice", argument.name));
+            }
+        }
+
+        let env = environment();
+        let globals: BTreeSet<&str> = env.globals().map(|(name, _)| name).collect();
+        let check = |what: String, source: &str, known: &BTreeSet<&str>| {
+            let template = match env.template_from_str(source) {
+                Ok(template) => template,
+                Err(e) => return Some(format!("{what} is not a valid template: {e}")),
+            };
+            let mut unknown: Vec<String> = template
+                .undeclared_variables(false)
+                .into_iter()
+                .filter(|v| !known.contains(v.as_str()) && !globals.contains(v.as_str()))
+                .collect();
+            unknown.sort();
+            (!unknown.is_empty())
+                .then(|| format!("{what} uses undeclared variables: {}", unknown.join(", ")))
+        };
+
+        let mut variables = arguments.clone();
+        for binding in &self.data {
+            let what = format!("data '{}'", binding.name);
+            if !is_identifier(&binding.name) {
+                problems.push(format!("{what}: name is not a valid identifier"));
+            }
+            if !variables.insert(binding.name.as_str()) {
+                problems.push(format!(
+                    "{what}: name clashes with another argument or binding"
+                ));
+            }
+            let required = match binding.source {
+                DataSource::Bookmark => Some(("bookmark", &binding.bookmark)),
+                DataSource::Collection => Some(("collection", &binding.collection)),
+                _ => None,
+            };
+            if let Some((field, None)) = required {
+                problems.push(format!("{what}: source requires '{field}'"));
+            }
+            problems.extend(binding.fields().filter_map(|(field, value)| {
+                check(format!("{what} field '{field}'"), value, &arguments)
+            }));
+        }
+        for (index, message) in self.messages.iter().enumerate() {
+            problems.extend(check(
+                format!("message {}", index + 1),
+                &message.text,
+                &variables,
+            ));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.join("; "))
+        }
+    }
+
+    /// Arguments advertised in `prompts/list`.
+    pub fn prompt_arguments(&self) -> Vec<PromptArgument> {
+        self.arguments
+            .iter()
+            .map(|argument| PromptArgument {
+                name: argument.name.clone(),
+                description: argument.description.clone(),
+                required: Some(argument.required),
+            })
+            .collect()
+    }
+
+    /// Caller's arguments with defaults filled in, rejecting missing required ones.
+    pub fn context(
+        &self,
+        arguments: Option<&JsonObject>,
+    ) -> Result<TemplateContext, RaindropMcpError> {
+        let mut context = TemplateContext::new();
+        for argument in &self.arguments {
+            let value = arguments
+                .and_then(|args| args.get(&argument.name))
+                .filter(|value| !value.is_null())
+                .cloned()
+                .or_else(|| argument.default.clone().map(serde_json::Value::String));
+            match value {
+                Some(value) => {
+                    context.insert(argument.name.clone(), value);
+                }
+                None if argument.required => {
+                    return Err(RaindropMcpError::InvalidParameter(format!(
+                        "Prompt '{}' requires argument '{}'",
+                        self.name, argument.name
+                    )));
+                }
+                // Optional arguments render as empty strings
+                None => {
+                    context.insert(argument.name.clone(), serde_json::Value::from(""));
+                }
+            }
+        }
+        Ok(context)
+    }
+
+    /// Substitutes arguments into each data binding. Fields that render empty are dropped.
+    pub fn resolve_bindings(
+        &self,
+        context: &TemplateContext,
+    ) -> Result<Vec<ResolvedBinding>, RaindropMcpError> {
+        let env = environment();
+        let render = |binding: &DataBinding, field: &str, source: &Option<String>| {
+            let Some(source) = source else {
+                return Ok(None);
+            };
+            let value = env.render_str(source, context).map_err(|e| {
+                RaindropMcpError::InvalidParameter(format!(
+                    "Prompt '{}' data '{}' field '{field}': {e}",
+                    self.name, binding.name
+                ))
+            })?;
+            let value = value.trim().to_string();
+            Ok::<_, RaindropMcpError>((!value.is_empty()).then_some(value))
+        };
+
+        self.data
+            .iter()
+            .map(|binding| {
+                let bookmark = render(binding, "bookmark", &binding.bookmark)?
+                    .map(|id| {
+                        id.parse::<i64>().map_err(|_| {
+                            RaindropMcpError::InvalidParameter(format!(
+                                "Prompt '{}' data '{}': bookmark '{id}' is not an ID",
+                                self.name, binding.name
+                            ))
+                        })
+                    })
+                    .transpose()?;
+                Ok(ResolvedBinding {
+                    name: binding.name.clone(),
+                    source: binding.source,
+                    collection: render(binding, "collection", &binding.collection)?,
+                    tag: render(binding, "tag", &binding.tag)?,
+                    search: render(binding, "search", &binding.search)?,
+                    bookmark,
+                    sort: render(binding, "sort", &binding.sort)?,
+                    limit: binding.limit.unwrap_or(DEFAULT_BINDING_LIMIT),
+                })
+            })
+            .collect()
+    }
+
+    /// Renders the messages, truncating them to share `max_tokens` in order.
+    pub fn render(
+        &self,
+        context: &TemplateContext,
+        max_tokens: usize,
+    ) -> Result<Vec<PromptMessage>, RaindropMcpError> {
+        let env = environment();
+        let mut remaining = max_tokens;
+        self.messages
+            .iter()
+            .enumerate()
+            .map(|(index, message)| {
+                let text = env.render_str(&message.text, context).map_err(|e| {
+                    RaindropMcpError::InvalidParameter(format!(
+                        "Prompt '{}' message {}: {e}",
+                        self.name,
+                        index + 1
+                    ))
+                })?;
+                let (text, _) = truncate_to_tokens(&text, remaining);
+                remaining = remaining.saturating_sub(approx_tokens(&text));
+                let role = match message.role {
+                    TemplateRole::User => PromptMessageRole::User,
+                    TemplateRole::Assistant => PromptMessageRole::Assistant,
+                };
+                Ok(PromptMessage::new_text(role, text))
+            })
+            .collect()
+    }
+}
+
+impl DataBinding {
+    fn fields(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [
+            ("collection", &self.collection),
+            ("tag", &self.tag),
+            ("search", &self.search),
+            ("bookmark", &self.bookmark),
+            ("sort", &self.sort),
+        ]
+        .into_iter()
+        .filter_map(|(field, value)| Some((field, value.as_deref()?)))
+    }
+}
+
+/// Templates loaded from one directory at a point in time.
+#[derive(Debug, Default)]
+pub struct LoadedTemplates {
+    // Path, modification time and length of every file, to notice edits
+    stamp: Vec<(PathBuf, Option<SystemTime>, u64)>,
+    pub templates: BTreeMap<String, PromptTemplate>,
+    pub errors: Vec<TemplateError>,
+}
+
+/// User prompt templates in a config directory, reloaded whenever its files change.
+#[derive(Debug)]
+pub struct PromptTemplates {
+    dir: Option<PathBuf>,
+    reserved: Vec<String>,
+    loaded: Mutex<Arc<LoadedTemplates>>,
+}
+
+impl PromptTemplates {
+    /// Templates in `RAINDROP_PROMPTS_DIR`, or `prompts` in the user's config directory.
+    ///
+    /// `reserved` names the built-in prompts that templates may not replace.
+    pub fn open_default(reserved: &[&str]) -> Self {
+        let dir = match env::var("RAINDROP_PROMPTS_DIR") {
+            Ok(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+            _ => default_config_dir().map(|dir| dir.join(PROMPTS_DIR)),
+        };
+        Self::new(dir, reserved)
+    }
+
+    pub fn new(dir: Option<PathBuf>, reserved: &[&str]) -> Self {
+        if let Some(dir) = &dir {
+            info!("Loading prompt templates from {}", dir.display());
+        }
+        Self {
+            dir,
+            reserved: reserved.iter().map(|name| name.to_string()).collect(),
+            loaded: Mutex::new(Arc::default()),
+        }
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Current templates, re-reading the directory if any file was added, removed or edited.
+    pub fn current(&self) -> Arc<LoadedTemplates> {
+        let stamp = self.stamp();
+        let mut loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        if loaded.stamp != stamp {
+            *loaded = Arc::new(self.load(stamp));
+        }
+        loaded.clone()
+    }
+
+    fn stamp(&self) -> Vec<(PathBuf, Option<SystemTime>, u64)> {
+        let Some(entries) = self.dir.as_ref().and_then(|dir| fs::read_dir(dir).ok()) else {
+            return vec![];
+        };
+        let mut stamp: Vec<_> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let extension = path.extension()?.to_str()?;
+                if !matches!(extension, "toml" | "yaml" | "yml") {
+                    return None;
+                }
+                let metadata = fs::metadata(&path).ok()?;
+                Some((path, metadata.modified().ok(), metadata.len()))
+            })
+            .collect();
+        stamp.sort();
+        stamp
+    }
+
+    fn load(&self, stamp: Vec<(PathBuf, Option<SystemTime>, u64)>) -> LoadedTemplates {
+        let reserved: Vec<&str> = self.reserved.iter().map(String::as_str).collect();
+        let mut templates = BTreeMap::new();
+        let mut errors = vec![];
+        for (path, _, _) in &stamp {
+            let file = path.display().to_string();
+            let result = fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| PromptTemplate::parse(path, &text))
+                .and_then(|template| {
+                    template.validate(&reserved)?;
+                    if templates.contains_key(&template.name) {
+                        return Err(format!(
+                            "name '{}' is already defined by another file",
+                            template.name
+                        ));
+                    }
+                    Ok(template)
+                });
+            match result {
+                Ok(template) => {
+                    debug!("Loaded prompt template '{}' from {}", template.name, file);
+                    templates.insert(template.name.clone(), template);
+                }
+                Err(message) => {
+                    warn!("Skipping prompt template {}: {}", file, message);
+                    errors.push(TemplateError { file, message });
+                }
+            }
+        }
+        LoadedTemplates {
+            stamp,
+            templates,
+            errors,
+        }
+    }
+}
+
+fn environment() -> Environment<'static> {
+    let mut env = Environment::new();
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+    env
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const TRIAGE: &str = r#"
+name = "triage"
+description = "Triage tagged bookmarks"
+
+[[arguments]]
+name = "collectionId"
+required = true
+
+[[arguments]]
+name = "tag"
+default = "inbox"
+
+[[data]]
+name = "items"
+source = "bookmarks"
+collection = "{{ collectionId }}"
+tag = "{{ tag }}"
+limit = 10
+
+[[messages]]
+text = "Triage {{ items | length }} bookmarks tagged {{ tag }}:{% for b in items %} {{ b.title }};{% endfor %}"
+"#;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!(
+            "raindrop-prompt-templates-{}-{name}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_resolves_bindings_and_renders() {
+        let template = PromptTemplate::parse(Path::new("triage.toml"), TRIAGE).unwrap();
+        template.validate(&["weekly-digest"]).unwrap();
+
+        let args = serde_json::json!({ "collectionId": "42" });
+        let mut context = template.context(args.as_object()).unwrap();
+        let bindings = template.resolve_bindings(&context).unwrap();
+        assert_eq!(
+            bindings,
+            vec![ResolvedBinding {
+                name: "items".to_string(),
+                source: DataSource::Bookmarks,
+                collection: Some("42".to_string()),
+                tag: Some("inbox".to_string()),
+                search: None,
+                bookmark: None,
+                sort: None,
+                limit: 10,
+            }]
+        );
+
+        context.insert(
+            "items".to_string(),
+            serde_json::json!([{ "title": "A" }, { "title": "B" }]),
+        );
+        let messages = template.render(&context, 1000).unwrap();
+        assert_eq!(
+            messages[0].content,
+            rmcp::model::PromptMessageContent::text("Triage 2 bookmarks tagged inbox: A; B;")
+        );
+
+        assert!(template.context(None).is_err());
+    }
+
+    #[test]
+    fn test_loads_directory_and_reports_errors() {
+        let dir = temp_dir("load");
+        fs::write(dir.join("triage.toml"), TRIAGE).unwrap();
+        fs::write(
+            dir.join("review.yaml"),
+            "name: review\nmessages:\n  - text: Review {{ collection.title }}\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("digest.yml"),
+            "name: weekly-digest\nmessages:\n  - text: '{% if %}'\n",
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let templates = PromptTemplates::new(Some(dir.clone()), &["weekly-digest"]);
+        let loaded = templates.current();
+        assert_eq!(loaded.templates.keys().collect::<Vec<_>>(), vec!["triage"]);
+        let errors: Vec<&str> = loaded.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("built-in prompt"), "{}", errors[0]);
+        assert!(errors[0].contains("not a valid template"), "{}", errors[0]);
+        assert!(
+            errors[1].contains("undeclared variables: collection"),
+            "{}",
+            errors[1]
+        );
+
+        // Fixing a file is picked up on the next access
+        fs::write(
+            dir.join("review.yaml"),
+            "name: review\narguments:\n  - name: collection\nmessages:\n  - text: Review {{ collection }}\n",
+        )
+        .unwrap();
+        let loaded = templates.current();
+        assert_eq!(
+            loaded.templates.keys().collect::<Vec<_>>(),
+            vec!["review", "triage"]
+        );
+        assert!(Arc::ptr_eq(&loaded, &templates.current()));
+    }
+}
@@ -0,0 +1,276 @@
+use rmcp::model::{JsonObject, PromptMessage, PromptMessageRole};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::content::approx_tokens;
+use crate::raindrop::{types::Bookmark, urls::normalize_url};
+
+/// Token budget for the data a prompt embeds, unless the caller passes `maxTokens`.
+pub const DEFAULT_PROMPT_TOKENS: usize = 8000;
+
+/// Names of the prompts built into the server, which user templates may not reuse.
+pub const BUILTIN_PROMPTS: &[&str] = &[
+    "bookmark-summary",
+    "organize-unsorted",
+    "weekly-digest",
+    "tag-suggestions",
+    "duplicate-finder",
+];
+
+// Excerpts are cut to this many characters so more bookmarks fit the budget
+const EXCERPT_CHARS: usize = 200;
+
+/// Compact view of a bookmark for embedding in prompts.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptBookmark {
+    pub id: i64,
+    pub title: String,
+    pub link: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub excerpt: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    pub collection: i64,
+    /// Creation date, without the time
+    pub created: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub important: bool,
+}
+
+impl From<&Bookmark> for PromptBookmark {
+    fn from(bookmark: &Bookmark) -> Self {
+        let full = bookmark.excerpt.as_deref().unwrap_or_default().trim();
+        let mut excerpt: String = full.chars().take(EXCERPT_CHARS).collect();
+        if excerpt.len() < full.len() {
+            excerpt.push('…');
+        }
+        Self {
+            id: bookmark.id,
+            title: bookmark.title.clone(),
+            link: bookmark.link.clone(),
+            excerpt,
+            tags: bookmark.tags.clone(),
+            collection: bookmark.collection.id,
+            created: bookmark
+                .created
+                .get(..10)
+                .unwrap_or(&bookmark.created)
+                .to_string(),
+            important: bookmark.important,
+        }
+    }
+}
+
+/// Bookmarks that are probably the same page.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    /// `url` for links that normalize to the same URL, `title` for identical titles
+    pub reason: &'static str,
+    pub key: String,
+    pub bookmarks: Vec<PromptBookmark>,
+}
+
+/// Groups bookmarks sharing a normalized URL, then different URLs sharing a title.
+pub fn find_duplicates(bookmarks: &[Bookmark]) -> Vec<DuplicateGroup> {
+    let mut by_url: BTreeMap<String, Vec<&Bookmark>> = BTreeMap::new();
+    for bookmark in bookmarks {
+        by_url
+            .entry(normalize_url(&bookmark.link))
+            .or_default()
+            .push(bookmark);
+    }
+
+    // One representative per URL, so a title group only lists distinct pages
+    let mut by_title: BTreeMap<String, Vec<&Bookmark>> = BTreeMap::new();
+    for group in by_url.values() {
+        let title = group[0].title.trim().to_lowercase();
+        if !title.is_empty() {
+            by_title.entry(title).or_default().push(group[0]);
+        }
+    }
+
+    let groups = |reason, map: BTreeMap<String, Vec<&Bookmark>>| {
+        map.into_iter()
+            .filter(|(_, group)| group.len() > 1)
+            .map(move |(key, group)| DuplicateGroup {
+                reason,
+                key,
+                bookmarks: group.into_iter().map(PromptBookmark::from).collect(),
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut duplicates = groups("url", by_url);
+    duplicates.extend(groups("title", by_title));
+    duplicates
+}
+
+/// One JSON object per line, stopping before `max_tokens` and noting how many were left out.
+pub fn render_lines<T: Serialize>(items: &[T], max_tokens: usize) -> String {
+    render_first_lines(items, items.len(), max_tokens)
+}
+
+/// Like `render_lines` for the first `items` of `total`, counting the rest as omitted.
+pub fn render_first_lines<T: Serialize>(items: &[T], total: usize, max_tokens: usize) -> String {
+    let mut out = String::new();
+    let mut used = 0;
+    let mut rendered = 0;
+    for item in items {
+        let line = serde_json::to_string(item).unwrap_or_default();
+        let cost = approx_tokens(&line) + 1;
+        if used + cost > max_tokens {
+            break;
+        }
+        used += cost;
+        rendered += 1;
+        out.push_str(&line);
+        out.push('\n');
+    }
+    if rendered < total {
+        out.push_str(&omitted_note(total - rendered));
+    }
+    out
+}
+
+/// Approximate tokens `render_lines` would need to list every one of `items`.
+pub fn lines_tokens<T: Serialize>(items: impl IntoIterator<Item = T>) -> usize {
+    items
+        .into_iter()
+        .map(|item| approx_tokens(&serde_json::to_string(&item).unwrap_or_default()) + 1)
+        .sum()
+}
+
+/// Like `render_lines`, under a `## name (count)` heading per group.
+pub fn render_grouped<T: Serialize>(groups: &[(String, Vec<T>)], max_tokens: usize) -> String {
+    let total: usize = groups.iter().map(|(_, items)| items.len()).sum();
+    let mut out = String::new();
+    let mut used = 0;
+    let mut rendered = 0;
+    'groups: for (name, items) in groups {
+        let heading = format!("## {name} ({})\n", items.len());
+        used += approx_tokens(&heading);
+        out.push_str(&heading);
+        for item in items {
+            let line = serde_json::to_string(item).unwrap_or_default();
+            let cost = approx_tokens(&line) + 1;
+            if used + cost > max_tokens {
+                break 'groups;
+            }
+            used += cost;
+            rendered += 1;
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    if rendered < total {
+        out.push_str(&omitted_note(total - rendered));
+    }
+    out
+}
+
+/// User message embedding prefetched data as a resource.
+pub fn resource_message(uri: String, text: String) -> PromptMessage {
+    PromptMessage::new_resource(
+        PromptMessageRole::User,
+        uri,
+        "text/plain".to_string(),
+        Some(text),
+        None,
+    )
+}
+
+/// Integer prompt argument, given either as a number or as a numeric string.
+pub fn int_arg(arguments: Option<&JsonObject>, name: &str) -> Option<i64> {
+    let value = arguments?.get(name)?;
+    value
+        .as_i64()
+        .or_else(|| value.as_str()?.trim().parse().ok())
+}
+
+/// `maxTokens` argument, or the default budget.
+pub fn token_budget(arguments: Option<&JsonObject>) -> usize {
+    int_arg(arguments, "maxTokens")
+        .filter(|tokens| *tokens > 0)
+        .map_or(DEFAULT_PROMPT_TOKENS, |tokens| tokens as usize)
+}
+
+fn omitted_note(count: usize) -> String {
+    format!("[{count} more omitted to fit the token budget]\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn bookmark(id: i64, title: &str, link: &str) -> Bookmark {
+        serde_json::from_value(serde_json::json!({
+            "_id": id,
+            "title": title,
+            "excerpt": "",
+            "note": "",
+            "type": "link",
+            "tags": [],
+            "cover": "",
+            "link": link,
+            "domain": "example.com",
+            "created": "2024-06-01T10:00:00.000Z",
+            "lastUpdate": "2024-06-01T10:00:00.000Z",
+            "user": { "$id": 1 },
+            "collection": { "$id": -1 },
+            "important": false,
+            "broken": false,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let bookmarks = vec![
+            bookmark(1, "Rust book", "https://doc.rust-lang.org/book/"),
+            bookmark(
+                2,
+                "The Rust Book",
+                "http://www.doc.rust-lang.org/book?utm_source=x",
+            ),
+            bookmark(3, "Tokio", "https://tokio.rs"),
+            bookmark(4, "tokio", "https://github.com/tokio-rs/tokio"),
+            bookmark(5, "Serde", "https://serde.rs"),
+        ];
+        let groups = find_duplicates(&bookmarks);
+        let summary: Vec<(&str, Vec<i64>)> = groups
+            .iter()
+            .map(|g| (g.reason, g.bookmarks.iter().map(|b| b.id).collect()))
+            .collect();
+        assert_eq!(summary, vec![("url", vec![1, 2]), ("title", vec![4, 3])]);
+    }
+
+    #[test]
+    fn test_render_respects_token_budget() {
+        let items: Vec<PromptBookmark> = (0..50)
+            .map(|id| PromptBookmark::from(&bookmark(id, "Title", "https://example.com")))
+            .collect();
+        let text = render_lines(&items, 100);
+        assert!(approx_tokens(&text) <= 120);
+        assert!(text.ends_with("more omitted to fit the token budget]\n"));
+
+        // Bookmarks that were never fetched count as omitted too
+        let text = render_first_lines(&items[..2], 40, 1000);
+        assert!(text.ends_with("[38 more omitted to fit the token budget]\n"));
+        assert!(lines_tokens(&items[..2]) <= 1000);
+        assert!(lines_tokens(&items) > 100);
+
+        let groups = vec![("Unsorted".to_string(), items[..2].to_vec())];
+        let text = render_grouped(&groups, 1000);
+        assert!(text.starts_with("## Unsorted (2)\n{\"id\":0,"));
+        assert!(!text.contains("omitted"));
+
+        let args = serde_json::json!({ "maxTokens": "500", "limit": 20 });
+        let args = args.as_object();
+        assert_eq!(token_budget(args), 500);
+        assert_eq!(int_arg(args, "limit"), Some(20));
+        assert_eq!(token_budget(None), DEFAULT_PROMPT_TOKENS);
+    }
+}
@@ -0,0 +1,537 @@
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use rmcp::model::{ErrorData as McpError, RawResourceTemplate, ResourceTemplate};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+const SCHEME: &str = "raindrop://";
+
+/// Resources returned per `resources/list` page.
+pub const RESOURCE_PAGE_SIZE: usize = 50;
+
+/// How long the later pages of a `resources/list` snapshot stay available.
+pub const LISTING_TTL: Duration = Duration::from_secs(300);
+
+// Snapshots kept at once; the oldest is dropped beyond this
+const MAX_LISTINGS: usize = 16;
+
+const CURSOR_PREFIX: &str = "listing:";
+
+/// Sort orders accepted by the Raindrop bookmarks endpoint.
+pub const BOOKMARK_SORTS: &[&str] = &[
+    "-created", "created", "score", "-sort", "title", "-title", "domain", "-domain",
+];
+
+/// Why a resource URI could not be routed.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ResourceUriError {
+    #[error("Resource URIs must start with {SCHEME}, got '{0}'")]
+    UnsupportedScheme(String),
+
+    #[error("Unknown resource: {0}")]
+    UnknownResource(String),
+
+    #[error("Invalid {what} '{value}' in {uri}")]
+    InvalidSegment {
+        uri: String,
+        what: &'static str,
+        value: String,
+    },
+
+    #[error("Empty {what} in {uri}")]
+    EmptySegment { uri: String, what: &'static str },
+
+    #[error("Invalid value '{value}' for query parameter '{name}' in {uri}")]
+    InvalidQuery {
+        uri: String,
+        name: String,
+        value: String,
+    },
+
+    #[error("Unsupported query parameter '{name}' in {uri}")]
+    UnsupportedQuery { uri: String, name: String },
+
+    #[error("Invalid resource list cursor: {0}")]
+    InvalidCursor(String),
+}
+
+impl From<ResourceUriError> for McpError {
+    fn from(err: ResourceUriError) -> Self {
+        match err {
+            ResourceUriError::UnknownResource(_) => {
+                McpError::resource_not_found(err.to_string(), None)
+            }
+            _ => McpError::invalid_params(err.to_string(), None),
+        }
+    }
+}
+
+/// A parsed `raindrop://` resource URI.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResourceRoute {
+    AllCollections,
+    AllTags,
+    AllHighlights,
+    UserInfo,
+    UserStats,
+    UpcomingReminders,
+    Dashboard,
+    Collection(i64),
+    CollectionBookmarks {
+        id: i64,
+        page: Option<i32>,
+        sort: Option<String>,
+    },
+    Bookmark(i64),
+    BookmarkHighlights(i64),
+    TagBookmarks(String),
+    Search(String),
+}
+
+impl ResourceRoute {
+    /// Routes a resource URI, percent-decoding path segments and query values.
+    pub fn parse(uri: &str) -> Result<Self, ResourceUriError> {
+        let rest = uri
+            .strip_prefix(SCHEME)
+            .ok_or_else(|| ResourceUriError::UnsupportedScheme(uri.to_string()))?;
+        let (path, query) = match rest.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (rest, None),
+        };
+        let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+
+        let route = match segments.as_slice() {
+            ["collections", "all"] => Self::AllCollections,
+            ["tags", "all"] => Self::AllTags,
+            ["highlights", "all"] => Self::AllHighlights,
+            ["user", "info"] => Self::UserInfo,
+            ["user", "stats"] => Self::UserStats,
+            ["reminders", "upcoming"] => Self::UpcomingReminders,
+            ["stats", "dashboard"] => Self::Dashboard,
+            ["collection", id] => Self::Collection(parse_id(uri, "collection ID", id)?),
+            ["collection", id, "bookmarks"] => {
+                let id = parse_id(uri, "collection ID", id)?;
+                let mut page = None;
+                let mut sort = None;
+                for (name, value) in query_pairs(uri, query)? {
+                    match name.as_str() {
+                        "page" => {
+                            page = Some(value.parse::<i32>().ok().filter(|p| *p >= 0).ok_or(
+                                ResourceUriError::InvalidQuery {
+                                    uri: uri.to_string(),
+                                    name,
+                                    value,
+                                },
+                            )?)
+                        }
+                        "sort" if BOOKMARK_SORTS.contains(&value.as_str()) => sort = Some(value),
+                        "sort" => {
+                            return Err(ResourceUriError::InvalidQuery {
+                                uri: uri.to_string(),
+                                name,
+                                value,
+                            });
+                        }
+                        _ => {
+                            return Err(ResourceUriError::UnsupportedQuery {
+                                uri: uri.to_string(),
+                                name,
+                            });
+                        }
+                    }
+                }
+                return Ok(Self::CollectionBookmarks { id, page, sort });
+            }
+            ["bookmark", id] => Self::Bookmark(parse_id(uri, "bookmark ID", id)?),
+            ["bookmark", id, "highlights"] => {
+                Self::BookmarkHighlights(parse_id(uri, "bookmark ID", id)?)
+            }
+            ["tag", name, "bookmarks"] => Self::TagBookmarks(decode(uri, "tag name", name)?),
+            ["search", search] => Self::Search(decode(uri, "search query", search)?),
+            _ => return Err(ResourceUriError::UnknownResource(uri.to_string())),
+        };
+
+        // Only the paginated bookmark listing takes query parameters
+        if let Some((name, _)) = query_pairs(uri, query)?.into_iter().next() {
+            return Err(ResourceUriError::UnsupportedQuery {
+                uri: uri.to_string(),
+                name,
+            });
+        }
+        Ok(route)
+    }
+
+    /// Canonical URI of the route; `parse(route.uri())` yields the route again.
+    pub fn uri(&self) -> String {
+        let path = match self {
+            Self::AllCollections => "collections/all".to_string(),
+            Self::AllTags => "tags/all".to_string(),
+            Self::AllHighlights => "highlights/all".to_string(),
+            Self::UserInfo => "user/info".to_string(),
+            Self::UserStats => "user/stats".to_string(),
+            Self::UpcomingReminders => "reminders/upcoming".to_string(),
+            Self::Dashboard => "stats/dashboard".to_string(),
+            Self::Collection(id) => format!("collection/{id}"),
+            Self::CollectionBookmarks { id, page, sort } => {
+                let mut query = vec![];
+                if let Some(page) = page {
+                    query.push(format!("page={page}"));
+                }
+                if let Some(sort) = sort {
+                    query.push(format!("sort={}", urlencoding::encode(sort)));
+                }
+                let query = if query.is_empty() {
+                    String::new()
+                } else {
+                    format!("?{}", query.join("&"))
+                };
+                format!("collection/{id}/bookmarks{query}")
+            }
+            Self::Bookmark(id) => format!("bookmark/{id}"),
+            Self::BookmarkHighlights(id) => format!("bookmark/{id}/highlights"),
+            Self::TagBookmarks(name) => format!("tag/{}/bookmarks", urlencoding::encode(name)),
+            Self::Search(query) => format!("search/{}", urlencoding::encode(query)),
+        };
+        format!("{SCHEME}{path}")
+    }
+}
+
+/// Newest bookmarks listed as resources, from `RAINDROP_RESOURCE_RECENT_BOOKMARKS` (default none).
+pub fn recent_bookmarks_listed() -> usize {
+    std::env::var("RAINDROP_RESOURCE_RECENT_BOOKMARKS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Opaque `resources/list` cursor pointing at `offset` within snapshot `listing`.
+pub fn encode_cursor(listing: u64, offset: usize) -> String {
+    BASE64_URL_SAFE_NO_PAD.encode(format!("{CURSOR_PREFIX}{listing}:{offset}"))
+}
+
+pub fn decode_cursor(cursor: &str) -> Result<(u64, usize), ResourceUriError> {
+    BASE64_URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|text| {
+            let (listing, offset) = text.strip_prefix(CURSOR_PREFIX)?.split_once(':')?;
+            Some((listing.parse().ok()?, offset.parse().ok()?))
+        })
+        .ok_or_else(|| ResourceUriError::InvalidCursor(cursor.to_string()))
+}
+
+/// The page of `items` starting at `offset`, and the offset of the next page if any.
+pub fn paginate<T: Clone>(
+    items: &[T],
+    offset: usize,
+    page_size: usize,
+) -> Option<(Vec<T>, Option<usize>)> {
+    if offset > items.len() {
+        return None;
+    }
+    let end = offset.saturating_add(page_size).min(items.len());
+    Some((
+        items[offset..end].to_vec(),
+        (end < items.len()).then_some(end),
+    ))
+}
+
+/// Snapshots of `resources/list` results, so every page of one listing comes from the same
+/// fetch: nothing is skipped or repeated when the library changes between pages.
+#[derive(Debug)]
+pub struct Listings<T> {
+    entries: Mutex<HashMap<u64, Listing<T>>>,
+    next_id: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Listing<T> {
+    created: Instant,
+    items: Arc<Vec<T>>,
+}
+
+impl<T> Default for Listings<T> {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+}
+
+impl<T> Listings<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps `items` for later pages, returning the snapshot's ID.
+    pub fn insert(&self, items: Arc<Vec<T>>) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut entries = self.lock();
+        entries.retain(|_, listing| listing.created.elapsed() < LISTING_TTL);
+        if entries.len() >= MAX_LISTINGS {
+            if let Some(&oldest) = entries
+                .iter()
+                .min_by_key(|(_, listing)| listing.created)
+                .map(|(id, _)| id)
+            {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            id,
+            Listing {
+                created: Instant::now(),
+                items,
+            },
+        );
+        id
+    }
+
+    /// Snapshot `id`, unless it has expired.
+    pub fn get(&self, id: u64) -> Option<Arc<Vec<T>>> {
+        self.lock()
+            .get(&id)
+            .filter(|listing| listing.created.elapsed() < LISTING_TTL)
+            .map(|listing| listing.items.clone())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u64, Listing<T>>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Resource templates for the parameterized routes.
+pub fn resource_templates() -> Vec<ResourceTemplate> {
+    [
+        (
+            "raindrop://collection/{id}",
+            "Collection",
+            "A single collection by ID",
+        ),
+        (
+            "raindrop://collection/{id}/bookmarks{?page,sort}",
+            "Collection Bookmarks",
+            "One page of bookmarks in a collection (0 = all, -1 = Unsorted, -99 = Trash). \
+             sort is one of -created, created, score, -sort, title, -title, domain, -domain",
+        ),
+        (
+            "raindrop://bookmark/{id}",
+            "Bookmark",
+            "A single bookmark by ID",
+        ),
+        (
+            "raindrop://bookmark/{id}/highlights",
+            "Bookmark Highlights",
+            "Highlights of a single bookmark",
+        ),
+        (
+            "raindrop://tag/{name}/bookmarks",
+            "Tagged Bookmarks",
+            "Bookmarks carrying a tag",
+        ),
+        (
+            "raindrop://search/{query}",
+            "Search",
+            "Bookmarks matching a Raindrop search query",
+        ),
+    ]
+    .into_iter()
+    .map(|(uri_template, name, description)| {
+        ResourceTemplate::new(
+            RawResourceTemplate {
+                uri_template: uri_template.into(),
+                name: name.into(),
+                description: Some(description.into()),
+                mime_type: Some("application/json".into()),
+            },
+            None,
+        )
+    })
+    .collect()
+}
+
+fn decode(uri: &str, what: &'static str, segment: &str) -> Result<String, ResourceUriError> {
+    let decoded = urlencoding::decode(segment).map_err(|_| ResourceUriError::InvalidSegment {
+        uri: uri.to_string(),
+        what,
+        value: segment.to_string(),
+    })?;
+    if decoded.trim().is_empty() {
+        return Err(ResourceUriError::EmptySegment {
+            uri: uri.to_string(),
+            what,
+        });
+    }
+    Ok(decoded.into_owned())
+}
+
+fn parse_id(uri: &str, what: &'static str, segment: &str) -> Result<i64, ResourceUriError> {
+    decode(uri, what, segment)?
+        .parse()
+        .map_err(|_| ResourceUriError::InvalidSegment {
+            uri: uri.to_string(),
+            what,
+            value: segment.to_string(),
+        })
+}
+
+fn query_pairs(uri: &str, query: Option<&str>) -> Result<Vec<(String, String)>, ResourceUriError> {
+    query
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = urlencoding::decode(value).map_err(|_| ResourceUriError::InvalidQuery {
+                uri: uri.to_string(),
+                name: name.to_string(),
+                value: value.to_string(),
+            })?;
+            Ok((name.to_string(), value.into_owned()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_routes_templates_and_fixed_uris() {
+        assert_eq!(
+            ResourceRoute::parse("raindrop://tags/all"),
+            Ok(ResourceRoute::AllTags)
+        );
+        assert_eq!(
+            ResourceRoute::parse("raindrop://collection/-1"),
+            Ok(ResourceRoute::Collection(-1))
+        );
+        assert_eq!(
+            ResourceRoute::parse("raindrop://collection/42/bookmarks?page=2&sort=-created"),
+            Ok(ResourceRoute::CollectionBookmarks {
+                id: 42,
+                page: Some(2),
+                sort: Some("-created".to_string()),
+            })
+        );
+        assert_eq!(
+            ResourceRoute::parse("raindrop://collection/42/bookmarks"),
+            Ok(ResourceRoute::CollectionBookmarks {
+                id: 42,
+                page: None,
+                sort: None,
+            })
+        );
+        assert_eq!(
+            ResourceRoute::parse("raindrop://bookmark/7/highlights"),
+            Ok(ResourceRoute::BookmarkHighlights(7))
+        );
+        assert_eq!(
+            ResourceRoute::parse("raindrop://tag/machine%20learning/bookmarks"),
+            Ok(ResourceRoute::TagBookmarks("machine learning".to_string()))
+        );
+        assert_eq!(
+            ResourceRoute::parse("raindrop://search/rust%20%23async"),
+            Ok(ResourceRoute::Search("rust #async".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_rejects_malformed_uris() {
+        let uri = "raindrop://collection/abc";
+        assert_eq!(
+            ResourceRoute::parse(uri),
+            Err(ResourceUriError::InvalidSegment {
+                uri: uri.to_string(),
+                what: "collection ID",
+                value: "abc".to_string(),
+            })
+        );
+        assert!(matches!(
+            ResourceRoute::parse("https://raindrop.io/bookmark/1"),
+            Err(ResourceUriError::UnsupportedScheme(_))
+        ));
+        assert!(matches!(
+            ResourceRoute::parse("raindrop://bookmark/1/notes"),
+            Err(ResourceUriError::UnknownResource(_))
+        ));
+        assert!(matches!(
+            ResourceRoute::parse("raindrop://tag//bookmarks"),
+            Err(ResourceUriError::EmptySegment { .. })
+        ));
+        assert!(matches!(
+            ResourceRoute::parse("raindrop://collection/1/bookmarks?page=-3"),
+            Err(ResourceUriError::InvalidQuery { .. })
+        ));
+        assert!(matches!(
+            ResourceRoute::parse("raindrop://collection/1/bookmarks?sort=newest"),
+            Err(ResourceUriError::InvalidQuery { .. })
+        ));
+        assert!(matches!(
+            ResourceRoute::parse("raindrop://bookmark/1?page=2"),
+            Err(ResourceUriError::UnsupportedQuery { .. })
+        ));
+
+        let err: McpError = ResourceRoute::parse("raindrop://nope").unwrap_err().into();
+        assert_eq!(err.code, McpError::resource_not_found("", None).code);
+    }
+
+    #[test]
+    fn test_uri_round_trips() {
+        for route in [
+            ResourceRoute::Dashboard,
+            ResourceRoute::Collection(-1),
+            ResourceRoute::CollectionBookmarks {
+                id: 3,
+                page: Some(1),
+                sort: Some("-title".to_string()),
+            },
+            ResourceRoute::TagBookmarks("c++/std lib".to_string()),
+            ResourceRoute::Search("#rust async?".to_string()),
+        ] {
+            assert_eq!(ResourceRoute::parse(&route.uri()), Ok(route));
+        }
+    }
+
+    #[test]
+    fn test_paginate_with_opaque_cursors() {
+        let items: Vec<i32> = (0..5).collect();
+        assert_eq!(paginate(&items, 0, 2), Some((vec![0, 1], Some(2))));
+        assert_eq!(paginate(&items, 2, 2), Some((vec![2, 3], Some(4))));
+        assert_eq!(paginate(&items, 4, 2), Some((vec![4], None)));
+        assert_eq!(paginate(&items, 9, 2), None);
+
+        let cursor = encode_cursor(7, 2);
+        assert_eq!(decode_cursor(&cursor), Ok((7, 2)));
+        assert!(matches!(
+            decode_cursor("not-a-cursor"),
+            Err(ResourceUriError::InvalidCursor(_))
+        ));
+    }
+
+    #[test]
+    fn test_listings_keep_snapshots() {
+        let listings = Listings::new();
+        let first = listings.insert(Arc::new(vec![1, 2, 3]));
+        let second = listings.insert(Arc::new(vec![4]));
+        assert_ne!(first, second);
+        assert_eq!(listings.get(first).as_deref(), Some(&vec![1, 2, 3]));
+        assert_eq!(listings.get(second).as_deref(), Some(&vec![4]));
+        assert_eq!(listings.get(99), None);
+
+        // Only the newest snapshots are kept
+        for _ in 0..MAX_LISTINGS {
+            listings.insert(Arc::new(vec![]));
+        }
+        assert_eq!(listings.get(first), None);
+    }
+}
@@ -1,31 +1,115 @@
+use base64::{Engine, prelude::BASE64_STANDARD};
+use chrono::{DateTime, NaiveDate, Utc};
 use rmcp::{
     RoleServer,
     handler::server::{
         ServerHandler,
-        tool::{Parameters, ToolRouter},
+        tool::{Parameters, ToolCallContext, ToolRouter},
     },
     model::ErrorData as McpError,
     model::*,
-    service::RequestContext,
-    tool, tool_handler, tool_router,
+    service::{Peer, RequestContext},
+    tool, tool_router,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::future::Future;
 use std::sync::Arc;
-use tracing::{debug, info};
+use std::time::Duration;
+use tracing::{debug, info, warn};
 
 use crate::{
+    config::{Config, Profile},
+    content::{
+        self, ContentFormat, DEFAULT_MAX_TOKENS,
+        extract::{extract_article, lead_summary},
+        fetcher::PageFetcher,
+    },
     error::RaindropMcpError,
-    raindrop::{client::RaindropClient, types::SearchParams},
+    mcp::{
+        completion::{self, Candidate, CompletionCache, CompletionKind, CompletionSource},
+        confirmation::{ConfirmationStore, DestructivePreview, PREVIEW_TITLE_LIMIT},
+        prompt_templates::{DataSource, PromptTemplates, ResolvedBinding},
+        prompts::{self, PromptBookmark},
+        resources::{self, Listings, ResourceRoute},
+        subscriptions::{self, Subscriptions},
+    },
+    raindrop::{
+        analytics,
+        client::{MAX_PER_PAGE, MAX_UPLOAD_BYTES, RaindropClient},
+        tags::{self, TagAliases},
+        types::{
+            Bookmark, BookmarkSelection, BulkCreateStatus, BulkUpdate, CacheStatus, Collection,
+            CollectionRef, NewBookmark, ParsedUrl, SearchParams, TRASH_COLLECTION_ID,
+            UNSORTED_COLLECTION_ID,
+        },
+    },
 };
 
+// Length limit for summaries saved to bookmark notes
+const SUMMARY_MAX_CHARS: usize = 300;
+
+// Tags, pairs and domains listed by the analytics tools unless the caller asks otherwise
+const DEFAULT_ANALYTICS_TOP: usize = 20;
+
 #[derive(Clone)]
 pub struct McpServer {
     client: Arc<RaindropClient>,
+    confirmations: Arc<ConfirmationStore>,
+    fetcher: Arc<PageFetcher>,
+    tag_aliases: Arc<TagAliases>,
+    subscriptions: Arc<Subscriptions>,
+    resource_listings: Arc<Listings<Resource>>,
+    prompt_templates: Arc<PromptTemplates>,
+    completions: Arc<CompletionCache>,
+    /// Profile the fields above act as
+    profile: String,
+    accounts: Arc<BTreeMap<String, Account>>,
+    /// Whether uploads may name a file on this machine, rather than only send its content
+    local_files: bool,
     tool_router: ToolRouter<Self>,
 }
 
+/// Per-profile state, swapped in when a tool call names a `profile`.
+#[derive(Clone)]
+struct Account {
+    settings: Profile,
+    client: Arc<RaindropClient>,
+    /// Tokens previewed on this account, so they cannot confirm a change to another
+    confirmations: Arc<ConfirmationStore>,
+    fetcher: Arc<PageFetcher>,
+    tag_aliases: Arc<TagAliases>,
+    completions: Arc<CompletionCache>,
+}
+
+impl Account {
+    fn open(name: &str, settings: &Profile) -> std::result::Result<Self, RaindropMcpError> {
+        Ok(Self {
+            settings: settings.clone(),
+            client: Arc::new(RaindropClient::from_profile(name, settings)?),
+            confirmations: Arc::new(ConfirmationStore::default()),
+            fetcher: Arc::new(page_fetcher(settings)?),
+            tag_aliases: Arc::new(TagAliases::open_for_profile(name).unwrap_or_else(|e| {
+                warn!("Tag aliases of profile '{}' will not be saved: {}", name, e);
+                TagAliases::in_memory()
+            })),
+            completions: Arc::new(CompletionCache::new(Duration::from_secs(
+                settings.cache.completion_ttl_secs,
+            ))),
+        })
+    }
+}
+
+/// Page fetcher following a profile's `cache` settings.
+fn page_fetcher(settings: &Profile) -> std::result::Result<PageFetcher, RaindropMcpError> {
+    match (&settings.cache.page_dir, settings.cache.pages) {
+        (_, false) => PageFetcher::new(None),
+        (Some(dir), true) => PageFetcher::new(Some(dir.clone())),
+        (None, true) => PageFetcher::with_default_cache(),
+    }
+}
+
 // Parameter structures for tools
 #[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
@@ -53,10 +137,27 @@ struct UpdateCollectionParams {
     sort: Option<String>,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GetCollectionParams {
+    id: i64,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct DeleteCollectionParams {
     id: i64,
+    /// Token returned by a previous call without it, once the user has confirmed the preview
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confirmation_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct EmptyTrashParams {
+    /// Token returned by a previous call without it, once the user has confirmed the preview
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confirmation_token: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -91,7 +192,9 @@ struct GetBookmarkParams {
 #[serde(rename_all = "camelCase")]
 struct CreateBookmarkParams {
     link: String,
-    title: String,
+    /// Defaults to the title parsed from the page, which also fills excerpt, cover and type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     collection: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -99,10 +202,52 @@ struct CreateBookmarkParams {
     #[serde(skip_serializing_if = "Option::is_none")]
     excerpt: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[allow(dead_code)]
     note: Option<String>,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct CreateBookmarksParams {
+    items: Vec<BulkBookmarkItem>,
+    /// Collection for items that do not set their own (default: the profile's default collection, or Unsorted)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<i64>,
+    /// Report links that are already saved as duplicates instead of saving them again (default: true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skip_duplicates: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct BulkBookmarkItem {
+    link: String,
+    /// Defaults to the title Raindrop parses from the page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    excerpt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    important: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ParseUrlParams {
+    url: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct CheckUrlsExistParams {
+    urls: Vec<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct UpdateBookmarkParams {
@@ -125,6 +270,123 @@ struct UpdateBookmarkParams {
 #[serde(rename_all = "camelCase")]
 struct DeleteBookmarkParams {
     id: i64,
+    /// Delete permanently instead of moving to the trash (requires confirmation)
+    #[serde(default)]
+    permanent: bool,
+    /// Token returned by a previous permanent delete call, once the user has confirmed the preview
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confirmation_token: Option<String>,
+}
+
+// Trash parameters
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GetTrashParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    per_page: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct RestoreBookmarksParams {
+    ids: Vec<i64>,
+    /// Target collection; defaults to the collection each bookmark was trashed from, or Unsorted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<i64>,
+}
+
+// Upload parameters
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct UploadFileParams {
+    /// Path of a local file to upload
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    /// Base64-encoded file content, used instead of path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    /// File name to store; required with content, defaults to the name in path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_name: Option<String>,
+    /// Target collection (default: the profile's default collection, or Unsorted)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct UploadCoverParams {
+    /// Bookmark whose cover is replaced
+    id: i64,
+    /// Path of a local image to upload
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    /// Base64-encoded image content, used instead of path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    /// File name to store; required with content, defaults to the name in path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_name: Option<String>,
+}
+
+// Page content parameters
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GetCachedCopyParams {
+    id: i64,
+    /// Output format: markdown (default) or text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<ContentFormat>,
+    /// Approximate token budget for the returned content (default: 4000)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct FetchBookmarkContentParams {
+    id: i64,
+    /// Output format: markdown (default) or text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<ContentFormat>,
+    /// Approximate token budget for the returned content (default: 4000)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<usize>,
+    /// Append a short summary of the article to the bookmark's note
+    #[serde(skip_serializing_if = "Option::is_none")]
+    save_summary: Option<bool>,
+    /// Summary to save instead of the extracted lead sentences
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+}
+
+// Reminder parameters
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct SetReminderParams {
+    id: i64,
+    /// When to be reminded, as an RFC 3339 timestamp or a YYYY-MM-DD date (UTC midnight)
+    date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ClearReminderParams {
+    id: i64,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ListUpcomingRemindersParams {
+    /// Only include reminders at or after this date (default: now)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    after: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<usize>,
 }
 
 // Tag management parameters
@@ -140,18 +402,122 @@ struct GetTagsParams {
 struct RenameTagParams {
     old_name: String,
     new_name: String,
+    /// Only rename within this collection, given by ID or name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<CollectionArg>,
+    /// Only report how many bookmarks in scope carry the tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dry_run: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct DeleteTagParams {
     name: String,
+    /// Only delete within this collection, given by ID or name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<CollectionArg>,
+    /// Only report how many bookmarks in scope carry the tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dry_run: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct DeleteTagsParams {
     names: Vec<String>,
+    /// Only delete within this collection, given by ID or name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<CollectionArg>,
+    /// Only report how many bookmarks in scope carry each tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dry_run: Option<bool>,
+}
+
+/// Collection given by numeric ID or by name
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum CollectionArg {
+    Id(i64),
+    Name(String),
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct MergeTagsParams {
+    /// Tags to merge away
+    tags: Vec<String>,
+    /// Tag that replaces them
+    into: String,
+    /// Only merge within this collection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<i64>,
+    /// Token returned by a previous call without it, once the user has confirmed the preview
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confirmation_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct SuggestTagMergesParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<i64>,
+    /// Largest edit distance for spelling suggestions (default: 1, or 2 for long tags)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_distance: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GetTagTreeParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<i64>,
+    /// Hierarchy separator (default: "/")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    separator: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct TagAnalyticsParams {
+    /// Only analyze bookmarks in this collection, given by ID or name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<CollectionArg>,
+    /// Number of top tags and pairs to report (default: 20)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct LibraryDashboardParams {
+    /// Only include bookmarks in this collection, given by ID or name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<CollectionArg>,
+    /// Number of top domains to report (default: 20)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct SetTagAliasParams {
+    alias: String,
+    /// Tag the alias stands for
+    canonical: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct RemoveTagAliasParams {
+    alias: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ApplyTagAliasesParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<i64>,
 }
 
 // Search parameters
@@ -178,19 +544,47 @@ struct SearchBookmarksParams {
 #[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct BatchUpdateBookmarksParams {
-    ids: Vec<i64>,
+    /// Bookmarks to update; use either ids or search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ids: Option<Vec<i64>>,
+    /// Search filter selecting the bookmarks to update, e.g. "#old domain:example.com"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    search: Option<String>,
+    /// Collection the selected bookmarks are in (default: all collections)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    in_collection: Option<i64>,
+    /// Collection to move the bookmarks to
     #[serde(skip_serializing_if = "Option::is_none")]
     collection: Option<i64>,
+    /// Tags to add, keeping existing ones
     #[serde(skip_serializing_if = "Option::is_none")]
-    tags: Option<Vec<String>>,
+    add_tags: Option<Vec<String>>,
+    /// Tags to remove, keeping the others
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remove_tags: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     important: Option<bool>,
+    /// Cover image URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cover: Option<String>,
+    /// Media links to append; an empty list removes all media
+    #[serde(skip_serializing_if = "Option::is_none")]
+    media: Option<Vec<String>>,
+    /// Text appended to each bookmark's note
+    #[serde(skip_serializing_if = "Option::is_none")]
+    append_note: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct BatchDeleteBookmarksParams {
     ids: Vec<i64>,
+    /// Delete permanently instead of moving to the trash (requires confirmation)
+    #[serde(default)]
+    permanent: bool,
+    /// Token returned by a previous permanent delete call, once the user has confirmed the preview
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confirmation_token: Option<String>,
 }
 
 // Highlight parameters
@@ -204,6 +598,20 @@ struct GetHighlightsParams {
     collection_id: Option<i64>,
 }
 
+// Undo journal parameters
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ListOperationsParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct UndoOperationParams {
+    id: u64,
+}
+
 // Export parameters
 #[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
@@ -229,10 +637,7 @@ impl McpServer {
     }
 
     #[tool(description = "Gets a specific collection by ID")]
-    async fn get_collection(
-        &self,
-        Parameters(params): Parameters<DeleteCollectionParams>,
-    ) -> String {
+    async fn get_collection(&self, Parameters(params): Parameters<GetCollectionParams>) -> String {
         debug!("Getting collection: {}", params.id);
         match self.client.get_collection(params.id).await {
             Ok(collection) => serde_json::to_string(&collection)
@@ -290,11 +695,23 @@ impl McpServer {
         }
     }
 
-    #[tool(description = "Deletes a collection")]
+    #[tool(
+        description = "Deletes a collection. The first call returns a preview and a confirmationToken; \
+                       call again with the token only after the user has confirmed"
+    )]
     async fn delete_collection(
         &self,
         Parameters(params): Parameters<DeleteCollectionParams>,
     ) -> String {
+        let operation = format!("delete_collection:{}", params.id);
+        let Some(token) = params.confirmation_token else {
+            let preview = self.preview_delete_collection(params.id).await;
+            return self.request_confirmation(&operation, preview);
+        };
+        if let Err(e) = self.confirmations.redeem(&token, &operation) {
+            return format!(r#"{{"error": "{e}"}}"#);
+        }
+
         info!("Deleting collection: {}", params.id);
         match self.client.delete_collection(params.id).await {
             Ok(_) => serde_json::json!({"success": true}).to_string(),
@@ -350,26 +767,150 @@ impl McpServer {
         }
     }
 
-    #[tool(description = "Creates a new bookmark")]
+    #[tool(
+        description = "Creates a new bookmark. Without a title, the title, excerpt, cover and \
+                       type are filled from the parsed page"
+    )]
     async fn create_bookmark(
         &self,
         Parameters(params): Parameters<CreateBookmarkParams>,
     ) -> String {
         info!("Creating bookmark: {}", params.link);
+        let mut bookmark = NewBookmark {
+            link: params.link,
+            collection: Some(CollectionRef {
+                id: params
+                    .collection
+                    .unwrap_or_else(|| self.client.default_collection()),
+            }),
+            title: params.title,
+            excerpt: params.excerpt,
+            note: params.note,
+            tags: params.tags,
+            ..Default::default()
+        };
+        if bookmark.title.is_none() {
+            match self.parse_link(&bookmark.link).await {
+                Ok((parsed, _)) => bookmark.apply_metadata(&parsed),
+                // Raindrop still falls back to the link itself
+                Err(e) => warn!("Could not parse metadata for {}: {}", bookmark.link, e),
+            }
+        }
+
+        match self.client.create_bookmark_from(bookmark).await {
+            Ok(bookmark) => serde_json::to_string(&bookmark)
+                .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
+
+    #[tool(
+        description = "Create many bookmarks at once, each with its own collection, tags, note \
+                       and important flag. Reports per-item created, duplicate or failed status"
+    )]
+    async fn create_bookmarks(
+        &self,
+        Parameters(params): Parameters<CreateBookmarksParams>,
+    ) -> String {
+        info!("Creating {} bookmarks", params.items.len());
+        if params.items.is_empty() {
+            return r#"{"error": "items must not be empty"}"#.to_string();
+        }
+        let default_collection = params
+            .collection
+            .unwrap_or_else(|| self.client.default_collection());
+        let items = params
+            .items
+            .into_iter()
+            .map(|item| NewBookmark {
+                please_parse: item.title.is_none().then(|| serde_json::json!({})),
+                link: item.link,
+                collection: Some(CollectionRef {
+                    id: item.collection.unwrap_or(default_collection),
+                }),
+                title: item.title,
+                excerpt: item.excerpt,
+                note: item.note,
+                tags: item.tags,
+                important: item.important,
+                ..Default::default()
+            })
+            .collect();
+
         match self
             .client
-            .create_bookmark(
-                params.link,
-                params.collection.unwrap_or(0), // Default to "All" collection
-                Some(params.title),
-                params.excerpt,
-                params.tags,
-                None, // important field not included in params
-            )
+            .create_bookmarks(items, params.skip_duplicates.unwrap_or(true))
             .await
         {
-            Ok(bookmark) => serde_json::to_string(&bookmark)
-                .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
+            Ok(results) => {
+                let count = |status: BulkCreateStatus| {
+                    results.iter().filter(|r| r.status == status).count()
+                };
+                serde_json::json!({
+                    "created": count(BulkCreateStatus::Created),
+                    "duplicates": count(BulkCreateStatus::Duplicate),
+                    "failed": count(BulkCreateStatus::Failed),
+                    "results": results,
+                })
+                .to_string()
+            }
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
+
+    #[tool(
+        description = "Parse a URL's title, excerpt, cover, media and type before saving it, \
+                       and report whether it is already in the library"
+    )]
+    async fn parse_url(&self, Parameters(params): Parameters<ParseUrlParams>) -> String {
+        debug!("Parsing URL: {}", params.url);
+        let (parsed, source) = match self.parse_link(&params.url).await {
+            Ok(result) => result,
+            Err(e) => return format!(r#"{{"error": "{e}"}}"#),
+        };
+
+        let mut result = serde_json::json!({
+            "url": params.url,
+            "source": source,
+            "metadata": parsed,
+        });
+        match self.client.exists(std::slice::from_ref(&params.url)).await {
+            Ok(existence) => {
+                let ids = existence
+                    .into_iter()
+                    .next()
+                    .map(|e| e.ids)
+                    .unwrap_or_default();
+                result["exists"] = (!ids.is_empty()).into();
+                result["existingIds"] = ids.into();
+            }
+            Err(e) => {
+                result["exists"] = serde_json::Value::Null;
+                result["existsError"] = e.to_string().into();
+            }
+        }
+        result.to_string()
+    }
+
+    #[tool(
+        description = "Check which of a batch of URLs are already saved, returning the matching \
+                       bookmark IDs per URL. Trivially different URLs (scheme, www, trailing \
+                       slash, tracking parameters) still match"
+    )]
+    async fn check_urls_exist(
+        &self,
+        Parameters(params): Parameters<CheckUrlsExistParams>,
+    ) -> String {
+        debug!("Checking {} URLs", params.urls.len());
+        if params.urls.is_empty() {
+            return r#"{"error": "urls must not be empty"}"#.to_string();
+        }
+        match self.client.exists(&params.urls).await {
+            Ok(results) => serde_json::json!({
+                "existingCount": results.iter().filter(|r| r.exists).count(),
+                "results": results,
+            })
+            .to_string(),
             Err(e) => format!(r#"{{"error": "{e}"}}"#),
         }
     }
@@ -396,16 +937,40 @@ impl McpServer {
         }
     }
 
-    #[tool(description = "Deletes a bookmark")]
+    #[tool(
+        description = "Deletes a bookmark by moving it to the trash, or permanently with \
+                       permanent=true. Permanent deletes return a preview and a \
+                       confirmationToken first; call again with the token only after the user \
+                       has confirmed"
+    )]
     async fn delete_bookmark(
         &self,
         Parameters(params): Parameters<DeleteBookmarkParams>,
     ) -> String {
-        info!("Deleting bookmark: {}", params.id);
-        match self.client.delete_bookmark(params.id, false).await {
-            // false = move to trash, not permanent
-            Ok(_) => serde_json::json!({"success": true}).to_string(),
-            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        if params.permanent {
+            let operation = format!("delete_bookmark:{}:permanent", params.id);
+            let Some(token) = params.confirmation_token else {
+                let preview = self
+                    .preview_permanent_delete(&operation, &[params.id])
+                    .await;
+                return self.request_confirmation(&operation, preview);
+            };
+            if let Err(e) = self.confirmations.redeem(&token, &operation) {
+                return format!(r#"{{"error": "{e}"}}"#);
+            }
+        }
+
+        info!(
+            "Deleting bookmark: {} (permanent: {})",
+            params.id, params.permanent
+        );
+        match self
+            .client
+            .delete_bookmark(params.id, params.permanent)
+            .await
+        {
+            Ok(_) => serde_json::json!({"success": true}).to_string(),
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
         }
     }
 
@@ -433,46 +998,354 @@ impl McpServer {
         }
     }
 
-    #[tool(description = "Batch update multiple bookmarks")]
+    #[tool(
+        description = "Batch update bookmarks selected by ids or by a search filter within a \
+                       collection: add or remove tags, move to a collection, set important, set \
+                       cover or media, and append to notes"
+    )]
     async fn batch_update_bookmarks(
         &self,
         Parameters(params): Parameters<BatchUpdateBookmarksParams>,
     ) -> String {
-        info!("Batch updating {} bookmarks", params.ids.len());
-        let mut updates = serde_json::Map::new();
+        let selection = BookmarkSelection {
+            collection_id: params.in_collection.unwrap_or(0),
+            ids: params.ids,
+            search: params.search,
+        };
+        let update = BulkUpdate {
+            add_tags: params.add_tags.unwrap_or_default(),
+            remove_tags: params.remove_tags.unwrap_or_default(),
+            collection: params.collection,
+            important: params.important,
+            cover: params.cover,
+            media: params.media,
+            append_note: params.append_note,
+        };
+
+        info!("Batch updating bookmarks");
+        match self.client.bulk_update(selection, update).await {
+            Ok(report) => serde_json::to_string(&report)
+                .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
 
-        if let Some(collection) = params.collection {
-            updates.insert(
-                "collection".to_string(),
-                serde_json::Value::Number(collection.into()),
+    #[tool(
+        description = "Batch delete multiple bookmarks by moving them to the trash, or \
+                       permanently with permanent=true. Permanent deletes return a preview and a \
+                       confirmationToken first; call again with the token only after the user \
+                       has confirmed"
+    )]
+    async fn batch_delete_bookmarks(
+        &self,
+        Parameters(params): Parameters<BatchDeleteBookmarksParams>,
+    ) -> String {
+        if params.permanent {
+            let mut ids = params.ids.clone();
+            ids.sort_unstable();
+            ids.dedup();
+            let operation = format!(
+                "batch_delete_bookmarks:{}:permanent",
+                ids.iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
             );
+            let Some(token) = params.confirmation_token else {
+                let preview = self.preview_permanent_delete(&operation, &ids).await;
+                return self.request_confirmation(&operation, preview);
+            };
+            if let Err(e) = self.confirmations.redeem(&token, &operation) {
+                return format!(r#"{{"error": "{e}"}}"#);
+            }
+        }
+
+        info!(
+            "Batch deleting {} bookmarks (permanent: {})",
+            params.ids.len(),
+            params.permanent
+        );
+        match self
+            .client
+            .batch_delete_bookmarks(params.ids, params.permanent)
+            .await
+        {
+            Ok(result) => serde_json::to_string(&result)
+                .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
         }
-        if let Some(tags) = params.tags {
-            updates.insert("tags".to_string(), serde_json::to_value(tags).unwrap());
+    }
+
+    // Trash tools
+    #[tool(description = "List bookmarks currently in the trash")]
+    async fn get_trash(&self, Parameters(params): Parameters<GetTrashParams>) -> String {
+        debug!("Getting trash");
+        match self.client.get_trash(params.page, params.per_page).await {
+            Ok(trash) => serde_json::to_string(&trash)
+                .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
         }
-        if let Some(important) = params.important {
-            updates.insert("important".to_string(), serde_json::Value::Bool(important));
+    }
+
+    #[tool(
+        description = "Restore bookmarks from the trash into their original collection, or into \
+                       the given collection"
+    )]
+    async fn restore_bookmarks(
+        &self,
+        Parameters(params): Parameters<RestoreBookmarksParams>,
+    ) -> String {
+        info!("Restoring {} bookmarks", params.ids.len());
+        match self
+            .client
+            .restore_bookmarks(params.ids, params.collection)
+            .await
+        {
+            Ok(restored) => serde_json::to_string(&restored)
+                .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
         }
+    }
 
+    // Upload tools
+    #[tool(
+        description = "Upload a local file or base64 content as a new bookmark (document, image, \
+                       etc.) in a collection"
+    )]
+    async fn upload_file(&self, Parameters(params): Parameters<UploadFileParams>) -> String {
+        let (file_name, data) = match read_upload_source(
+            params.path,
+            params.content,
+            params.file_name,
+            self.local_files,
+        ) {
+            Ok(source) => source,
+            Err(e) => return format!(r#"{{"error": "{e}"}}"#),
+        };
+        info!("Uploading file: {}", file_name);
         match self
             .client
-            .batch_update_bookmarks(params.ids, serde_json::Value::Object(updates))
+            .upload_file(
+                file_name,
+                data,
+                params
+                    .collection
+                    .unwrap_or_else(|| self.client.default_collection()),
+            )
             .await
         {
-            Ok(result) => serde_json::to_string(&result)
+            Ok(bookmark) => serde_json::to_string(&bookmark)
                 .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
             Err(e) => format!(r#"{{"error": "{e}"}}"#),
         }
     }
 
-    #[tool(description = "Batch delete multiple bookmarks")]
-    async fn batch_delete_bookmarks(
+    #[tool(description = "Upload a custom cover image for an existing bookmark")]
+    async fn upload_cover(&self, Parameters(params): Parameters<UploadCoverParams>) -> String {
+        let (file_name, data) = match read_upload_source(
+            params.path,
+            params.content,
+            params.file_name,
+            self.local_files,
+        ) {
+            Ok(source) => source,
+            Err(e) => return format!(r#"{{"error": "{e}"}}"#),
+        };
+        info!("Uploading cover for bookmark: {}", params.id);
+        match self.client.upload_cover(params.id, file_name, data).await {
+            Ok(bookmark) => serde_json::to_string(&bookmark)
+                .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
+
+    // Page content tools
+    #[tool(
+        description = "Read the permanent copy of a bookmarked page (Raindrop Pro) as Markdown or \
+                       plain text, even when the original link is dead"
+    )]
+    async fn get_cached_copy(&self, Parameters(params): Parameters<GetCachedCopyParams>) -> String {
+        debug!("Getting permanent copy of bookmark: {}", params.id);
+        let bookmark = match self.client.get_bookmark(params.id).await {
+            Ok(bookmark) => bookmark,
+            Err(e) => return format!(r#"{{"error": "{e}"}}"#),
+        };
+        match bookmark.cache.as_ref().map(|cache| &cache.status) {
+            Some(CacheStatus::Ready) => {}
+            Some(status) => {
+                let status = serde_json::to_value(status).unwrap_or_default();
+                return format!(
+                    r#"{{"error": "Permanent copy of bookmark {} is not ready (status: {})"}}"#,
+                    params.id,
+                    status.as_str().unwrap_or("unknown")
+                );
+            }
+            None => {
+                return format!(
+                    r#"{{"error": "Bookmark {} has no permanent copy"}}"#,
+                    params.id
+                );
+            }
+        }
+
+        match self.client.get_cached_copy(params.id).await {
+            Ok(html) => {
+                let converted = content::convert_html(
+                    &html,
+                    params.format.unwrap_or_default(),
+                    params.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+                );
+                serde_json::json!({
+                    "bookmarkId": bookmark.id,
+                    "title": bookmark.title,
+                    "link": bookmark.link,
+                    "cacheCreated": bookmark.cache.and_then(|cache| cache.created),
+                    "page": converted,
+                })
+                .to_string()
+            }
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
+
+    #[tool(
+        description = "Fetch a bookmarked page and extract its main article as Markdown or plain \
+                       text. Falls back to the Raindrop permanent copy when the page is \
+                       unreachable; can append a short summary to the bookmark's note"
+    )]
+    async fn fetch_bookmark_content(
         &self,
-        Parameters(params): Parameters<BatchDeleteBookmarksParams>,
+        Parameters(params): Parameters<FetchBookmarkContentParams>,
     ) -> String {
-        info!("Batch deleting {} bookmarks", params.ids.len());
-        match self.client.batch_delete_bookmarks(params.ids, false).await {
-            Ok(result) => serde_json::to_string(&result)
+        debug!("Fetching content of bookmark: {}", params.id);
+        let bookmark = match self.client.get_bookmark(params.id).await {
+            Ok(bookmark) => bookmark,
+            Err(e) => return format!(r#"{{"error": "{e}"}}"#),
+        };
+
+        let (html, source, fetch_error) = match self.fetcher.fetch(&bookmark.link).await {
+            Ok(page) => (
+                page.html,
+                if page.from_cache { "cache" } else { "live" },
+                None,
+            ),
+            Err(e) => {
+                let has_copy = matches!(
+                    bookmark.cache.as_ref().map(|cache| &cache.status),
+                    Some(CacheStatus::Ready)
+                );
+                if !has_copy {
+                    return format!(r#"{{"error": "{e}"}}"#);
+                }
+                debug!(
+                    "Falling back to permanent copy of bookmark {}: {}",
+                    params.id, e
+                );
+                match self.client.get_cached_copy(params.id).await {
+                    Ok(html) => (html, "permanentCopy", Some(e.to_string())),
+                    Err(copy_error) => {
+                        return format!(
+                            r#"{{"error": "{e}; permanent copy unavailable: {copy_error}"}}"#
+                        );
+                    }
+                }
+            }
+        };
+
+        let page = extract_article(
+            &html,
+            params.format.unwrap_or_default(),
+            params.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+        );
+
+        let mut saved_summary = None;
+        if params.save_summary.unwrap_or(false) {
+            let summary = params.summary.unwrap_or_else(|| {
+                let text = extract_article(&html, ContentFormat::Text, DEFAULT_MAX_TOKENS);
+                lead_summary(&text.content, SUMMARY_MAX_CHARS)
+            });
+            let summary = summary.trim();
+            if summary.is_empty() {
+                return format!(
+                    r#"{{"error": "No summary could be extracted from {}"}}"#,
+                    bookmark.link
+                );
+            }
+            let existing = bookmark.note.as_deref().unwrap_or_default().trim_end();
+            if !existing.contains(summary) {
+                let note = if existing.is_empty() {
+                    format!("Summary: {summary}")
+                } else {
+                    format!("{existing}\n\nSummary: {summary}")
+                };
+                if let Err(e) = self
+                    .client
+                    .update_bookmark(params.id, serde_json::json!({ "note": note }))
+                    .await
+                {
+                    return format!(r#"{{"error": "{e}"}}"#);
+                }
+            }
+            saved_summary = Some(summary.to_string());
+        }
+
+        let mut result = serde_json::json!({
+            "bookmarkId": bookmark.id,
+            "title": bookmark.title,
+            "link": bookmark.link,
+            "source": source,
+            "page": page,
+        });
+        if let Some(fetch_error) = fetch_error {
+            result["fetchError"] = fetch_error.into();
+        }
+        if let Some(summary) = saved_summary {
+            result["savedSummary"] = summary.into();
+        }
+        result.to_string()
+    }
+
+    // Reminder tools
+    #[tool(description = "Set or replace the reminder on a bookmark")]
+    async fn set_reminder(&self, Parameters(params): Parameters<SetReminderParams>) -> String {
+        info!("Setting reminder on bookmark: {}", params.id);
+        let date = match parse_date(&params.date) {
+            Ok(date) => date,
+            Err(e) => return format!(r#"{{"error": "{e}"}}"#),
+        };
+        match self.client.set_reminder(params.id, date, params.note).await {
+            Ok(bookmark) => serde_json::to_string(&bookmark)
+                .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
+
+    #[tool(description = "Remove the reminder from a bookmark")]
+    async fn clear_reminder(&self, Parameters(params): Parameters<ClearReminderParams>) -> String {
+        info!("Clearing reminder on bookmark: {}", params.id);
+        match self.client.clear_reminder(params.id).await {
+            Ok(bookmark) => serde_json::to_string(&bookmark)
+                .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
+
+    #[tool(description = "List bookmarks with upcoming reminders, soonest first")]
+    async fn list_upcoming_reminders(
+        &self,
+        Parameters(params): Parameters<ListUpcomingRemindersParams>,
+    ) -> String {
+        debug!("Listing upcoming reminders");
+        let after = match params.after.as_deref().map(parse_date).transpose() {
+            Ok(after) => after.unwrap_or_else(Utc::now),
+            Err(e) => return format!(r#"{{"error": "{e}"}}"#),
+        };
+        match self
+            .client
+            .get_upcoming_reminders(after, params.limit)
+            .await
+        {
+            Ok(reminders) => serde_json::to_string(&reminders)
                 .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
             Err(e) => format!(r#"{{"error": "{e}"}}"#),
         }
@@ -506,42 +1379,341 @@ impl McpServer {
         }
     }
 
-    #[tool(description = "Rename a tag across all bookmarks")]
+    #[tool(
+        description = "Rename a tag across all bookmarks or within one collection (ID or name). \
+                       Use dryRun to preview how many bookmarks carry the tag first"
+    )]
     async fn rename_tag(&self, Parameters(params): Parameters<RenameTagParams>) -> String {
+        let (collection_id, mut preview) = match self
+            .tag_change_preview(std::slice::from_ref(&params.old_name), params.collection)
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => return format!(r#"{{"error": "{e}"}}"#),
+        };
+        if params.dry_run.unwrap_or(false) {
+            preview["dryRun"] = true.into();
+            return preview.to_string();
+        }
+
         info!(
-            "Renaming tag from '{}' to '{}'",
-            params.old_name, params.new_name
+            "Renaming tag from '{}' to '{}' in {:?}",
+            params.old_name, params.new_name, collection_id
         );
         match self
             .client
-            .rename_tag(params.old_name.clone(), params.new_name.clone(), None)
+            .rename_tag(params.old_name, params.new_name, collection_id)
             .await
         {
-            Ok(result) => serde_json::to_string(&result)
-                .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
+            Ok(_) => {
+                preview["success"] = true.into();
+                preview.to_string()
+            }
             Err(e) => format!(r#"{{"error": "{e}"}}"#),
         }
     }
 
-    #[tool(description = "Delete a single tag from all bookmarks")]
+    #[tool(
+        description = "Delete a single tag from all bookmarks or within one collection (ID or \
+                       name). Use dryRun to preview how many bookmarks carry the tag first"
+    )]
     async fn delete_tag(&self, Parameters(params): Parameters<DeleteTagParams>) -> String {
-        info!("Deleting tag: {}", params.name);
-        match self.client.delete_tags(vec![params.name.clone()], None).await {
-            Ok(_) => serde_json::json!({"success": true, "message": format!("Tag '{name}' deleted", name = params.name)}).to_string(),
+        self.delete_tags_in_scope(vec![params.name], params.collection, params.dry_run)
+            .await
+    }
+
+    #[tool(
+        description = "Delete multiple tags from all bookmarks or within one collection (ID or \
+                       name). Use dryRun to preview how many bookmarks carry each tag first"
+    )]
+    async fn delete_tags(&self, Parameters(params): Parameters<DeleteTagsParams>) -> String {
+        self.delete_tags_in_scope(params.names, params.collection, params.dry_run)
+            .await
+    }
+
+    #[tool(
+        description = "Merge several tags into one, on all bookmarks or within a collection. The \
+                       first call returns a preview and a confirmationToken; call again with the \
+                       token only after the user has confirmed"
+    )]
+    async fn merge_tags(&self, Parameters(params): Parameters<MergeTagsParams>) -> String {
+        let mut tags: Vec<String> = params
+            .tags
+            .into_iter()
+            .filter(|t| *t != params.into)
+            .collect();
+        tags.sort();
+        tags.dedup();
+        if tags.is_empty() {
+            return r#"{"error": "No tags to merge"}"#.to_string();
+        }
+        // The token only confirms this exact merge
+        let operation = format!(
+            "merge_tags:{}:{}:{}",
+            tags.join(","),
+            params.into,
+            params
+                .collection
+                .map_or_else(|| "all".to_string(), |id| id.to_string())
+        );
+        let Some(token) = params.confirmation_token else {
+            let preview = self
+                .preview_merge_tags(&operation, &tags, &params.into, params.collection)
+                .await;
+            return self.request_confirmation(&operation, preview);
+        };
+        if let Err(e) = self.confirmations.redeem(&token, &operation) {
+            return format!(r#"{{"error": "{e}"}}"#);
+        }
+
+        info!("Merging {} tags into '{}'", tags.len(), params.into);
+        match self
+            .client
+            .merge_tags(tags.clone(), params.into.clone(), params.collection)
+            .await
+        {
+            Ok(_) => serde_json::json!({
+                "success": true,
+                "merged": tags,
+                "into": params.into,
+            })
+            .to_string(),
             Err(e) => format!(r#"{{"error": "{e}"}}"#),
         }
     }
 
-    #[tool(description = "Delete multiple tags from all bookmarks")]
-    async fn delete_tags(&self, Parameters(params): Parameters<DeleteTagsParams>) -> String {
-        info!("Deleting {} tags", params.names.len());
-        match self.client.delete_tags(params.names, None).await {
-            Ok(result) => serde_json::to_string(&result)
+    #[tool(
+        description = "Suggest tags to merge, computed locally: case and separator variants, \
+                       singular/plural forms, near-identical spellings (Levenshtein) and \
+                       user-defined aliases. Apply a suggestion with merge_tags"
+    )]
+    async fn suggest_tag_merges(
+        &self,
+        Parameters(params): Parameters<SuggestTagMergesParams>,
+    ) -> String {
+        debug!("Suggesting tag merges");
+        match self.client.get_tags(params.collection).await {
+            Ok(tags) => {
+                let suggestions =
+                    tags::suggest_merges(&tags, &self.tag_aliases.list(), params.max_distance);
+                serde_json::json!({
+                    "tagCount": tags.len(),
+                    "suggestions": suggestions,
+                })
+                .to_string()
+            }
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
+
+    #[tool(
+        description = "Show hierarchical tags such as lang/rust as a tree with per-branch \
+                       bookmark counts"
+    )]
+    async fn get_tag_tree(&self, Parameters(params): Parameters<GetTagTreeParams>) -> String {
+        debug!("Building tag tree");
+        let separator = match params.separator.as_deref().map(|s| {
+            let mut chars = s.chars();
+            (chars.next(), chars.next())
+        }) {
+            None => tags::DEFAULT_HIERARCHY_SEPARATOR,
+            Some((Some(c), None)) => c,
+            Some(_) => {
+                return r#"{"error": "separator must be a single character"}"#.to_string();
+            }
+        };
+        match self.client.get_tags(params.collection).await {
+            Ok(all_tags) => {
+                let tree = tags::tag_tree(&all_tags, separator);
+                serde_json::json!({
+                    "text": tags::render_tag_tree(&tree),
+                    "tree": tree,
+                })
+                .to_string()
+            }
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
+
+    #[tool(
+        description = "Tag statistics over all bookmarks: frequency, co-occurrence matrix and \
+                       top pairs, tags used once, tags by collection and monthly usage trend. \
+                       Returns a compact table and structured JSON"
+    )]
+    async fn tag_analytics(&self, Parameters(params): Parameters<TagAnalyticsParams>) -> String {
+        let collection_id = match self.resolve_collection(params.collection).await {
+            Ok(scope) => scope.map(|(id, _)| id).unwrap_or(0),
+            Err(e) => return format!(r#"{{"error": "{e}"}}"#),
+        };
+        debug!("Computing tag analytics for collection {}", collection_id);
+        match self
+            .client
+            .get_all_bookmarks(SearchParams {
+                collection: Some(collection_id),
+                ..Default::default()
+            })
+            .await
+        {
+            Ok(bookmarks) => {
+                let stats = analytics::tag_analytics(
+                    &bookmarks,
+                    params.top.unwrap_or(DEFAULT_ANALYTICS_TOP),
+                );
+                serde_json::json!({
+                    "table": analytics::render_tag_table(&stats),
+                    "analytics": stats,
+                })
+                .to_string()
+            }
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
+
+    #[tool(
+        description = "Library statistics dashboard: bookmarks per collection, domain, type and \
+                       month, important and broken counts, highlights per bookmark, oldest \
+                       unread items (no highlights or note) and growth rate. Optionally scoped \
+                       to a collection. Also available as the raindrop://stats/dashboard resource"
+    )]
+    async fn get_library_dashboard(
+        &self,
+        Parameters(params): Parameters<LibraryDashboardParams>,
+    ) -> String {
+        let collection_id = match self.resolve_collection(params.collection).await {
+            Ok(scope) => scope.map(|(id, _)| id),
+            Err(e) => return format!(r#"{{"error": "{e}"}}"#),
+        };
+        match self
+            .library_dashboard(collection_id, params.top.unwrap_or(DEFAULT_ANALYTICS_TOP))
+            .await
+        {
+            Ok(dashboard) => serde_json::json!({
+                "table": analytics::render_dashboard(&dashboard),
+                "dashboard": dashboard,
+            })
+            .to_string(),
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
+
+    #[tool(
+        description = "List the configured profiles (Raindrop accounts). Any tool accepts a \
+                       `profile` argument to act as another profile"
+    )]
+    async fn list_profiles(&self, _params: Parameters<EmptyParams>) -> String {
+        let profiles: Vec<serde_json::Value> = self
+            .accounts
+            .iter()
+            .map(|(name, account)| {
+                serde_json::json!({
+                    "name": name,
+                    "active": *name == self.profile,
+                    "readOnly": account.settings.read_only,
+                    "baseUrl": account.settings.base_url(),
+                    "defaultCollection": account.client.default_collection(),
+                })
+            })
+            .collect();
+        serde_json::to_string(&profiles)
+            .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#))
+    }
+
+    #[tool(
+        description = "List user prompt templates loaded from the prompts config directory, \
+                       with validation errors for any template files that failed to load"
+    )]
+    async fn list_prompt_templates(&self, _params: Parameters<EmptyParams>) -> String {
+        let loaded = self.prompt_templates.current();
+        let templates: Vec<serde_json::Value> = loaded
+            .templates
+            .values()
+            .map(|template| {
+                serde_json::json!({
+                    "name": template.name,
+                    "description": template.description,
+                    "arguments": template.arguments.iter().map(|a| &a.name).collect::<Vec<_>>(),
+                    "data": template.data.iter().map(|d| &d.name).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "directory": self.prompt_templates.dir(),
+            "templates": templates,
+            "errors": loaded.errors,
+        })
+        .to_string()
+    }
+
+    #[tool(description = "List user-defined tag aliases (alias -> canonical tag)")]
+    async fn list_tag_aliases(&self, _params: Parameters<EmptyParams>) -> String {
+        serde_json::to_string(&self.tag_aliases.list())
+            .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#))
+    }
+
+    #[tool(
+        description = "Define a tag alias, e.g. js -> javascript. Aliases drive \
+                       suggest_tag_merges and apply_tag_aliases"
+    )]
+    async fn set_tag_alias(&self, Parameters(params): Parameters<SetTagAliasParams>) -> String {
+        info!(
+            "Setting tag alias '{}' -> '{}'",
+            params.alias, params.canonical
+        );
+        match self.tag_aliases.set(&params.alias, &params.canonical) {
+            Ok(_) => serde_json::to_string(&self.tag_aliases.list())
                 .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
             Err(e) => format!(r#"{{"error": "{e}"}}"#),
         }
     }
 
+    #[tool(description = "Remove a tag alias")]
+    async fn remove_tag_alias(
+        &self,
+        Parameters(params): Parameters<RemoveTagAliasParams>,
+    ) -> String {
+        info!("Removing tag alias '{}'", params.alias);
+        match self.tag_aliases.remove(&params.alias) {
+            Ok(removed) => serde_json::json!({"success": removed}).to_string(),
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
+
+    #[tool(description = "Merge every tag that has an alias into its canonical tag")]
+    async fn apply_tag_aliases(
+        &self,
+        Parameters(params): Parameters<ApplyTagAliasesParams>,
+    ) -> String {
+        info!("Applying tag aliases");
+        let all_tags = match self.client.get_tags(params.collection).await {
+            Ok(all_tags) => all_tags,
+            Err(e) => return format!(r#"{{"error": "{e}"}}"#),
+        };
+        let mut merges: std::collections::BTreeMap<String, Vec<String>> = Default::default();
+        for tag in all_tags {
+            let canonical = self.tag_aliases.resolve(&tag.id);
+            if canonical != tag.id {
+                merges.entry(canonical).or_default().push(tag.id);
+            }
+        }
+
+        let mut applied = vec![];
+        for (into, sources) in merges {
+            if let Err(e) = self
+                .client
+                .merge_tags(sources.clone(), into.clone(), params.collection)
+                .await
+            {
+                return serde_json::json!({
+                    "error": e.to_string(),
+                    "applied": applied,
+                })
+                .to_string();
+            }
+            applied.push(serde_json::json!({ "merged": sources, "into": into }));
+        }
+        serde_json::json!({ "applied": applied }).to_string()
+    }
+
     // Highlight tools
     #[tool(description = "Get highlights with optional filtering")]
     async fn get_highlights(&self, Parameters(params): Parameters<GetHighlightsParams>) -> String {
@@ -591,8 +1763,21 @@ impl McpServer {
     }
 
     // Utility tools
-    #[tool(description = "Empty the trash (permanently delete all trashed bookmarks)")]
-    async fn empty_trash(&self, _params: Parameters<EmptyParams>) -> String {
+    #[tool(
+        description = "Empty the trash (permanently delete all trashed bookmarks). The first call \
+                       returns a preview and a confirmationToken; call again with the token only \
+                       after the user has confirmed"
+    )]
+    async fn empty_trash(&self, Parameters(params): Parameters<EmptyTrashParams>) -> String {
+        let operation = "empty_trash";
+        let Some(token) = params.confirmation_token else {
+            let preview = self.preview_empty_trash().await;
+            return self.request_confirmation(operation, preview);
+        };
+        if let Err(e) = self.confirmations.redeem(&token, operation) {
+            return format!(r#"{{"error": "{e}"}}"#);
+        }
+
         info!("Emptying trash");
         match self.client.empty_trash().await {
             Ok(_) => serde_json::json!({"success": true, "message": "Trash emptied successfully"})
@@ -636,6 +1821,40 @@ impl McpServer {
         }
     }
 
+    // Undo journal tools
+    #[tool(description = "List recorded mutating operations, most recent first")]
+    async fn list_operations(
+        &self,
+        Parameters(params): Parameters<ListOperationsParams>,
+    ) -> String {
+        debug!("Listing journaled operations");
+        match self.client.list_operations(params.limit) {
+            Ok(operations) => serde_json::to_string(&operations)
+                .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
+
+    #[tool(description = "Undo the most recent operation that has not been undone yet")]
+    async fn undo_last(&self, _params: Parameters<EmptyParams>) -> String {
+        info!("Undoing last operation");
+        match self.client.undo_last().await {
+            Ok(report) => serde_json::to_string(&report)
+                .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
+
+    #[tool(description = "Undo a specific operation by its journal ID")]
+    async fn undo_operation(&self, Parameters(params): Parameters<UndoOperationParams>) -> String {
+        info!("Undoing operation: {}", params.id);
+        match self.client.undo_operation(params.id).await {
+            Ok(report) => serde_json::to_string(&report)
+                .unwrap_or_else(|e| format!(r#"{{"error": "Serialization error: {e}"}}"#)),
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
+
     #[tool(description = "Check the status of an ongoing import operation")]
     async fn get_import_status(&self, _params: Parameters<EmptyParams>) -> String {
         debug!("Getting import status");
@@ -658,28 +1877,100 @@ impl McpServer {
 }
 
 impl McpServer {
+    /// Server for the profiles in the default config file, or the environment alone.
     pub fn new() -> std::result::Result<Self, RaindropMcpError> {
-        let client = Arc::new(RaindropClient::new()?);
+        Self::from_config(&Config::load(None)?, None)
+    }
+
+    /// Server acting as `profile` (or the config's default), with every other profile
+    /// available to tools through their `profile` argument.
+    pub fn from_config(
+        config: &Config,
+        profile: Option<&str>,
+    ) -> std::result::Result<Self, RaindropMcpError> {
+        let primary = config.primary_profile(profile)?;
+        let mut accounts = BTreeMap::new();
+        for (name, settings) in config.profiles() {
+            match Account::open(&name, &settings) {
+                Ok(account) => {
+                    accounts.insert(name, account);
+                }
+                Err(e) if name != primary => warn!("Skipping profile '{}': {}", name, e),
+                Err(e) => return Err(e),
+            }
+        }
+        let account = accounts[&primary].clone();
+        info!(
+            "Using profile '{}' ({} profiles available)",
+            primary,
+            accounts.len()
+        );
 
         Ok(Self {
-            client,
+            client: account.client,
+            confirmations: account.confirmations,
+            fetcher: account.fetcher,
+            tag_aliases: account.tag_aliases,
+            subscriptions: Arc::new(Subscriptions::new()),
+            resource_listings: Arc::new(Listings::new()),
+            prompt_templates: Arc::new(PromptTemplates::open_default(prompts::BUILTIN_PROMPTS)),
+            completions: account.completions,
+            profile: primary,
+            accounts: Arc::new(accounts),
+            local_files: true,
             tool_router: Self::tool_router(),
         })
     }
 
-    // Helper to convert our error to MCP error
-    fn to_mcp_error(err: RaindropMcpError) -> McpError {
-        McpError {
-            code: ErrorCode(err.to_mcp_error_code()),
-            message: err.to_string().into(),
-            data: None,
-        }
+    /// Server for one HTTP session, acting only as the holder of `access_token`.
+    ///
+    /// `settings` contributes everything but credentials. Nothing holding account data is
+    /// shared with other sessions: the client, its undo journal, confirmations, tag aliases,
+    /// subscriptions and completions are all new, and no other profile is reachable.
+    /// `fetcher` is shared, so it must keep no page cache.
+    /// Uploads take inline content only, never a path on the server.
+    pub(crate) fn for_session(
+        profile: &str,
+        settings: &Profile,
+        access_token: &str,
+        fetcher: Arc<PageFetcher>,
+        prompt_templates: Arc<PromptTemplates>,
+    ) -> std::result::Result<Self, RaindropMcpError> {
+        let account = Account {
+            settings: settings.clone(),
+            client: Arc::new(RaindropClient::for_session(settings, access_token)?),
+            confirmations: Arc::new(ConfirmationStore::default()),
+            fetcher,
+            tag_aliases: Arc::new(TagAliases::in_memory()),
+            completions: Arc::new(CompletionCache::new(Duration::from_secs(
+                settings.cache.completion_ttl_secs,
+            ))),
+        };
+
+        Ok(Self {
+            client: account.client.clone(),
+            confirmations: account.confirmations.clone(),
+            fetcher: account.fetcher.clone(),
+            tag_aliases: account.tag_aliases.clone(),
+            subscriptions: Arc::new(Subscriptions::new()),
+            resource_listings: Arc::new(Listings::new()),
+            prompt_templates,
+            completions: account.completions.clone(),
+            profile: profile.to_string(),
+            accounts: Arc::new(BTreeMap::from([(profile.to_string(), account)])),
+            // The host's files belong to its operator, not to whoever holds a session
+            local_files: false,
+            tool_router: Self::tool_router(),
+        })
     }
-}
 
-#[tool_handler]
-impl ServerHandler for McpServer {
-    fn get_info(&self) -> ServerInfo {
+    /// Drops every resource subscription so the watcher stops polling for a departed client.
+    pub(crate) fn close_subscriptions(&self) {
+        self.subscriptions.close();
+    }
+
+    /// Name and capabilities announced on `initialize`.
+    pub fn server_info() -> ServerInfo {
         ServerInfo {
             instructions: Some(
                 "A Raindrop.io MCP server for managing bookmarks and collections".into(),
@@ -687,70 +1978,1094 @@ impl ServerHandler for McpServer {
             capabilities: ServerCapabilities::builder()
                 .enable_tools()
                 .enable_resources()
+                .enable_resources_subscribe()
                 .enable_prompts()
+                .enable_completions()
                 .build(),
             ..Default::default()
         }
     }
 
+    async fn resolve_collection(
+        &self,
+        collection: Option<CollectionArg>,
+    ) -> std::result::Result<Option<(i64, Option<String>)>, RaindropMcpError> {
+        match collection {
+            None => Ok(None),
+            Some(CollectionArg::Id(id)) => Ok(Some((id, None))),
+            Some(CollectionArg::Name(name)) => match self.client.find_collection(&name).await {
+                Ok(collection) => Ok(Some((collection.id, Some(collection.title)))),
+                // Accept IDs passed as strings when no collection has that name
+                Err(RaindropMcpError::NotFound(_)) if name.trim().parse::<i64>().is_ok() => {
+                    Ok(Some((name.trim().parse().unwrap_or_default(), None)))
+                }
+                Err(e) => Err(e),
+            },
+        }
+    }
+
+    async fn library_dashboard(
+        &self,
+        collection_id: Option<i64>,
+        top: usize,
+    ) -> std::result::Result<analytics::LibraryDashboard, RaindropMcpError> {
+        debug!(
+            "Computing library dashboard for collection {:?}",
+            collection_id
+        );
+        let bookmarks = self
+            .client
+            .get_all_bookmarks(SearchParams {
+                collection: Some(collection_id.unwrap_or(0)),
+                ..Default::default()
+            })
+            .await?;
+        let titles = self.collection_titles().await?;
+        Ok(analytics::library_dashboard(
+            &bookmarks,
+            &titles,
+            Utc::now(),
+            top,
+        ))
+    }
+
+    // Titles of every collection, including the system ones
+    async fn collection_titles(
+        &self,
+    ) -> std::result::Result<HashMap<i64, String>, RaindropMcpError> {
+        let mut titles: HashMap<i64, String> = self
+            .client
+            .get_all_collections()
+            .await?
+            .into_iter()
+            .map(|c| (c.id, c.title))
+            .collect();
+        titles.insert(UNSORTED_COLLECTION_ID, "Unsorted".to_string());
+        titles.insert(TRASH_COLLECTION_ID, "Trash".to_string());
+        Ok(titles)
+    }
+
+    // Fetches the data a prompt template binds, as the JSON its messages render
+    async fn fetch_binding(
+        &self,
+        binding: &ResolvedBinding,
+    ) -> std::result::Result<serde_json::Value, RaindropMcpError> {
+        debug!("Fetching prompt data '{}': {:?}", binding.name, binding);
+        let collection_id = self
+            .resolve_collection(binding.collection.clone().map(CollectionArg::Name))
+            .await?
+            .map(|(id, _)| id);
+        let value = match binding.source {
+            DataSource::Bookmarks => {
+                let per_page = binding.limit.clamp(1, MAX_PER_PAGE as usize) as i32;
+                let mut bookmarks: Vec<PromptBookmark> = vec![];
+                let mut page = 0;
+                while bookmarks.len() < binding.limit {
+                    let result = self
+                        .client
+                        .get_bookmarks(SearchParams {
+                            collection: Some(collection_id.unwrap_or(0)),
+                            search: binding.search.clone(),
+                            tags: binding.tag.clone().map(|tag| vec![tag]),
+                            sort: binding.sort.clone(),
+                            page: Some(page),
+                            per_page: Some(per_page),
+                            ..Default::default()
+                        })
+                        .await?;
+                    let fetched = result.items.len();
+                    bookmarks.extend(result.items.iter().map(PromptBookmark::from));
+                    if fetched < per_page as usize {
+                        break;
+                    }
+                    page += 1;
+                }
+                bookmarks.truncate(binding.limit);
+                serde_json::to_value(bookmarks)?
+            }
+            DataSource::Bookmark => {
+                let id = binding.bookmark.ok_or_else(|| {
+                    RaindropMcpError::InvalidParameter("bookmark is required".to_string())
+                })?;
+                serde_json::to_value(self.client.get_bookmark(id).await?)?
+            }
+            DataSource::Collection => {
+                let id = collection_id.ok_or_else(|| {
+                    RaindropMcpError::InvalidParameter("collection is required".to_string())
+                })?;
+                serde_json::to_value(self.client.get_collection(id).await?)?
+            }
+            DataSource::Collections => {
+                serde_json::to_value(self.client.get_all_collections().await?)?
+            }
+            DataSource::Tags => serde_json::to_value(self.client.get_tags(collection_id).await?)?,
+            DataSource::Highlights => {
+                let mut highlights = match binding.bookmark {
+                    Some(id) => self.client.get_highlights(id).await?,
+                    None => {
+                        let per_page = binding.limit.clamp(1, MAX_PER_PAGE as usize) as i32;
+                        self.client
+                            .get_all_highlights(Some(0), Some(per_page))
+                            .await?
+                    }
+                };
+                highlights.truncate(binding.limit);
+                serde_json::to_value(highlights)?
+            }
+        };
+        Ok(value)
+    }
+
+    // Completion candidates from `source`, fetched at most once per cache TTL
+    async fn completion_candidates(
+        &self,
+        source: CompletionSource,
+    ) -> std::result::Result<Arc<Vec<Candidate>>, RaindropMcpError> {
+        if let Some(candidates) = self.completions.get(source) {
+            return Ok(candidates);
+        }
+        debug!("Fetching completion candidates: {:?}", source);
+        let candidates = match source {
+            CompletionSource::Collections => {
+                let mut candidates = vec![Candidate::new(UNSORTED_COLLECTION_ID, "Unsorted")];
+                candidates.extend(
+                    self.client
+                        .get_all_collections()
+                        .await?
+                        .into_iter()
+                        .map(|c| Candidate::new(c.id, c.title)),
+                );
+                candidates
+            }
+            CompletionSource::Tags => self
+                .client
+                .get_tags(None)
+                .await?
+                .into_iter()
+                .map(|tag| Candidate::new(tag.id.clone(), tag.id))
+                .collect(),
+            CompletionSource::RecentBookmarks => self
+                .client
+                .get_recent_bookmarks(0, completion::RECENT_BOOKMARK_CANDIDATES, None)
+                .await?
+                .into_iter()
+                .map(|b| Candidate::new(b.id, b.title))
+                .collect(),
+        };
+        Ok(self.completions.insert(source, candidates))
+    }
+
+    // Renders a user prompt template after fetching its data bindings
+    async fn get_template_prompt(
+        &self,
+        name: &str,
+        args: Option<&JsonObject>,
+        max_tokens: usize,
+    ) -> std::result::Result<GetPromptResult, McpError> {
+        let loaded = self.prompt_templates.current();
+        let Some(template) = loaded.templates.get(name) else {
+            let mut message = format!("Unknown prompt: {name}");
+            if !loaded.errors.is_empty() {
+                message.push_str(&format!(
+                    " ({} prompt template files failed to load; see list_prompt_templates)",
+                    loaded.errors.len()
+                ));
+            }
+            return Err(McpError::invalid_params(message, None));
+        };
+        // An explicit maxTokens argument wins over the template's own budget
+        let max_tokens = match prompts::int_arg(args, "maxTokens") {
+            Some(_) => max_tokens,
+            None => template.max_tokens.unwrap_or(max_tokens),
+        };
+
+        let mut context = template.context(args).map_err(Self::to_mcp_error)?;
+        for binding in template
+            .resolve_bindings(&context)
+            .map_err(Self::to_mcp_error)?
+        {
+            let value = self
+                .fetch_binding(&binding)
+                .await
+                .map_err(Self::to_mcp_error)?;
+            context.insert(binding.name, value);
+        }
+        Ok(GetPromptResult {
+            description: template.description.clone(),
+            messages: template
+                .render(&context, max_tokens)
+                .map_err(Self::to_mcp_error)?,
+        })
+    }
+
+    // Renders the text body of a routed resource
+    async fn read_route(
+        &self,
+        route: ResourceRoute,
+    ) -> std::result::Result<String, RaindropMcpError> {
+        let text = match route {
+            ResourceRoute::AllCollections => {
+                let collections = self.client.get_collections().await?;
+                format!(
+                    "Found {} collections\n\nData: {}",
+                    collections.len(),
+                    pretty_json(&collections)
+                )
+            }
+            ResourceRoute::AllTags => {
+                let tags = self.client.get_tags(None).await?;
+                format!("Found {} tags\n\nData: {}", tags.len(), pretty_json(&tags))
+            }
+            ResourceRoute::AllHighlights => {
+                let highlights = self.client.get_all_highlights(None, None).await?;
+                format!(
+                    "Found {} highlights\n\nData: {}",
+                    highlights.len(),
+                    pretty_json(&highlights)
+                )
+            }
+            ResourceRoute::UserInfo => format!(
+                "User information retrieved successfully\n\nData: {}",
+                pretty_json(&self.client.get_user_info().await?)
+            ),
+            ResourceRoute::UserStats => format!(
+                "User statistics retrieved successfully\n\nData: {}",
+                pretty_json(&self.client.get_user_stats(None).await?)
+            ),
+            ResourceRoute::UpcomingReminders => {
+                let reminders = self.client.get_upcoming_reminders(Utc::now(), None).await?;
+                format!(
+                    "Found {} upcoming reminders\n\nData: {}",
+                    reminders.len(),
+                    pretty_json(&reminders)
+                )
+            }
+            ResourceRoute::Dashboard => {
+                let dashboard = self.library_dashboard(None, DEFAULT_ANALYTICS_TOP).await?;
+                format!(
+                    "Library dashboard\n\n{}\nData: {}",
+                    analytics::render_dashboard(&dashboard),
+                    pretty_json(&dashboard)
+                )
+            }
+            ResourceRoute::Collection(id) => {
+                collection_text(&self.client.get_collection(id).await?)
+            }
+            ResourceRoute::CollectionBookmarks { id, page, sort } => {
+                let page = page.unwrap_or(0);
+                let result = self
+                    .client
+                    .get_bookmarks(SearchParams {
+                        collection: Some(id),
+                        page: Some(page),
+                        sort,
+                        ..Default::default()
+                    })
+                    .await?;
+                format!(
+                    "Page {page}: {} of {} bookmarks in collection {id}\n\nData: {}",
+                    result.items.len(),
+                    result.count,
+                    pretty_json(&result.items)
+                )
+            }
+            ResourceRoute::Bookmark(id) => bookmark_text(&self.client.get_bookmark(id).await?),
+            ResourceRoute::BookmarkHighlights(id) => {
+                let highlights = self.client.get_highlights(id).await?;
+                format!(
+                    "Found {} highlights on bookmark {id}\n\nData: {}",
+                    highlights.len(),
+                    pretty_json(&highlights)
+                )
+            }
+            ResourceRoute::TagBookmarks(tag) => {
+                let bookmarks = self
+                    .client
+                    .get_all_bookmarks(SearchParams {
+                        collection: Some(0),
+                        tags: Some(vec![tag.clone()]),
+                        ..Default::default()
+                    })
+                    .await?;
+                format!(
+                    "Found {} bookmarks tagged '{tag}'\n\nData: {}",
+                    bookmarks.len(),
+                    pretty_json(&bookmarks)
+                )
+            }
+            ResourceRoute::Search(query) => {
+                let result = self
+                    .client
+                    .get_bookmarks(SearchParams {
+                        search: Some(query.clone()),
+                        collection: Some(0),
+                        ..Default::default()
+                    })
+                    .await?;
+                format!(
+                    "Found {} bookmarks matching '{query}', showing {}\n\nData: {}",
+                    result.count,
+                    result.items.len(),
+                    pretty_json(&result.items)
+                )
+            }
+        };
+        Ok(text)
+    }
+
+    // Cheap change marker: lastUpdate where the API has one, otherwise the rendered body
+    async fn resource_fingerprint(
+        &self,
+        route: ResourceRoute,
+    ) -> std::result::Result<u64, RaindropMcpError> {
+        let state = match route {
+            ResourceRoute::Bookmark(id) | ResourceRoute::BookmarkHighlights(id) => {
+                self.client.get_bookmark(id).await?.last_update
+            }
+            ResourceRoute::Collection(id) => self.client.get_collection(id).await?.last_update,
+            // The rendered dashboard changes with the date alone, so only its inputs count.
+            // The newest edit and the library size stand in for every bookmark, and each
+            // collection's count and last update catch moves between them
+            ResourceRoute::Dashboard => {
+                let newest = self
+                    .client
+                    .get_bookmarks(SearchParams {
+                        collection: Some(0),
+                        sort: Some("-lastUpdate".to_string()),
+                        per_page: Some(1),
+                        ..Default::default()
+                    })
+                    .await?;
+                let latest = newest.items.first().map(|b| (b.id, b.last_update.as_str()));
+                let mut collections: Vec<(i64, String, i32, String)> = self
+                    .client
+                    .get_all_collections()
+                    .await?
+                    .into_iter()
+                    .map(|c| (c.id, c.title, c.count, c.last_update))
+                    .collect();
+                collections.sort_unstable();
+                format!("{}{latest:?}{collections:?}", newest.count)
+            }
+            route => self.read_route(route).await?,
+        };
+        Ok(subscriptions::fingerprint(&state))
+    }
+
+    // Checks subscribed resources on every poll or recheck and notifies the client of changes
+    async fn watch_subscriptions(self, peer: Peer<RoleServer>) {
+        let interval = subscriptions::poll_interval();
+        info!(
+            "Watching subscribed resources (poll interval: {:?})",
+            interval
+        );
+        loop {
+            self.subscriptions.wait(interval).await;
+            if peer.is_transport_closed() {
+                self.subscriptions.close();
+            }
+            // Nothing left to watch once everything is unsubscribed or the client went away
+            if self.subscriptions.stop_if_idle() {
+                info!("Stopped watching subscribed resources");
+                return;
+            }
+            for uri in self.subscriptions.uris() {
+                let Ok(route) = ResourceRoute::parse(&uri) else {
+                    continue;
+                };
+                let fingerprint = match self.resource_fingerprint(route).await {
+                    Ok(fingerprint) => fingerprint,
+                    // Deleting a resource is a change too
+                    Err(RaindropMcpError::NotFound(_)) => subscriptions::fingerprint(""),
+                    Err(e) => {
+                        warn!("Failed to check subscribed resource {}: {}", uri, e);
+                        continue;
+                    }
+                };
+                if !self.subscriptions.update(&uri, fingerprint) {
+                    continue;
+                }
+                debug!("Subscribed resource {} changed", uri);
+                if let Err(e) = peer
+                    .notify_resource_updated(ResourceUpdatedNotificationParam { uri })
+                    .await
+                {
+                    warn!("Stopping resource watcher: {}", e);
+                    self.subscriptions.stop_watching();
+                    return;
+                }
+            }
+        }
+    }
+
+    // Everything `resources/list` offers, in listing order
+    async fn resource_list(&self) -> Vec<Resource> {
+        let mut all = vec![
+            Resource::new(
+                RawResource {
+                    uri: "raindrop://collections/all".into(),
+                    name: "All Collections".into(),
+                    description: Some("List of all Raindrop collections".into()),
+                    mime_type: Some("application/json".into()),
+                    size: None,
+                },
+                None,
+            ),
+            Resource::new(
+                RawResource {
+                    uri: "raindrop://tags/all".into(),
+                    name: "All Tags".into(),
+                    description: Some("List of all tags across all bookmarks".into()),
+                    mime_type: Some("application/json".into()),
+                    size: None,
+                },
+                None,
+            ),
+            Resource::new(
+                RawResource {
+                    uri: "raindrop://highlights/all".into(),
+                    name: "All Highlights".into(),
+                    description: Some("List of all highlights across all bookmarks".into()),
+                    mime_type: Some("application/json".into()),
+                    size: None,
+                },
+                None,
+            ),
+            Resource::new(
+                RawResource {
+                    uri: "raindrop://user/info".into(),
+                    name: "User Info".into(),
+                    description: Some("Current user account information".into()),
+                    mime_type: Some("application/json".into()),
+                    size: None,
+                },
+                None,
+            ),
+            Resource::new(
+                RawResource {
+                    uri: "raindrop://user/stats".into(),
+                    name: "User Statistics".into(),
+                    description: Some("User account statistics".into()),
+                    mime_type: Some("application/json".into()),
+                    size: None,
+                },
+                None,
+            ),
+            Resource::new(
+                RawResource {
+                    uri: "raindrop://reminders/upcoming".into(),
+                    name: "Upcoming Reminders".into(),
+                    description: Some("Bookmarks with pending reminders, soonest first".into()),
+                    mime_type: Some("application/json".into()),
+                    size: None,
+                },
+                None,
+            ),
+            Resource::new(
+                RawResource {
+                    uri: "raindrop://stats/dashboard".into(),
+                    name: "Library Dashboard".into(),
+                    description: Some(
+                        "Bookmarks per collection, domain, type and month, unread items \
+                         and growth rate"
+                            .into(),
+                    ),
+                    mime_type: Some("application/json".into()),
+                    size: None,
+                },
+                None,
+            ),
+        ];
+
+        // Every collection, then optionally the newest bookmarks, as individual resources.
+        // Either may be missing when the API fails; the rest is still listed.
+        match self.client.get_all_collections().await {
+            Ok(collections) => all.extend(collections.iter().map(collection_resource)),
+            Err(e) => warn!("Listing resources without collections: {}", e),
+        }
+        let recent = resources::recent_bookmarks_listed();
+        if recent > 0 {
+            let result = self
+                .client
+                .get_bookmarks(SearchParams {
+                    collection: Some(0),
+                    sort: Some("-created".to_string()),
+                    per_page: Some((recent as i32).min(MAX_PER_PAGE)),
+                    ..Default::default()
+                })
+                .await;
+            match result {
+                Ok(result) => all.extend(result.items.iter().map(bookmark_resource)),
+                Err(e) => warn!("Listing resources without recent bookmarks: {}", e),
+            }
+        }
+        all
+    }
+
+    fn journal_revision(&self) -> Option<u64> {
+        self.client.journal().map(|journal| journal.revision())
+    }
+
+    // Resolves the scope and counts bookmarks carrying each tag within it
+    async fn tag_change_preview(
+        &self,
+        tags: &[String],
+        collection: Option<CollectionArg>,
+    ) -> std::result::Result<(Option<i64>, serde_json::Value), RaindropMcpError> {
+        let scope = self.resolve_collection(collection).await?;
+        let collection_id = scope.as_ref().map(|(id, _)| *id);
+        let in_scope = self.client.get_tags(collection_id).await?;
+        let usage: Vec<serde_json::Value> = tags
+            .iter()
+            .map(|tag| {
+                let bookmarks = in_scope
+                    .iter()
+                    .filter(|t| t.id.eq_ignore_ascii_case(tag))
+                    .map(|t| t.count)
+                    .sum::<i32>();
+                serde_json::json!({ "tag": tag, "bookmarks": bookmarks })
+            })
+            .collect();
+
+        let scope = match scope {
+            Some((id, title)) => serde_json::json!({ "collectionId": id, "title": title }),
+            None => serde_json::json!("all collections"),
+        };
+        Ok((
+            collection_id,
+            serde_json::json!({ "scope": scope, "tags": usage }),
+        ))
+    }
+
+    async fn delete_tags_in_scope(
+        &self,
+        tags: Vec<String>,
+        collection: Option<CollectionArg>,
+        dry_run: Option<bool>,
+    ) -> String {
+        let (collection_id, mut preview) = match self.tag_change_preview(&tags, collection).await {
+            Ok(result) => result,
+            Err(e) => return format!(r#"{{"error": "{e}"}}"#),
+        };
+        if dry_run.unwrap_or(false) {
+            preview["dryRun"] = true.into();
+            return preview.to_string();
+        }
+
+        info!("Deleting {} tags in {:?}", tags.len(), collection_id);
+        match self.client.delete_tags(tags, collection_id).await {
+            Ok(_) => {
+                preview["success"] = true.into();
+                preview.to_string()
+            }
+            Err(e) => format!(r#"{{"error": "{e}"}}"#),
+        }
+    }
+
+    // Raindrop's parser first, then the page's own OpenGraph/Twitter/oEmbed metadata
+    async fn parse_link(
+        &self,
+        url: &str,
+    ) -> std::result::Result<(ParsedUrl, &'static str), RaindropMcpError> {
+        let mut parsed = match self.client.parse_url(url).await {
+            Ok(parsed) if parsed.title.is_some() => return Ok((parsed, "raindrop")),
+            Ok(parsed) => parsed,
+            Err(e) => {
+                debug!("Raindrop could not parse {}: {}", url, e);
+                ParsedUrl::default()
+            }
+        };
+
+        let page = match self.fetcher.fetch(url).await {
+            Ok(page) => page,
+            Err(_) if !parsed.is_empty() => return Ok((parsed, "raindrop")),
+            Err(e) => return Err(e),
+        };
+        let page_url = url::Url::parse(url)
+            .map_err(|e| RaindropMcpError::InvalidParameter(format!("Invalid URL '{url}': {e}")))?;
+        let metadata = content::metadata::parse_metadata(&page.html, &page_url);
+        let mut local = metadata.parsed;
+        if let Some(oembed_url) = metadata.oembed_url {
+            match self.fetcher.fetch_json(oembed_url.as_str()).await {
+                Ok(oembed) => content::metadata::apply_oembed(&mut local, &oembed),
+                Err(e) => debug!("Ignoring oEmbed for {}: {}", url, e),
+            }
+        }
+        parsed.merge(local);
+        Ok((parsed, "local"))
+    }
+
+    // Issues a confirmation token for a destructive or sweeping operation and returns the preview
+    fn request_confirmation(
+        &self,
+        operation: &str,
+        preview: std::result::Result<DestructivePreview, RaindropMcpError>,
+    ) -> String {
+        let preview = match preview {
+            Ok(preview) => preview,
+            Err(e) => return format!(r#"{{"error": "{e}"}}"#),
+        };
+        let token = match self.confirmations.issue(operation) {
+            Ok(token) => token,
+            Err(e) => return format!(r#"{{"error": "{e}"}}"#),
+        };
+        serde_json::json!({
+            "confirmationRequired": true,
+            "confirmationToken": token,
+            "expiresInSeconds": self.confirmations.ttl().as_secs(),
+            "preview": preview,
+            "message": "Show the preview to the user and repeat the call with \
+                        confirmationToken only if they explicitly confirm.",
+        })
+        .to_string()
+    }
+
+    async fn preview_empty_trash(
+        &self,
+    ) -> std::result::Result<DestructivePreview, RaindropMcpError> {
+        let trash = self
+            .client
+            .get_bookmarks(SearchParams {
+                collection: Some(TRASH_COLLECTION_ID),
+                per_page: Some(PREVIEW_TITLE_LIMIT as i32),
+                ..Default::default()
+            })
+            .await?;
+
+        Ok(DestructivePreview {
+            operation: "empty_trash".to_string(),
+            description: "Permanently delete every bookmark in the trash".to_string(),
+            item_count: trash.count as i64,
+            titles: trash.items.into_iter().map(|b| b.title).collect(),
+            warnings: vec![],
+        })
+    }
+
+    async fn preview_merge_tags(
+        &self,
+        operation: &str,
+        tags: &[String],
+        into: &str,
+        collection_id: Option<i64>,
+    ) -> std::result::Result<DestructivePreview, RaindropMcpError> {
+        let in_scope = self.client.get_tags(collection_id).await?;
+        let mut item_count = 0;
+        let mut titles = Vec::with_capacity(tags.len());
+        let mut warnings = vec![];
+        for tag in tags {
+            let bookmarks: i64 = in_scope
+                .iter()
+                .filter(|t| t.id.eq_ignore_ascii_case(tag))
+                .map(|t| i64::from(t.count))
+                .sum();
+            if bookmarks == 0 {
+                warnings.push(format!("No bookmarks in scope are tagged '{tag}'"));
+            }
+            item_count += bookmarks;
+            titles.push(format!("{tag} ({bookmarks} bookmarks)"));
+        }
+        let scope = match collection_id {
+            Some(id) => format!("in collection {id}"),
+            None => "on all bookmarks".to_string(),
+        };
+
+        Ok(DestructivePreview {
+            operation: operation.to_string(),
+            description: format!("Replace {} tags with '{into}' {scope}", tags.len()),
+            item_count,
+            titles,
+            warnings,
+        })
+    }
+
+    async fn preview_permanent_delete(
+        &self,
+        operation: &str,
+        ids: &[i64],
+    ) -> std::result::Result<DestructivePreview, RaindropMcpError> {
+        let mut titles = Vec::with_capacity(ids.len().min(PREVIEW_TITLE_LIMIT));
+        for &id in ids.iter().take(PREVIEW_TITLE_LIMIT) {
+            let bookmark = self.client.get_bookmark(id).await?;
+            titles.push(format!("{} ({})", bookmark.title, bookmark.link));
+        }
+
+        Ok(DestructivePreview {
+            operation: operation.to_string(),
+            description: "Permanently delete bookmarks without moving them to the trash"
+                .to_string(),
+            item_count: ids.len() as i64,
+            titles,
+            warnings: vec![],
+        })
+    }
+
+    async fn preview_delete_collection(
+        &self,
+        id: i64,
+    ) -> std::result::Result<DestructivePreview, RaindropMcpError> {
+        let collection = self.client.get_collection(id).await?;
+        let bookmarks = self
+            .client
+            .get_bookmarks(SearchParams {
+                collection: Some(id),
+                per_page: Some(PREVIEW_TITLE_LIMIT as i32),
+                ..Default::default()
+            })
+            .await?;
+        let children = self
+            .client
+            .get_child_collections(id)
+            .await
+            .unwrap_or_default();
+
+        let mut warnings = vec![];
+        if !children.is_empty() {
+            warnings.push(format!(
+                "Nested collections will also be removed: {}",
+                children
+                    .iter()
+                    .map(|c| c.title.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
+        Ok(DestructivePreview {
+            operation: format!("delete_collection:{id}"),
+            description: format!(
+                "Delete collection '{}' and move its bookmarks to the trash",
+                collection.title
+            ),
+            item_count: bookmarks.count as i64,
+            titles: bookmarks.items.into_iter().map(|b| b.title).collect(),
+            warnings,
+        })
+    }
+
+    // Takes the `profile` argument off a tool call, returning the server acting as that profile
+    fn with_profile_argument(
+        &self,
+        request: &mut CallToolRequestParam,
+    ) -> std::result::Result<Option<Self>, McpError> {
+        let Some(value) = request
+            .arguments
+            .as_mut()
+            .and_then(|arguments| arguments.remove("profile"))
+        else {
+            return Ok(None);
+        };
+        let name = match &value {
+            serde_json::Value::Null => return Ok(None),
+            serde_json::Value::String(name) if *name == self.profile => return Ok(None),
+            serde_json::Value::String(name) => name,
+            _ => return Err(McpError::invalid_params("profile must be a string", None)),
+        };
+        let account = self.accounts.get(name).ok_or_else(|| {
+            McpError::invalid_params(
+                format!(
+                    "Unknown profile '{name}'; available: {}",
+                    self.accounts.keys().cloned().collect::<Vec<_>>().join(", ")
+                ),
+                None,
+            )
+        })?;
+        debug!("Calling {} as profile '{}'", request.name, name);
+        Ok(Some(Self {
+            client: account.client.clone(),
+            confirmations: account.confirmations.clone(),
+            fetcher: account.fetcher.clone(),
+            tag_aliases: account.tag_aliases.clone(),
+            completions: account.completions.clone(),
+            profile: name.clone(),
+            ..self.clone()
+        }))
+    }
+
+    // Helper to convert our error to MCP error
+    fn to_mcp_error(err: RaindropMcpError) -> McpError {
+        McpError {
+            code: ErrorCode(err.to_mcp_error_code()),
+            message: err.to_string().into(),
+            data: None,
+        }
+    }
+}
+
+fn pretty_json(data: &impl Serialize) -> String {
+    serde_json::to_string_pretty(data).unwrap_or_else(|_| "Error serializing data".to_string())
+}
+
+fn collection_text(collection: &Collection) -> String {
+    format!(
+        "Collection {}\n\nData: {}",
+        collection.id,
+        pretty_json(collection)
+    )
+}
+
+fn bookmark_text(bookmark: &Bookmark) -> String {
+    format!(
+        "Bookmark {}\n\nData: {}",
+        bookmark.id,
+        pretty_json(bookmark)
+    )
+}
+
+// Sizes are those of the text read_resource returns for the same URI
+fn collection_resource(collection: &Collection) -> Resource {
+    Resource::new(
+        RawResource {
+            uri: ResourceRoute::Collection(collection.id).uri(),
+            name: collection.title.clone(),
+            description: Some(format!("Collection with {} bookmarks", collection.count)),
+            mime_type: Some("application/json".into()),
+            size: u32::try_from(collection_text(collection).len()).ok(),
+        },
+        last_modified(&collection.last_update),
+    )
+}
+
+fn bookmark_resource(bookmark: &Bookmark) -> Resource {
+    Resource::new(
+        RawResource {
+            uri: ResourceRoute::Bookmark(bookmark.id).uri(),
+            name: bookmark.title.clone(),
+            description: Some(bookmark.link.clone()),
+            mime_type: Some("application/json".into()),
+            size: u32::try_from(bookmark_text(bookmark).len()).ok(),
+        },
+        last_modified(&bookmark.last_update),
+    )
+}
+
+// rmcp carries a resource's last modification time in the `timestamp` annotation
+fn last_modified(timestamp: &str) -> Option<Annotations> {
+    let timestamp = DateTime::parse_from_rfc3339(timestamp).ok()?;
+    Some(Annotations {
+        timestamp: Some(timestamp.with_timezone(&Utc)),
+        ..Default::default()
+    })
+}
+
+// Resolves upload content from either a local path, when allowed, or base64 data
+fn read_upload_source(
+    path: Option<String>,
+    content: Option<String>,
+    file_name: Option<String>,
+    local_files: bool,
+) -> std::result::Result<(String, Vec<u8>), RaindropMcpError> {
+    match (path, content) {
+        (Some(_), None) if !local_files => Err(RaindropMcpError::InvalidParameter(
+            "Uploading from a path is disabled on this server; send base64 content instead"
+                .to_string(),
+        )),
+        (Some(path), None) => {
+            let path = std::path::PathBuf::from(path);
+            let metadata = std::fs::metadata(&path)?;
+            if metadata.len() > MAX_UPLOAD_BYTES as u64 {
+                return Err(RaindropMcpError::InvalidParameter(format!(
+                    "File is {} bytes; the upload limit is {MAX_UPLOAD_BYTES} bytes",
+                    metadata.len()
+                )));
+            }
+            let name = file_name
+                .or_else(|| {
+                    path.file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                })
+                .ok_or_else(|| {
+                    RaindropMcpError::InvalidParameter(format!(
+                        "Cannot determine a file name for {}",
+                        path.display()
+                    ))
+                })?;
+            Ok((name, std::fs::read(&path)?))
+        }
+        (None, Some(content)) => {
+            let name = file_name.ok_or_else(|| {
+                RaindropMcpError::InvalidParameter(
+                    "fileName is required when uploading base64 content".to_string(),
+                )
+            })?;
+            let data = BASE64_STANDARD.decode(content.trim()).map_err(|e| {
+                RaindropMcpError::InvalidParameter(format!("Invalid base64 content: {e}"))
+            })?;
+            Ok((name, data))
+        }
+        (Some(_), Some(_)) => Err(RaindropMcpError::InvalidParameter(
+            "Provide either path or content, not both".to_string(),
+        )),
+        (None, None) => Err(RaindropMcpError::InvalidParameter(
+            "Either path or content is required".to_string(),
+        )),
+    }
+}
+
+// Accepts RFC 3339 timestamps or plain dates, which are taken as UTC midnight
+fn parse_date(value: &str) -> std::result::Result<DateTime<Utc>, RaindropMcpError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|date| date.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|date| date.and_time(chrono::NaiveTime::MIN).and_utc())
+        })
+        .map_err(|_| {
+            RaindropMcpError::InvalidParameter(format!(
+                "Invalid date '{value}'; expected RFC 3339 or YYYY-MM-DD"
+            ))
+        })
+}
+
+impl ServerHandler for McpServer {
+    fn get_info(&self) -> ServerInfo {
+        Self::server_info()
+    }
+
     async fn list_resources(
         &self,
-        _: Option<PaginatedRequestParam>,
+        request: Option<PaginatedRequestParam>,
         _: RequestContext<RoleServer>,
     ) -> std::result::Result<ListResourcesResult, McpError> {
+        let (listing, offset, all) = match request.and_then(|request| request.cursor) {
+            Some(cursor) => {
+                let (listing, offset) = resources::decode_cursor(&cursor)?;
+                let all = self.resource_listings.get(listing).ok_or_else(|| {
+                    McpError::invalid_params(
+                        "Resource list cursor has expired; list resources again",
+                        None,
+                    )
+                })?;
+                (Some(listing), offset, all)
+            }
+            None => (None, 0, Arc::new(self.resource_list().await)),
+        };
+        let (resources, next) = resources::paginate(&all, offset, resources::RESOURCE_PAGE_SIZE)
+            .ok_or_else(|| {
+                McpError::invalid_params("Resource list cursor is out of range", None)
+            })?;
+        // Later pages are served from this snapshot rather than fetched again
+        let next_cursor = next.map(|next| {
+            let listing = listing.unwrap_or_else(|| self.resource_listings.insert(all.clone()));
+            resources::encode_cursor(listing, next)
+        });
         Ok(ListResourcesResult {
-            resources: vec![
-                Resource::new(
-                    RawResource {
-                        uri: "raindrop://collections/all".into(),
-                        name: "All Collections".into(),
-                        description: Some("List of all Raindrop collections".into()),
-                        mime_type: Some("application/json".into()),
-                        size: None,
-                    },
-                    None,
-                ),
-                Resource::new(
-                    RawResource {
-                        uri: "raindrop://tags/all".into(),
-                        name: "All Tags".into(),
-                        description: Some("List of all tags across all bookmarks".into()),
-                        mime_type: Some("application/json".into()),
-                        size: None,
-                    },
-                    None,
-                ),
-                Resource::new(
-                    RawResource {
-                        uri: "raindrop://highlights/all".into(),
-                        name: "All Highlights".into(),
-                        description: Some("List of all highlights across all bookmarks".into()),
-                        mime_type: Some("application/json".into()),
-                        size: None,
-                    },
-                    None,
-                ),
-                Resource::new(
-                    RawResource {
-                        uri: "raindrop://user/info".into(),
-                        name: "User Info".into(),
-                        description: Some("Current user account information".into()),
-                        mime_type: Some("application/json".into()),
-                        size: None,
-                    },
-                    None,
-                ),
-                Resource::new(
-                    RawResource {
-                        uri: "raindrop://user/stats".into(),
-                        name: "User Statistics".into(),
-                        description: Some("User account statistics".into()),
-                        mime_type: Some("application/json".into()),
-                        size: None,
-                    },
-                    None,
+            resources,
+            next_cursor,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let mut request = request;
+        let switched = self.with_profile_argument(&mut request)?;
+        let server = switched.as_ref().unwrap_or(self);
+
+        let revision = server.journal_revision();
+        let result = server
+            .tool_router
+            .call(ToolCallContext::new(server, request, context))
+            .await;
+        // Every mutation is journaled, so new journal activity means cached data may be stale
+        if server.journal_revision() != revision {
+            server.completions.invalidate();
+            if !self.subscriptions.is_empty() {
+                self.subscriptions.recheck();
+            }
+        }
+        result
+    }
+
+    async fn list_tools(
+        &self,
+        _: Option<PaginatedRequestParam>,
+        _: RequestContext<RoleServer>,
+    ) -> std::result::Result<ListToolsResult, McpError> {
+        let mut tools = self.tool_router.list_all();
+        if self.accounts.len() > 1 {
+            let profile = serde_json::json!({
+                "type": "string",
+                "enum": self.accounts.keys().collect::<Vec<_>>(),
+                "description": format!(
+                    "Profile (Raindrop account) to act as (default: {})",
+                    self.profile
                 ),
-            ],
+            });
+            for tool in &mut tools {
+                let schema = Arc::make_mut(&mut tool.input_schema);
+                if let Some(properties) = schema
+                    .entry("properties")
+                    .or_insert_with(|| serde_json::json!({}))
+                    .as_object_mut()
+                {
+                    properties.insert("profile".to_string(), profile.clone());
+                }
+            }
+        }
+        Ok(ListToolsResult::with_all_items(tools))
+    }
+
+    async fn complete(
+        &self,
+        CompleteRequestParam { r#ref, argument }: CompleteRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> std::result::Result<CompleteResult, McpError> {
+        let Some(kind) = CompletionKind::for_argument(&r#ref, &argument.name) else {
+            return Ok(CompleteResult {
+                completion: CompletionInfo {
+                    values: vec![],
+                    total: Some(0),
+                    has_more: Some(false),
+                },
+            });
+        };
+        let candidates = self
+            .completion_candidates(kind.source())
+            .await
+            .map_err(Self::to_mcp_error)?;
+        Ok(CompleteResult {
+            completion: completion::complete(&argument.value, &candidates, kind),
+        })
+    }
+
+    async fn subscribe(
+        &self,
+        SubscribeRequestParam { uri }: SubscribeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> std::result::Result<(), McpError> {
+        let route = ResourceRoute::parse(&uri)?;
+        let fingerprint = self
+            .resource_fingerprint(route)
+            .await
+            .map_err(Self::to_mcp_error)?;
+        info!("Subscribing to {}", uri);
+        self.subscriptions.subscribe(&uri, fingerprint);
+        if self.subscriptions.start_watching() {
+            tokio::spawn(self.clone().watch_subscriptions(context.peer));
+        }
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        UnsubscribeRequestParam { uri }: UnsubscribeRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> std::result::Result<(), McpError> {
+        if self.subscriptions.unsubscribe(&uri) {
+            info!("Unsubscribed from {}", uri);
+        }
+        Ok(())
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _: Option<PaginatedRequestParam>,
+        _: RequestContext<RoleServer>,
+    ) -> std::result::Result<ListResourceTemplatesResult, McpError> {
+        Ok(ListResourceTemplatesResult {
+            resource_templates: resources::resource_templates(),
             next_cursor: None,
         })
     }
@@ -760,80 +3075,11 @@ impl ServerHandler for McpServer {
         ReadResourceRequestParam { uri }: ReadResourceRequestParam,
         _: RequestContext<RoleServer>,
     ) -> std::result::Result<ReadResourceResult, McpError> {
-        match uri.as_str() {
-            "raindrop://collections/all" => match self.client.get_collections().await {
-                Ok(collections) => Ok(ReadResourceResult {
-                    contents: vec![ResourceContents::text(
-                        format!(
-                            "Found {} collections\n\nData: {}",
-                            collections.len(),
-                            serde_json::to_string_pretty(&collections)
-                                .unwrap_or_else(|_| "Error serializing data".to_string())
-                        ),
-                        "raindrop://collections/all",
-                    )],
-                }),
-                Err(e) => Err(Self::to_mcp_error(e)),
-            },
-            "raindrop://tags/all" => match self.client.get_tags(None).await {
-                Ok(tags) => Ok(ReadResourceResult {
-                    contents: vec![ResourceContents::text(
-                        format!(
-                            "Found {} tags\n\nData: {}",
-                            tags.len(),
-                            serde_json::to_string_pretty(&tags)
-                                .unwrap_or_else(|_| "Error serializing data".to_string())
-                        ),
-                        "raindrop://tags/all",
-                    )],
-                }),
-                Err(e) => Err(Self::to_mcp_error(e)),
-            },
-            "raindrop://highlights/all" => match self.client.get_all_highlights(None, None).await {
-                Ok(highlights) => Ok(ReadResourceResult {
-                    contents: vec![ResourceContents::text(
-                        format!(
-                            "Found {} highlights\n\nData: {}",
-                            highlights.len(),
-                            serde_json::to_string_pretty(&highlights)
-                                .unwrap_or_else(|_| "Error serializing data".to_string())
-                        ),
-                        "raindrop://highlights/all",
-                    )],
-                }),
-                Err(e) => Err(Self::to_mcp_error(e)),
-            },
-            "raindrop://user/info" => match self.client.get_user_info().await {
-                Ok(user) => Ok(ReadResourceResult {
-                    contents: vec![ResourceContents::text(
-                        format!(
-                            "User information retrieved successfully\n\nData: {}",
-                            serde_json::to_string_pretty(&user)
-                                .unwrap_or_else(|_| "Error serializing data".to_string())
-                        ),
-                        "raindrop://user/info",
-                    )],
-                }),
-                Err(e) => Err(Self::to_mcp_error(e)),
-            },
-            "raindrop://user/stats" => match self.client.get_user_stats(None).await {
-                Ok(stats) => Ok(ReadResourceResult {
-                    contents: vec![ResourceContents::text(
-                        format!(
-                            "User statistics retrieved successfully\n\nData: {}",
-                            serde_json::to_string_pretty(&stats)
-                                .unwrap_or_else(|_| "Error serializing data".to_string())
-                        ),
-                        "raindrop://user/stats",
-                    )],
-                }),
-                Err(e) => Err(Self::to_mcp_error(e)),
-            },
-            _ => Err(McpError::invalid_params(
-                format!("Unknown resource ID: {uri}"),
-                None,
-            )),
-        }
+        let route = ResourceRoute::parse(&uri)?;
+        let text = self.read_route(route).await.map_err(Self::to_mcp_error)?;
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::text(text, uri)],
+        })
     }
 
     async fn list_prompts(
@@ -841,49 +3087,91 @@ impl ServerHandler for McpServer {
         _request: Option<PaginatedRequestParam>,
         _: RequestContext<RoleServer>,
     ) -> Result<ListPromptsResult, McpError> {
-        Ok(ListPromptsResult {
-            next_cursor: None,
-            prompts: vec![
-                Prompt::new(
-                    "bookmark-summary",
-                    Some("Generate a summary of bookmarks in a specific collection"),
-                    Some(vec![PromptArgument {
+        let max_tokens = || PromptArgument {
+            name: "maxTokens".to_string(),
+            description: Some(format!(
+                "Approximate token budget for the embedded data (default: {})",
+                prompts::DEFAULT_PROMPT_TOKENS
+            )),
+            required: Some(false),
+        };
+        let mut prompts = vec![
+            Prompt::new(
+                "bookmark-summary",
+                Some("Generate a summary of bookmarks in a specific collection"),
+                Some(vec![
+                    PromptArgument {
                         name: "collectionId".to_string(),
                         description: Some("ID of the collection to summarize".to_string()),
                         required: Some(true),
-                    }]),
-                ),
-                Prompt::new(
-                    "organize-unsorted",
-                    Some("Suggest organization for unsorted bookmarks"),
-                    Some(vec![PromptArgument {
+                    },
+                    max_tokens(),
+                ]),
+            ),
+            Prompt::new(
+                "organize-unsorted",
+                Some("Suggest organization for unsorted bookmarks"),
+                Some(vec![
+                    PromptArgument {
                         name: "limit".to_string(),
                         description: Some(
                             "Maximum number of bookmarks to analyze (default: 50)".to_string(),
                         ),
                         required: Some(false),
-                    }]),
-                ),
-                Prompt::new(
-                    "weekly-digest",
-                    Some("Create a digest of bookmarks added this week"),
-                    None,
-                ),
-                Prompt::new(
-                    "tag-suggestions",
-                    Some("Provide tag optimization suggestions"),
-                    Some(vec![PromptArgument {
+                    },
+                    max_tokens(),
+                ]),
+            ),
+            Prompt::new(
+                "weekly-digest",
+                Some("Create a digest of bookmarks added this week"),
+                Some(vec![
+                    PromptArgument {
+                        name: "days".to_string(),
+                        description: Some("Number of days to cover (default: 7)".to_string()),
+                        required: Some(false),
+                    },
+                    max_tokens(),
+                ]),
+            ),
+            Prompt::new(
+                "tag-suggestions",
+                Some("Provide tag optimization suggestions"),
+                Some(vec![
+                    PromptArgument {
                         name: "collectionId".to_string(),
                         description: Some("ID of the collection to analyze (optional)".to_string()),
                         required: Some(false),
-                    }]),
-                ),
-                Prompt::new(
-                    "duplicate-finder",
-                    Some("Find potential duplicate bookmarks"),
-                    None,
-                ),
-            ],
+                    },
+                    max_tokens(),
+                ]),
+            ),
+            Prompt::new(
+                "duplicate-finder",
+                Some("Find potential duplicate bookmarks"),
+                Some(vec![max_tokens()]),
+            ),
+        ];
+        prompts.extend(
+            self.prompt_templates
+                .current()
+                .templates
+                .values()
+                .map(|template| {
+                    let mut arguments = template.prompt_arguments();
+                    if !arguments.iter().any(|a| a.name == "maxTokens") {
+                        arguments.push(max_tokens());
+                    }
+                    Prompt::new(
+                        &template.name,
+                        template.description.clone(),
+                        Some(arguments),
+                    )
+                }),
+        );
+        Ok(ListPromptsResult {
+            next_cursor: None,
+            prompts,
         })
     }
 
@@ -892,107 +3180,514 @@ impl ServerHandler for McpServer {
         GetPromptRequestParam { name, arguments }: GetPromptRequestParam,
         _: RequestContext<RoleServer>,
     ) -> Result<GetPromptResult, McpError> {
+        let args = arguments.as_ref();
+        let max_tokens = prompts::token_budget(args);
         match name.as_str() {
             "bookmark-summary" => {
-                let collection_id = arguments
-                    .and_then(|args| args.get("collectionId")?.as_i64())
-                    .ok_or_else(|| {
-                        McpError::invalid_params("Missing required parameter: collectionId", None)
-                    })?;
+                let collection_id = prompts::int_arg(args, "collectionId").ok_or_else(|| {
+                    McpError::invalid_params("Missing required parameter: collectionId", None)
+                })?;
 
-                // Fetch collection info to include in prompt
                 let collection = self
                     .client
                     .get_collection(collection_id)
                     .await
-                    .map_err(|_| McpError::internal_error("Failed to fetch collection", None))?;
+                    .map_err(Self::to_mcp_error)?;
+                // Pages stop once the bookmarks so far fill the budget
+                let (fetched, total) = self
+                    .client
+                    .get_bookmarks_until(
+                        SearchParams {
+                            collection: Some(collection_id),
+                            ..Default::default()
+                        },
+                        |fetched| {
+                            prompts::lines_tokens(fetched.iter().map(PromptBookmark::from))
+                                > max_tokens
+                        },
+                    )
+                    .await
+                    .map_err(Self::to_mcp_error)?;
+                let bookmarks: Vec<PromptBookmark> =
+                    fetched.iter().map(PromptBookmark::from).collect();
 
                 Ok(GetPromptResult {
                     description: Some(format!("Summary for collection: {}", collection.title)),
-                    messages: vec![PromptMessage {
-                        role: PromptMessageRole::User,
-                        content: PromptMessageContent::text(format!(
-                            "Please analyze the bookmarks in the '{}' collection (ID: {}). \
-                             Provide: 1) Overview of main topics, 2) Key resources, \
-                             3) Content patterns, 4) Organization suggestions.",
-                            collection.title, collection_id
-                        )),
-                    }],
+                    messages: vec![
+                        PromptMessage::new_text(
+                            PromptMessageRole::User,
+                            format!(
+                                "Please analyze the {} bookmarks in the '{}' collection (ID: {}), \
+                                 listed in the attached resource. Provide: 1) Overview of main \
+                                 topics, 2) Key resources, 3) Content patterns, \
+                                 4) Organization suggestions.",
+                                total, collection.title, collection_id
+                            ),
+                        ),
+                        prompts::resource_message(
+                            ResourceRoute::CollectionBookmarks {
+                                id: collection_id,
+                                page: None,
+                                sort: None,
+                            }
+                            .uri(),
+                            prompts::render_first_lines(&bookmarks, total, max_tokens),
+                        ),
+                    ],
                 })
             }
             "organize-unsorted" => {
-                let limit = arguments
-                    .and_then(|args| args.get("limit")?.as_i64())
-                    .unwrap_or(50) as i32;
+                let limit = prompts::int_arg(args, "limit")
+                    .filter(|limit| *limit > 0)
+                    .unwrap_or(50) as usize;
+
+                let unsorted: Vec<PromptBookmark> = self
+                    .client
+                    .get_recent_bookmarks(UNSORTED_COLLECTION_ID, limit, None)
+                    .await
+                    .map_err(Self::to_mcp_error)?
+                    .iter()
+                    .map(PromptBookmark::from)
+                    .collect();
+                let collections: Vec<serde_json::Value> = self
+                    .client
+                    .get_all_collections()
+                    .await
+                    .map_err(Self::to_mcp_error)?
+                    .iter()
+                    .map(|c| {
+                        serde_json::json!({
+                            "id": c.id,
+                            "title": c.title,
+                            "count": c.count,
+                            "parent": c.parent.as_ref().map(|p| p.id),
+                        })
+                    })
+                    .collect();
+                // A quarter of the budget for the candidate collections, the rest for bookmarks
+                let collection_tokens = max_tokens / 4;
 
                 Ok(GetPromptResult {
                     description: Some(
                         "Organization suggestions for unsorted bookmarks".to_string(),
                     ),
-                    messages: vec![PromptMessage {
-                        role: PromptMessageRole::User,
-                        content: PromptMessageContent::text(format!(
-                            "Analyze the {limit} most recent unsorted bookmarks and suggest: \
-                             1) Appropriate collections/folders, 2) Relevant tags, \
-                             3) Grouping by topic or type, 4) Priority for review."
-                        )),
-                    }],
+                    messages: vec![
+                        PromptMessage::new_text(
+                            PromptMessageRole::User,
+                            format!(
+                                "Analyze the {} most recent unsorted bookmarks and suggest: \
+                                 1) Appropriate collections/folders, preferring the existing \
+                                 collections listed below, 2) Relevant tags, \
+                                 3) Grouping by topic or type, 4) Priority for review. \
+                                 Move bookmarks with batch_update_bookmarks once agreed.",
+                                unsorted.len()
+                            ),
+                        ),
+                        prompts::resource_message(
+                            ResourceRoute::AllCollections.uri(),
+                            prompts::render_lines(&collections, collection_tokens),
+                        ),
+                        prompts::resource_message(
+                            ResourceRoute::CollectionBookmarks {
+                                id: UNSORTED_COLLECTION_ID,
+                                page: None,
+                                sort: Some("-created".to_string()),
+                            }
+                            .uri(),
+                            prompts::render_lines(&unsorted, max_tokens - collection_tokens),
+                        ),
+                    ],
+                })
+            }
+            "weekly-digest" => {
+                let days = prompts::int_arg(args, "days")
+                    .filter(|days| *days > 0)
+                    .unwrap_or(7);
+                let since = Utc::now() - chrono::Duration::days(days);
+
+                let bookmarks = self
+                    .client
+                    .get_recent_bookmarks(0, usize::MAX, Some(since))
+                    .await
+                    .map_err(Self::to_mcp_error)?;
+                let titles = self.collection_titles().await.map_err(Self::to_mcp_error)?;
+                let mut by_collection: HashMap<i64, Vec<PromptBookmark>> = HashMap::new();
+                for bookmark in &bookmarks {
+                    by_collection
+                        .entry(bookmark.collection.id)
+                        .or_default()
+                        .push(PromptBookmark::from(bookmark));
+                }
+                let mut groups: Vec<(String, Vec<PromptBookmark>)> = by_collection
+                    .into_iter()
+                    .map(|(id, items)| {
+                        let title = titles
+                            .get(&id)
+                            .cloned()
+                            .unwrap_or_else(|| format!("Collection {id}"));
+                        (title, items)
+                    })
+                    .collect();
+                groups.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+
+                Ok(GetPromptResult {
+                    description: Some("Weekly bookmark digest".to_string()),
+                    messages: vec![
+                        PromptMessage::new_text(
+                            PromptMessageRole::User,
+                            format!(
+                                "Create a digest of the {} bookmarks added in the past {days} \
+                                 days, grouped by collection in the attached resource. \
+                                 Include: 1) Total count and breakdown by collection, \
+                                 2) Key themes and topics, 3) Most important additions, \
+                                 4) Recommendations for organization or follow-up.",
+                                bookmarks.len()
+                            ),
+                        ),
+                        prompts::resource_message(
+                            ResourceRoute::CollectionBookmarks {
+                                id: 0,
+                                page: None,
+                                sort: Some("-created".to_string()),
+                            }
+                            .uri(),
+                            prompts::render_grouped(&groups, max_tokens),
+                        ),
+                    ],
                 })
             }
-            "weekly-digest" => Ok(GetPromptResult {
-                description: Some("Weekly bookmark digest".to_string()),
-                messages: vec![PromptMessage {
-                    role: PromptMessageRole::User,
-                    content: PromptMessageContent::text(
-                        "Create a weekly digest of bookmarks added in the past 7 days. \
-                             Include: 1) Total count and breakdown by collection, \
-                             2) Key themes and topics, 3) Most important additions, \
-                             4) Recommendations for organization or follow-up."
-                            .to_string(),
-                    ),
-                }],
-            }),
             "tag-suggestions" => {
-                let collection_id = arguments.and_then(|args| args.get("collectionId")?.as_i64());
+                let collection_id = prompts::int_arg(args, "collectionId");
 
-                let prompt_text = if let Some(id) = collection_id {
+                let tags = self
+                    .client
+                    .get_tags(collection_id)
+                    .await
+                    .map_err(Self::to_mcp_error)?;
+                let suggestions = tags::suggest_merges(&tags, &self.tag_aliases.list(), None);
+                let suggestion_tokens = max_tokens / 4;
+                let data = format!(
+                    "Tags with bookmark counts:\n{}\nMerge candidates found locally \
+                     (case, separator, plural, spelling and alias variants):\n{}",
+                    prompts::render_lines(&tags, max_tokens - suggestion_tokens),
+                    prompts::render_lines(&suggestions, suggestion_tokens)
+                );
+
+                let mut prompt_text = if let Some(id) = collection_id {
                     format!(
-                        "Analyze tags in collection ID {id} and provide: \
-                         1) Unused or redundant tags, 2) Tag consolidation suggestions, \
-                         3) Missing tags based on content, 4) Tag hierarchy recommendations."
+                        "Analyze the {} tags in collection ID {id}, listed in the attached \
+                         resource, and provide: 1) Unused or redundant tags, \
+                         2) Tag consolidation suggestions, 3) Missing tags based on content, \
+                         4) Tag hierarchy recommendations.",
+                        tags.len()
                     )
                 } else {
-                    "Analyze all tags across the library and provide: \
-                     1) Unused or redundant tags, 2) Tag consolidation suggestions, \
-                     3) Popular tag patterns, 4) Tag hierarchy recommendations."
-                        .to_string()
+                    format!(
+                        "Analyze the {} tags across the library, listed in the attached \
+                         resource, and provide: 1) Unused or redundant tags, \
+                         2) Tag consolidation suggestions, 3) Popular tag patterns, \
+                         4) Tag hierarchy recommendations.",
+                        tags.len()
+                    )
                 };
+                prompt_text.push_str(
+                    " Use tag_analytics and get_tag_tree for co-occurrence and hierarchy \
+                     details rather than guessing from tag names, and apply agreed merges with \
+                     merge_tags.",
+                );
 
                 Ok(GetPromptResult {
                     description: Some("Tag optimization suggestions".to_string()),
-                    messages: vec![PromptMessage {
-                        role: PromptMessageRole::User,
-                        content: PromptMessageContent::text(prompt_text),
-                    }],
+                    messages: vec![
+                        PromptMessage::new_text(PromptMessageRole::User, prompt_text),
+                        prompts::resource_message(ResourceRoute::AllTags.uri(), data),
+                    ],
                 })
             }
-            "duplicate-finder" => Ok(GetPromptResult {
-                description: Some("Find duplicate bookmarks".to_string()),
-                messages: vec![PromptMessage {
-                    role: PromptMessageRole::User,
-                    content: PromptMessageContent::text(
-                        "Identify potential duplicate bookmarks by analyzing: \
-                             1) Similar URLs (including www/non-www variants), \
-                             2) Similar titles, 3) Same content in different locations, \
-                             4) Recommendations for which duplicates to keep or merge."
-                            .to_string(),
-                    ),
+            "duplicate-finder" => {
+                // Newest first, stopping once the duplicates found so far fill the budget
+                let (bookmarks, total) = self
+                    .client
+                    .get_bookmarks_until(
+                        SearchParams {
+                            sort: Some("-created".to_string()),
+                            ..Default::default()
+                        },
+                        |fetched| {
+                            prompts::lines_tokens(prompts::find_duplicates(fetched)) > max_tokens
+                        },
+                    )
+                    .await
+                    .map_err(Self::to_mcp_error)?;
+                let groups = prompts::find_duplicates(&bookmarks);
+                let scanned = if bookmarks.len() < total {
+                    format!("the {} newest of {total}", bookmarks.len())
+                } else {
+                    total.to_string()
+                };
+
+                Ok(GetPromptResult {
+                    description: Some("Find duplicate bookmarks".to_string()),
+                    messages: vec![
+                        PromptMessage::new_text(
+                            PromptMessageRole::User,
+                            format!(
+                                "The attached resource lists {} groups of potential duplicates \
+                                 among {} bookmarks. Groups with reason \"url\" share a URL once \
+                                 scheme, www, trailing slashes and tracking parameters are \
+                                 ignored; groups with reason \"title\" share a title but not a \
+                                 URL. For each group, decide whether the bookmarks are really \
+                                 the same content and recommend which to keep, merging tags \
+                                 and notes into it.",
+                                groups.len(),
+                                scanned
+                            ),
+                        ),
+                        prompts::resource_message(
+                            ResourceRoute::CollectionBookmarks {
+                                id: 0,
+                                page: None,
+                                sort: None,
+                            }
+                            .uri(),
+                            prompts::render_lines(&groups, max_tokens),
+                        ),
+                    ],
+                })
+            }
+            _ => self.get_template_prompt(&name, args, max_tokens).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_PROFILE;
+    use serde_json::json;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path, query_param},
+    };
+
+    fn two_profile_server() -> McpServer {
+        let config = Config::parse(
+            r#"
+default_profile = "a"
+
+[profiles.a]
+token = "token-a"
+base_url = "http://127.0.0.1:9"
+read_only = true
+cache = { pages = false }
+
+[profiles.b]
+token = "token-b"
+base_url = "http://127.0.0.1:9"
+read_only = true
+cache = { pages = false }
+"#,
+        )
+        .unwrap();
+        McpServer::from_config(&config, None).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_confirmation_tokens_stay_with_their_profile() {
+        let server = two_profile_server();
+        let token = server.confirmations.issue("empty_trash").unwrap();
+
+        let mut request = CallToolRequestParam {
+            name: "empty_trash".into(),
+            arguments: serde_json::json!({"profile": "b", "confirmationToken": token})
+                .as_object()
+                .cloned(),
+        };
+        let as_b = server.with_profile_argument(&mut request).unwrap().unwrap();
+        let result = as_b
+            .empty_trash(Parameters(EmptyTrashParams {
+                confirmation_token: Some(token.clone()),
+            }))
+            .await;
+        assert!(result.contains("Unknown or already used"), "{result}");
+
+        // The token was never spent, so profile A can still use it
+        assert!(server.confirmations.redeem(&token, "empty_trash").is_ok());
+    }
+
+    fn read_only_server(api: &MockServer) -> McpServer {
+        let config = Config::parse(&format!(
+            r#"
+[profiles.default]
+token = "token"
+base_url = "{}"
+read_only = true
+cache = {{ pages = false }}
+"#,
+            api.uri()
+        ))
+        .unwrap();
+        McpServer::from_config(&config, None).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_dashboard_fingerprint_reads_one_bookmark() {
+        let api = MockServer::start().await;
+        let newest = |title: &str, count: i32| {
+            json!({
+                "items": [{
+                    "_id": 1,
+                    "title": title,
+                    "excerpt": "",
+                    "note": "",
+                    "type": "link",
+                    "tags": [],
+                    "cover": "",
+                    "link": "https://example.com",
+                    "domain": "example.com",
+                    "created": "2024-01-01T00:00:00Z",
+                    "lastUpdate": format!("2024-01-0{count}T00:00:00Z"),
+                    "user": { "$id": 1 },
+                    "collection": { "$id": 10 },
+                    "important": false,
+                    "broken": false
                 }],
-            }),
-            _ => Err(McpError::invalid_params(
-                format!("Unknown prompt: {name}"),
-                None,
-            )),
+                "count": count
+            })
+        };
+        Mock::given(method("GET"))
+            .and(path("/raindrops/0"))
+            .and(query_param("sort", "-lastUpdate"))
+            .and(query_param("perpage", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(newest("First", 1)))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&api)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/raindrops/0"))
+            .and(query_param("sort", "-lastUpdate"))
+            .and(query_param("perpage", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(newest("Second", 2)))
+            .expect(1)
+            .mount(&api)
+            .await;
+        for route in ["/collections", "/collections/childrens"] {
+            Mock::given(method("GET"))
+                .and(path(route))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "items": [] })))
+                .mount(&api)
+                .await;
         }
+        let server = read_only_server(&api);
+
+        let first = server
+            .resource_fingerprint(ResourceRoute::Dashboard)
+            .await
+            .unwrap();
+        let second = server
+            .resource_fingerprint(ResourceRoute::Dashboard)
+            .await
+            .unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_merge_tags_needs_confirmation() {
+        let api = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": [{ "_id": "js", "count": 3 }, { "_id": "javascript", "count": 5 }]
+            })))
+            .mount(&api)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/raindrops/0"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({ "items": [], "count": 0 })),
+            )
+            .mount(&api)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/tags/0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "result": true })))
+            .expect(1)
+            .mount(&api)
+            .await;
+        let config = Config::parse(&format!(
+            "[profiles.default]\nbase_url = \"{}\"\n",
+            api.uri()
+        ))
+        .unwrap();
+        let server = McpServer::for_session(
+            DEFAULT_PROFILE,
+            &config.profiles()[DEFAULT_PROFILE],
+            "token",
+            Arc::new(PageFetcher::new(None).unwrap()),
+            Arc::new(PromptTemplates::new(None, prompts::BUILTIN_PROMPTS)),
+        )
+        .unwrap();
+        let merge = |into: &str, confirmation_token: Option<String>| {
+            server.merge_tags(Parameters(MergeTagsParams {
+                tags: vec!["js".to_string()],
+                into: into.to_string(),
+                collection: None,
+                confirmation_token,
+            }))
+        };
+
+        let preview: serde_json::Value =
+            serde_json::from_str(&merge("javascript", None).await).unwrap();
+        assert_eq!(preview["confirmationRequired"], true, "{preview}");
+        assert_eq!(preview["preview"]["itemCount"], 3);
+        let token = preview["confirmationToken"].as_str().unwrap().to_string();
+
+        // A token only confirms the merge it previewed
+        let other = merge("ecmascript", Some(token.clone())).await;
+        assert!(other.contains("was issued for"), "{other}");
+        let merged = merge("javascript", Some(token)).await;
+        assert!(merged.contains(r#""success":true"#), "{merged}");
+    }
+
+    #[tokio::test]
+    async fn test_dashboard_reports_api_errors_on_read_only_profiles() {
+        let api = MockServer::start().await;
+        for route in ["/collections", "/collections/childrens"] {
+            Mock::given(method("GET"))
+                .and(path(route))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "items": [] })))
+                .mount(&api)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/raindrops/0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": [],
+                "count": 0
+            })))
+            .mount(&api)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/raindrops/7"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("stats offline"))
+            .mount(&api)
+            .await;
+        let server = read_only_server(&api);
+        let dashboard = |collection: Option<CollectionArg>| {
+            server.get_library_dashboard(Parameters(LibraryDashboardParams {
+                collection,
+                top: None,
+            }))
+        };
+
+        // Reading needs no write access
+        let whole: serde_json::Value = serde_json::from_str(&dashboard(None).await).unwrap();
+        assert_eq!(whole["dashboard"]["bookmarkCount"], 0, "{whole}");
+
+        let failed = dashboard(Some(CollectionArg::Id(7))).await;
+        assert!(failed.contains("stats offline"), "{failed}");
+        let unknown = dashboard(Some(CollectionArg::Name("Missing".to_string()))).await;
+        assert!(unknown.contains("Collection 'Missing'"), "{unknown}");
     }
 }
@@ -0,0 +1,501 @@
+use axum::{
+    Router,
+    extract::Request,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+};
+use http::{HeaderMap, StatusCode, header};
+use rmcp::{
+    RoleServer, Service,
+    model::{
+        ClientNotification, ClientRequest, ErrorData as McpError, Extensions, ServerInfo,
+        ServerResult,
+    },
+    service::{NotificationContext, RequestContext},
+    transport::streamable_http_server::{
+        StreamableHttpServerConfig, StreamableHttpService, session::local::LocalSessionManager,
+    },
+};
+use std::sync::{Arc, OnceLock};
+use tracing::{debug, warn};
+
+use crate::{
+    config::{Config, Profile},
+    content::fetcher::PageFetcher,
+    error::RaindropMcpError,
+    mcp::{McpServer, prompt_templates::PromptTemplates, prompts},
+};
+
+/// Path the MCP endpoint is served at.
+pub const MCP_PATH: &str = "/mcp";
+
+/// Settings every HTTP session starts from. None of them identify a Raindrop user: each
+/// session brings its own token as `Authorization: Bearer <token>` on every request.
+pub struct SessionFactory {
+    profile: String,
+    settings: Profile,
+    fetcher: Arc<PageFetcher>,
+    prompt_templates: Arc<PromptTemplates>,
+}
+
+impl SessionFactory {
+    /// Sessions using `profile`'s settings (or the config's default), but never its token.
+    pub fn from_config(config: &Config, profile: Option<&str>) -> Result<Self, RaindropMcpError> {
+        let name = config.primary_profile(profile)?;
+        let settings = config.profiles().remove(&name).unwrap_or_default();
+        Self::new(
+            name,
+            settings,
+            Arc::new(PromptTemplates::open_default(prompts::BUILTIN_PROMPTS)),
+        )
+    }
+
+    pub fn new(
+        profile: String,
+        settings: Profile,
+        prompt_templates: Arc<PromptTemplates>,
+    ) -> Result<Self, RaindropMcpError> {
+        Ok(Self {
+            // Remote callers must not reach the server's own machine or networks. Nor is
+            // there a page cache, which would hand one session's pages to another.
+            fetcher: Arc::new(PageFetcher::new(None)?.public_only()?),
+            profile,
+            settings,
+            prompt_templates,
+        })
+    }
+
+    /// A session that has not authenticated yet.
+    pub fn session(self: &Arc<Self>) -> SessionServer {
+        SessionServer {
+            factory: self.clone(),
+            session: OnceLock::new(),
+        }
+    }
+
+    fn open(&self, access_token: &str) -> Result<Session, McpError> {
+        let server = McpServer::for_session(
+            &self.profile,
+            &self.settings,
+            access_token,
+            self.fetcher.clone(),
+            self.prompt_templates.clone(),
+        )
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        Ok(Session {
+            access_token: access_token.into(),
+            server,
+        })
+    }
+}
+
+/// HTTP routes serving MCP at [`MCP_PATH`], one [`SessionServer`] per MCP session.
+pub fn router(factory: SessionFactory) -> Router {
+    let factory = Arc::new(factory);
+    let service = StreamableHttpService::new(
+        move || Ok(factory.session()),
+        Arc::new(LocalSessionManager::default()),
+        StreamableHttpServerConfig::default(),
+    );
+    Router::new()
+        .route_service(MCP_PATH, service)
+        .layer(middleware::from_fn(require_bearer))
+}
+
+// Turns away requests without credentials before they can open a session
+async fn require_bearer(request: Request, next: Next) -> Response {
+    if bearer_token(request.headers()).is_none() {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "Unauthorized: send the Raindrop access token as a bearer token",
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// The token of an `Authorization: Bearer <token>` header.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+/// MCP service for one HTTP session.
+///
+/// The first request's bearer token picks the Raindrop user, and a server holding a client
+/// for that token alone is created. Every later request must carry the same token, so a
+/// leaked session ID cannot be used with other credentials.
+pub struct SessionServer {
+    factory: Arc<SessionFactory>,
+    session: OnceLock<Session>,
+}
+
+struct Session {
+    access_token: Box<str>,
+    server: McpServer,
+}
+
+impl SessionServer {
+    fn authenticate(&self, extensions: &Extensions) -> Result<&McpServer, McpError> {
+        let token = extensions
+            .get::<http::request::Parts>()
+            .and_then(|parts| bearer_token(&parts.headers))
+            .ok_or_else(|| McpError::invalid_request("Missing bearer token", None))?;
+        let session = match self.session.get() {
+            Some(session) => session,
+            None => {
+                debug!("Opening MCP session for a new bearer token");
+                // A concurrent first request may win; its token is checked below like any other
+                let _ = self.session.set(self.factory.open(token)?);
+                self.session.get().expect("session was just set")
+            }
+        };
+        if !same_token(&session.access_token, token) {
+            warn!("Rejected a request whose bearer token does not match its session");
+            return Err(McpError::invalid_request(
+                "Bearer token does not match this session",
+                None,
+            ));
+        }
+        Ok(&session.server)
+    }
+}
+
+// Dropped once the session closes; its resource watcher must not outlive it
+impl Drop for SessionServer {
+    fn drop(&mut self) {
+        if let Some(session) = self.session.get() {
+            session.server.close_subscriptions();
+        }
+    }
+}
+
+impl Service<RoleServer> for SessionServer {
+    async fn handle_request(
+        &self,
+        request: ClientRequest,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ServerResult, McpError> {
+        let server = self.authenticate(&context.extensions)?;
+        server.handle_request(request, context).await
+    }
+
+    async fn handle_notification(
+        &self,
+        notification: ClientNotification,
+        context: NotificationContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        let server = self.authenticate(&context.extensions)?;
+        server.handle_notification(notification, context).await
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        McpServer::server_info()
+    }
+}
+
+// Compares in time independent of where the tokens differ
+fn same_token(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::{Value, json};
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{header as header_matcher, method, path},
+    };
+
+    const ACCEPT: &str = "application/json, text/event-stream";
+
+    struct McpHttpClient {
+        http: reqwest::Client,
+        url: String,
+    }
+
+    impl McpHttpClient {
+        async fn post(
+            &self,
+            token: Option<&str>,
+            session_id: Option<&str>,
+            body: Value,
+        ) -> reqwest::Response {
+            let mut request = self
+                .http
+                .post(&self.url)
+                .header(reqwest::header::ACCEPT, ACCEPT)
+                .json(&body);
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+            if let Some(session_id) = session_id {
+                request = request.header("Mcp-Session-Id", session_id);
+            }
+            request.send().await.unwrap()
+        }
+
+        // Opens a session as `token`, returning its ID
+        async fn initialize(&self, token: &str) -> String {
+            let response = self
+                .post(
+                    Some(token),
+                    None,
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": 0,
+                        "method": "initialize",
+                        "params": {
+                            "protocolVersion": "2025-03-26",
+                            "capabilities": {},
+                            "clientInfo": {"name": "test", "version": "0"}
+                        }
+                    }),
+                )
+                .await;
+            let session_id = response.headers()["mcp-session-id"]
+                .to_str()
+                .unwrap()
+                .to_string();
+            assert!(first_message(response).await.get("result").is_some());
+
+            let response = self
+                .post(
+                    Some(token),
+                    Some(&session_id),
+                    json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+                )
+                .await;
+            assert_eq!(response.status(), reqwest::StatusCode::ACCEPTED);
+            session_id
+        }
+
+        async fn call_tool(&self, token: &str, session_id: &str, name: &str) -> Value {
+            self.call_tool_with(token, session_id, name, json!({}))
+                .await
+        }
+
+        async fn call_tool_with(
+            &self,
+            token: &str,
+            session_id: &str,
+            name: &str,
+            arguments: Value,
+        ) -> Value {
+            let response = self
+                .post(
+                    Some(token),
+                    Some(session_id),
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": 1,
+                        "method": "tools/call",
+                        "params": {"name": name, "arguments": arguments}
+                    }),
+                )
+                .await;
+            first_message(response).await
+        }
+    }
+
+    // First JSON-RPC message of a server-sent event stream
+    async fn first_message(mut response: reqwest::Response) -> Value {
+        let mut text = String::new();
+        while let Some(chunk) = response.chunk().await.unwrap() {
+            text.push_str(&String::from_utf8_lossy(&chunk));
+            let events = text.rsplit_once("\n\n").map_or("", |(events, _)| events);
+            if let Some(data) = events
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .find(|data| !data.trim().is_empty())
+            {
+                return serde_json::from_str(data.trim()).unwrap();
+            }
+        }
+        panic!("stream ended without a message: {text}");
+    }
+
+    fn tool_text(message: &Value) -> String {
+        message["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    async fn serve(raindrop: &MockServer) -> McpHttpClient {
+        let settings = Profile {
+            base_url: Some(raindrop.uri()),
+            ..Default::default()
+        };
+        let templates = Arc::new(PromptTemplates::new(None, prompts::BUILTIN_PROMPTS));
+        let factory = SessionFactory::new("default".to_string(), settings, templates).unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}{MCP_PATH}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router(factory)).await });
+        McpHttpClient {
+            http: reqwest::Client::new(),
+            url,
+        }
+    }
+
+    async fn mock_collections(raindrop: &MockServer, token: &str, title: &str) {
+        Mock::given(method("GET"))
+            .and(path("/collections"))
+            .and(header_matcher("Authorization", format!("Bearer {token}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "result": true,
+                "items": [{
+                    "_id": 1,
+                    "title": title,
+                    "view": "list",
+                    "sort": 0,
+                    "count": 0,
+                    "user": { "$id": 1 },
+                    "created": "2024-01-01T00:00:00Z",
+                    "lastUpdate": "2024-01-01T00:00:00Z"
+                }]
+            })))
+            .expect(1)
+            .mount(raindrop)
+            .await;
+    }
+
+    #[test]
+    fn test_bearer_token() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+
+        headers.insert(header::AUTHORIZATION, "Bearer abc".parse().unwrap());
+        assert_eq!(bearer_token(&headers), Some("abc"));
+        headers.insert(header::AUTHORIZATION, "bearer  abc ".parse().unwrap());
+        assert_eq!(bearer_token(&headers), Some("abc"));
+        headers.insert(header::AUTHORIZATION, "Basic abc".parse().unwrap());
+        assert_eq!(bearer_token(&headers), None);
+        headers.insert(header::AUTHORIZATION, "Bearer ".parse().unwrap());
+        assert_eq!(bearer_token(&headers), None);
+
+        assert!(same_token("abc", "abc"));
+        assert!(!same_token("abc", "abd"));
+        assert!(!same_token("abc", "abcd"));
+    }
+
+    #[tokio::test]
+    async fn test_sessions_use_their_own_tokens() {
+        let raindrop = MockServer::start().await;
+        mock_collections(&raindrop, "token-alice", "Alice's reading").await;
+        mock_collections(&raindrop, "token-bob", "Bob's recipes").await;
+        let mcp = serve(&raindrop).await;
+
+        let alice = mcp.initialize("token-alice").await;
+        let bob = mcp.initialize("token-bob").await;
+        assert_ne!(alice, bob);
+
+        let text = tool_text(&mcp.call_tool("token-bob", &bob, "get_collections").await);
+        assert!(text.contains("Bob's recipes"), "{text}");
+        assert!(!text.contains("Alice"), "{text}");
+
+        let text = tool_text(
+            &mcp.call_tool("token-alice", &alice, "get_collections")
+                .await,
+        );
+        assert!(text.contains("Alice's reading"), "{text}");
+        assert!(!text.contains("Bob"), "{text}");
+
+        // Every upstream request carried the token of the session that made it
+        for request in raindrop.received_requests().await.unwrap() {
+            let auth = request.headers["authorization"].to_str().unwrap();
+            assert!(
+                auth == "Bearer token-alice" || auth == "Bearer token-bob",
+                "{auth}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_rejects_other_credentials() {
+        let raindrop = MockServer::start().await;
+        mock_collections(&raindrop, "token-alice", "Alice's reading").await;
+        let mcp = serve(&raindrop).await;
+
+        let alice = mcp.initialize("token-alice").await;
+
+        // Bob knowing Alice's session ID gets nothing out of it
+        let message = mcp.call_tool("token-bob", &alice, "get_collections").await;
+        assert!(message.get("result").is_none(), "{message}");
+        assert!(
+            message["error"]["message"]
+                .as_str()
+                .unwrap()
+                .contains("does not match")
+        );
+
+        // Requests without credentials never reach a session
+        let response = mcp
+            .post(
+                None,
+                Some(&alice),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+            )
+            .await;
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let response = mcp.post(None, None, json!({})).await;
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        // Alice's own session still works, and made the only upstream request
+        let text = tool_text(
+            &mcp.call_tool("token-alice", &alice, "get_collections")
+                .await,
+        );
+        assert!(text.contains("Alice's reading"), "{text}");
+    }
+
+    #[test]
+    fn test_sessions_keep_no_page_cache() {
+        // A profile caching pages for its stdio server
+        let mut settings = Profile::default();
+        settings.cache.pages = true;
+        settings.cache.page_dir = Some(std::env::temp_dir().join("raindrop-session-pages"));
+        let templates = Arc::new(PromptTemplates::new(None, prompts::BUILTIN_PROMPTS));
+        let factory = SessionFactory::new("default".to_string(), settings, templates).unwrap();
+
+        // Every session shares this fetcher, so a cache would serve one tenant's pages to another
+        assert_eq!(factory.fetcher.cache_dir(), None);
+    }
+
+    #[tokio::test]
+    async fn test_session_cannot_upload_server_files() {
+        let raindrop = MockServer::start().await;
+        let mcp = serve(&raindrop).await;
+        let alice = mcp.initialize("token-alice").await;
+
+        let secret =
+            std::env::temp_dir().join(format!("raindrop-session-secret-{}", std::process::id()));
+        std::fs::write(&secret, "host secret").unwrap();
+        for tool in ["upload_file", "upload_cover"] {
+            let message = mcp
+                .call_tool_with(
+                    "token-alice",
+                    &alice,
+                    tool,
+                    json!({"id": 1, "path": secret.to_string_lossy()}),
+                )
+                .await;
+            let text = tool_text(&message);
+            assert!(text.contains("disabled"), "{text}");
+        }
+        std::fs::remove_file(&secret).unwrap();
+
+        // Nothing was read from the host and sent upstream
+        assert!(raindrop.received_requests().await.unwrap().is_empty());
+    }
+}
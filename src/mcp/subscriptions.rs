@@ -0,0 +1,180 @@
+use std::{
+    collections::HashMap,
+    env,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tokio::sync::Notify;
+
+/// Seconds between checks of subscribed resources unless `RAINDROP_POLL_INTERVAL_SECS` is set.
+pub const DEFAULT_POLL_INTERVAL_SECS: u64 = 60;
+
+/// Resources a client subscribed to, with the fingerprint each had when last checked.
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    entries: Mutex<HashMap<String, u64>>,
+    recheck: Notify,
+    watching: AtomicBool,
+}
+
+impl Subscriptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts tracking `uri`, whose current state hashes to `fingerprint`.
+    pub fn subscribe(&self, uri: &str, fingerprint: u64) {
+        self.lock().insert(uri.to_string(), fingerprint);
+    }
+
+    /// Returns whether `uri` was subscribed. Removing the last one wakes the watcher so it stops.
+    pub fn unsubscribe(&self, uri: &str) -> bool {
+        let mut entries = self.lock();
+        let removed = entries.remove(uri).is_some();
+        if removed && entries.is_empty() {
+            self.recheck.notify_one();
+        }
+        removed
+    }
+
+    /// Drops every subscription and wakes the watcher so it stops, e.g. when the session ends.
+    pub fn close(&self) {
+        self.lock().clear();
+        self.recheck.notify_one();
+    }
+
+    pub fn uris(&self) -> Vec<String> {
+        let mut uris: Vec<String> = self.lock().keys().cloned().collect();
+        uris.sort();
+        uris
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Records the latest fingerprint of `uri`, returning whether the resource changed.
+    ///
+    /// Unsubscribed URIs are ignored, so a check racing an unsubscribe never notifies.
+    pub fn update(&self, uri: &str, fingerprint: u64) -> bool {
+        match self.lock().get_mut(uri) {
+            Some(previous) if *previous != fingerprint => {
+                *previous = fingerprint;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Wakes the watcher so subscriptions are checked now rather than at the next poll.
+    pub fn recheck(&self) {
+        self.recheck.notify_one();
+    }
+
+    /// Waits for the next poll, or an earlier `recheck`. Without an interval only rechecks wake it.
+    pub async fn wait(&self, interval: Option<Duration>) {
+        match interval {
+            Some(interval) => {
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = self.recheck.notified() => {}
+                }
+            }
+            None => self.recheck.notified().await,
+        }
+    }
+
+    /// Returns true exactly once, for the caller that should start the watcher.
+    pub fn start_watching(&self) -> bool {
+        !self.watching.swap(true, Ordering::SeqCst)
+    }
+
+    /// Lets a new watcher start after the previous one stopped.
+    pub fn stop_watching(&self) {
+        self.watching.store(false, Ordering::SeqCst);
+    }
+
+    /// Stops watching if nothing is subscribed, returning whether the watcher should exit.
+    ///
+    /// Checked under the same lock `subscribe` takes, so a new subscription either keeps this
+    /// watcher running or finds it stopped and starts another.
+    pub fn stop_if_idle(&self) -> bool {
+        let entries = self.lock();
+        if entries.is_empty() {
+            self.watching.store(false, Ordering::SeqCst);
+        }
+        entries.is_empty()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, u64>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Poll interval from `RAINDROP_POLL_INTERVAL_SECS`; `0` disables polling.
+pub fn poll_interval() -> Option<Duration> {
+    let secs = env::var("RAINDROP_POLL_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_POLL_INTERVAL_SECS);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Stable hash of a resource's state, e.g. its `lastUpdate` or rendered body.
+pub fn fingerprint(state: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn test_tracks_changes_per_uri() {
+        let subscriptions = Subscriptions::new();
+        let uri = "raindrop://bookmark/1";
+        subscriptions.subscribe(uri, fingerprint("2024-01-01T00:00:00Z"));
+
+        assert!(!subscriptions.update(uri, fingerprint("2024-01-01T00:00:00Z")));
+        assert!(subscriptions.update(uri, fingerprint("2024-02-01T00:00:00Z")));
+        assert!(!subscriptions.update(uri, fingerprint("2024-02-01T00:00:00Z")));
+        assert!(!subscriptions.update("raindrop://bookmark/2", 1));
+
+        assert!(subscriptions.start_watching());
+        assert!(!subscriptions.start_watching());
+
+        // A recheck issued before the watcher waits is not lost
+        subscriptions.recheck();
+        subscriptions.wait(None).await;
+
+        assert!(!subscriptions.stop_if_idle());
+        assert!(subscriptions.unsubscribe(uri));
+        assert!(!subscriptions.update(uri, 0));
+        assert_eq!(subscriptions.uris(), Vec::<String>::new());
+
+        // Removing the last subscription wakes the watcher, which then stops
+        subscriptions.wait(None).await;
+        assert!(subscriptions.stop_if_idle());
+        assert!(subscriptions.start_watching());
+    }
+
+    #[tokio::test]
+    async fn test_close_drops_subscriptions() {
+        let subscriptions = Subscriptions::new();
+        subscriptions.subscribe("raindrop://bookmark/1", 1);
+        subscriptions.subscribe("raindrop://bookmark/2", 2);
+        assert!(subscriptions.start_watching());
+
+        subscriptions.close();
+        subscriptions.wait(None).await;
+        assert!(subscriptions.is_empty());
+        assert!(subscriptions.stop_if_idle());
+    }
+}
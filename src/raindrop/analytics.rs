@@ -0,0 +1,608 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use super::types::Bookmark;
+
+/// Unread items listed by the dashboard.
+pub const DASHBOARD_UNREAD_LIMIT: usize = 10;
+
+/// Number of bookmarks carrying a tag.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Two tags that appear on the same bookmarks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagPair {
+    pub a: String,
+    pub b: String,
+    pub count: usize,
+}
+
+/// Co-occurrence counts among the most frequent tags; `matrix[i][j]` pairs `tags[i]` and `tags[j]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoOccurrence {
+    pub tags: Vec<String>,
+    pub matrix: Vec<Vec<usize>>,
+}
+
+/// Tag usage among bookmarks created in one month.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthlyTagUsage {
+    /// `YYYY-MM`
+    pub month: String,
+    pub bookmarks: usize,
+    pub tagged: usize,
+    /// Counts for the most frequent tags overall
+    pub tags: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagAnalytics {
+    pub bookmark_count: usize,
+    pub tagged_bookmarks: usize,
+    pub distinct_tags: usize,
+    /// Most frequent tags, most used first
+    pub frequency: Vec<TagCount>,
+    pub co_occurrence: CoOccurrence,
+    pub top_pairs: Vec<TagPair>,
+    /// Tags carried by exactly one bookmark
+    pub used_once: Vec<String>,
+    /// Most frequent tags per collection ID
+    pub by_collection: BTreeMap<i64, Vec<TagCount>>,
+    /// Oldest month first
+    pub trend: Vec<MonthlyTagUsage>,
+}
+
+/// Computes tag statistics over `bookmarks`, keeping the `top` most frequent tags and pairs.
+pub fn tag_analytics(bookmarks: &[Bookmark], top: usize) -> TagAnalytics {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut pairs: HashMap<(&str, &str), usize> = HashMap::new();
+    let mut by_collection: BTreeMap<i64, HashMap<&str, usize>> = BTreeMap::new();
+    let mut tagged_bookmarks = 0;
+
+    for bookmark in bookmarks {
+        let tags: BTreeSet<&str> = bookmark.tags.iter().map(String::as_str).collect();
+        if tags.is_empty() {
+            continue;
+        }
+        tagged_bookmarks += 1;
+        let collection = by_collection.entry(bookmark.collection.id).or_default();
+        for tag in &tags {
+            *counts.entry(tag).or_default() += 1;
+            *collection.entry(tag).or_default() += 1;
+        }
+        let tags: Vec<&str> = tags.into_iter().collect();
+        for (i, a) in tags.iter().enumerate() {
+            for b in &tags[i + 1..] {
+                *pairs.entry((a, b)).or_default() += 1;
+            }
+        }
+    }
+
+    let all = ranked(&counts);
+    let top_tags: Vec<String> = all.iter().take(top).map(|t| t.tag.clone()).collect();
+    let matrix = top_tags
+        .iter()
+        .map(|a| {
+            top_tags
+                .iter()
+                .map(|b| match a.cmp(b) {
+                    std::cmp::Ordering::Less => pairs.get(&(a.as_str(), b.as_str())),
+                    std::cmp::Ordering::Greater => pairs.get(&(b.as_str(), a.as_str())),
+                    std::cmp::Ordering::Equal => counts.get(a.as_str()),
+                })
+                .map(|count| count.copied().unwrap_or_default())
+                .collect()
+        })
+        .collect();
+
+    let mut top_pairs: Vec<TagPair> = pairs
+        .iter()
+        .map(|((a, b), count)| TagPair {
+            a: a.to_string(),
+            b: b.to_string(),
+            count: *count,
+        })
+        .collect();
+    top_pairs.sort_by(|x, y| {
+        y.count
+            .cmp(&x.count)
+            .then_with(|| (&x.a, &x.b).cmp(&(&y.a, &y.b)))
+    });
+    top_pairs.truncate(top);
+
+    let mut used_once: Vec<String> = all
+        .iter()
+        .filter(|t| t.count == 1)
+        .map(|t| t.tag.clone())
+        .collect();
+    used_once.sort();
+
+    let mut months: BTreeMap<String, MonthlyTagUsage> = BTreeMap::new();
+    for bookmark in bookmarks {
+        let Some(month) = month_of(&bookmark.created) else {
+            continue;
+        };
+        let usage = months
+            .entry(month.clone())
+            .or_insert_with(|| MonthlyTagUsage {
+                month,
+                bookmarks: 0,
+                tagged: 0,
+                tags: BTreeMap::new(),
+            });
+        usage.bookmarks += 1;
+        if !bookmark.tags.is_empty() {
+            usage.tagged += 1;
+        }
+        let tags: BTreeSet<&String> = bookmark.tags.iter().collect();
+        for tag in tags.into_iter().filter(|t| top_tags.contains(t)) {
+            *usage.tags.entry(tag.clone()).or_default() += 1;
+        }
+    }
+
+    TagAnalytics {
+        bookmark_count: bookmarks.len(),
+        tagged_bookmarks,
+        distinct_tags: counts.len(),
+        frequency: all.into_iter().take(top).collect(),
+        co_occurrence: CoOccurrence {
+            tags: top_tags,
+            matrix,
+        },
+        top_pairs,
+        used_once,
+        by_collection: by_collection
+            .into_iter()
+            .map(|(id, counts)| (id, ranked(&counts).into_iter().take(top).collect()))
+            .collect(),
+        trend: months.into_values().collect(),
+    }
+}
+
+/// Compact text table of the most frequent tags and pairs.
+pub fn render_tag_table(analytics: &TagAnalytics) -> String {
+    let width = analytics
+        .frequency
+        .iter()
+        .map(|t| t.tag.chars().count())
+        .chain(std::iter::once(3))
+        .max()
+        .unwrap_or_default();
+
+    let mut out = format!(
+        "{} bookmarks, {} tagged, {} distinct tags, {} used once\n\n",
+        analytics.bookmark_count,
+        analytics.tagged_bookmarks,
+        analytics.distinct_tags,
+        analytics.used_once.len()
+    );
+    out.push_str(&format!(
+        "{:<width$}  {:>5}  {:>6}\n",
+        "Tag", "Count", "Share"
+    ));
+    for TagCount { tag, count } in &analytics.frequency {
+        let share = *count as f64 * 100.0 / analytics.tagged_bookmarks.max(1) as f64;
+        out.push_str(&format!("{tag:<width$}  {count:>5}  {share:>5.1}%\n"));
+    }
+    if !analytics.top_pairs.is_empty() {
+        out.push_str("\nTop pairs\n");
+        for TagPair { a, b, count } in &analytics.top_pairs {
+            out.push_str(&format!("{count:>5}  {a} + {b}\n"));
+        }
+    }
+    out
+}
+
+/// Number of bookmarks under a key such as a domain, type or month.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyCount {
+    pub key: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionCount {
+    pub id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HighlightStats {
+    pub total: usize,
+    pub bookmarks_with_highlights: usize,
+    pub per_bookmark: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrowthStats {
+    pub last_30_days: usize,
+    pub previous_30_days: usize,
+    /// Change from the previous 30 days, absent when that period was empty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change_percent: Option<f64>,
+    /// Bookmarks per month since the oldest bookmark
+    pub average_per_month: f64,
+}
+
+/// Bookmark that has never been annotated, i.e. has no highlights and no note.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnreadItem {
+    pub id: i64,
+    pub title: String,
+    pub link: String,
+    pub created: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryDashboard {
+    pub bookmark_count: usize,
+    pub important: usize,
+    pub broken: usize,
+    /// Largest collections first
+    pub by_collection: Vec<CollectionCount>,
+    /// Most bookmarked domains first
+    pub by_domain: Vec<KeyCount>,
+    pub by_type: Vec<KeyCount>,
+    /// Oldest month first
+    pub by_month: Vec<KeyCount>,
+    pub highlights: HighlightStats,
+    /// Oldest first
+    pub oldest_unread: Vec<UnreadItem>,
+    pub growth: GrowthStats,
+}
+
+/// Library-wide statistics over `bookmarks`, relative to `now`.
+///
+/// `collection_titles` names the collections; `top` bounds the domain list.
+pub fn library_dashboard(
+    bookmarks: &[Bookmark],
+    collection_titles: &HashMap<i64, String>,
+    now: DateTime<Utc>,
+    top: usize,
+) -> LibraryDashboard {
+    let mut collections: HashMap<i64, usize> = HashMap::new();
+    let mut domains: HashMap<&str, usize> = HashMap::new();
+    let mut types: HashMap<String, usize> = HashMap::new();
+    let mut months: BTreeMap<String, usize> = BTreeMap::new();
+    let mut highlights = HighlightStats::default();
+    let mut unread = vec![];
+    let mut growth = GrowthStats::default();
+    let mut oldest: Option<DateTime<Utc>> = None;
+
+    for bookmark in bookmarks {
+        *collections.entry(bookmark.collection.id).or_default() += 1;
+        *domains.entry(bookmark.domain.as_str()).or_default() += 1;
+        let bookmark_type = serde_json::to_value(&bookmark.bookmark_type)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        *types.entry(bookmark_type).or_default() += 1;
+        if let Some(month) = month_of(&bookmark.created) {
+            *months.entry(month).or_default() += 1;
+        }
+
+        let highlight_count = bookmark.highlights.as_ref().map_or(0, Vec::len);
+        highlights.total += highlight_count;
+        if highlight_count > 0 {
+            highlights.bookmarks_with_highlights += 1;
+        }
+        let has_note = bookmark
+            .note
+            .as_deref()
+            .is_some_and(|n| !n.trim().is_empty());
+        if highlight_count == 0 && !has_note {
+            unread.push(bookmark);
+        }
+
+        if let Ok(created) = DateTime::parse_from_rfc3339(&bookmark.created) {
+            let created = created.with_timezone(&Utc);
+            oldest = Some(oldest.map_or(created, |o| o.min(created)));
+            let age = now - created;
+            if age >= Duration::zero() && age < Duration::days(30) {
+                growth.last_30_days += 1;
+            } else if age >= Duration::days(30) && age < Duration::days(60) {
+                growth.previous_30_days += 1;
+            }
+        }
+    }
+
+    let total = bookmarks.len();
+    highlights.per_bookmark = highlights.total as f64 / total.max(1) as f64;
+    growth.change_percent = (growth.previous_30_days > 0).then(|| {
+        (growth.last_30_days as f64 - growth.previous_30_days as f64) * 100.0
+            / growth.previous_30_days as f64
+    });
+    if let Some(oldest) = oldest {
+        // Average month length, so partial months count proportionally
+        let months_spanned = ((now - oldest).num_days() as f64 / 30.44).max(1.0);
+        growth.average_per_month = total as f64 / months_spanned;
+    }
+
+    unread.sort_by(|a, b| a.created.cmp(&b.created).then(a.id.cmp(&b.id)));
+    let mut by_collection: Vec<CollectionCount> = collections
+        .into_iter()
+        .map(|(id, count)| CollectionCount {
+            id,
+            title: collection_titles.get(&id).cloned(),
+            count,
+        })
+        .collect();
+    by_collection.sort_by(|a, b| b.count.cmp(&a.count).then(a.id.cmp(&b.id)));
+
+    LibraryDashboard {
+        bookmark_count: total,
+        important: bookmarks.iter().filter(|b| b.important).count(),
+        broken: bookmarks.iter().filter(|b| b.broken).count(),
+        by_collection,
+        by_domain: ranked_keys(domains.into_iter().map(|(k, v)| (k.to_string(), v)))
+            .into_iter()
+            .take(top)
+            .collect(),
+        by_type: ranked_keys(types),
+        by_month: months
+            .into_iter()
+            .map(|(key, count)| KeyCount { key, count })
+            .collect(),
+        highlights,
+        oldest_unread: unread
+            .into_iter()
+            .take(DASHBOARD_UNREAD_LIMIT)
+            .map(|b| UnreadItem {
+                id: b.id,
+                title: b.title.clone(),
+                link: b.link.clone(),
+                created: b.created.clone(),
+            })
+            .collect(),
+        growth,
+    }
+}
+
+/// Compact text summary of a dashboard.
+pub fn render_dashboard(dashboard: &LibraryDashboard) -> String {
+    let mut out = format!(
+        "{} bookmarks, {} important, {} broken\n",
+        dashboard.bookmark_count, dashboard.important, dashboard.broken
+    );
+    out.push_str(&format!(
+        "Highlights: {} on {} bookmarks ({:.2} per bookmark)\n",
+        dashboard.highlights.total,
+        dashboard.highlights.bookmarks_with_highlights,
+        dashboard.highlights.per_bookmark
+    ));
+    let change = dashboard
+        .growth
+        .change_percent
+        .map(|c| format!(" ({c:+.0}%)"))
+        .unwrap_or_default();
+    out.push_str(&format!(
+        "Growth: {} in the last 30 days vs {} before{change}, {:.1} per month on average\n",
+        dashboard.growth.last_30_days,
+        dashboard.growth.previous_30_days,
+        dashboard.growth.average_per_month
+    ));
+
+    let section = |out: &mut String, title: &str, rows: Vec<(String, usize)>| {
+        if rows.is_empty() {
+            return;
+        }
+        out.push_str(&format!("\n{title}\n"));
+        for (key, count) in rows {
+            out.push_str(&format!("{count:>6}  {key}\n"));
+        }
+    };
+    section(
+        &mut out,
+        "Collections",
+        dashboard
+            .by_collection
+            .iter()
+            .map(|c| {
+                let name = c.title.clone().unwrap_or_else(|| c.id.to_string());
+                (name, c.count)
+            })
+            .collect(),
+    );
+    section(
+        &mut out,
+        "Domains",
+        dashboard
+            .by_domain
+            .iter()
+            .map(|d| (d.key.clone(), d.count))
+            .collect(),
+    );
+    section(
+        &mut out,
+        "Types",
+        dashboard
+            .by_type
+            .iter()
+            .map(|t| (t.key.clone(), t.count))
+            .collect(),
+    );
+    section(
+        &mut out,
+        "Months",
+        dashboard
+            .by_month
+            .iter()
+            .map(|m| (m.key.clone(), m.count))
+            .collect(),
+    );
+    if !dashboard.oldest_unread.is_empty() {
+        out.push_str("\nOldest unread\n");
+        for item in &dashboard.oldest_unread {
+            let date = item.created.get(..10).unwrap_or(&item.created);
+            out.push_str(&format!("{date}  {} ({})\n", item.title, item.link));
+        }
+    }
+    out
+}
+
+/// `YYYY-MM` of an RFC 3339 timestamp.
+pub fn month_of(timestamp: &str) -> Option<String> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|date| date.format("%Y-%m").to_string())
+}
+
+fn ranked_keys(counts: impl IntoIterator<Item = (String, usize)>) -> Vec<KeyCount> {
+    let mut ranked: Vec<KeyCount> = counts
+        .into_iter()
+        .map(|(key, count)| KeyCount { key, count })
+        .collect();
+    ranked.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+    ranked
+}
+
+// Most used first; ties sorted by name so results are stable
+fn ranked(counts: &HashMap<&str, usize>) -> Vec<TagCount> {
+    let mut ranked: Vec<TagCount> = counts
+        .iter()
+        .map(|(tag, count)| TagCount {
+            tag: tag.to_string(),
+            count: *count,
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn bookmark(id: i64, collection: i64, created: &str, tags: &[&str]) -> Bookmark {
+        serde_json::from_value(serde_json::json!({
+            "_id": id,
+            "title": format!("Bookmark {id}"),
+            "type": "link",
+            "tags": tags,
+            "link": format!("https://example.com/{id}"),
+            "domain": "example.com",
+            "created": created,
+            "lastUpdate": created,
+            "user": { "$id": 1 },
+            "collection": { "$id": collection },
+            "important": false,
+            "broken": false
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_tag_analytics() {
+        let bookmarks = vec![
+            bookmark(1, 10, "2024-01-05T00:00:00Z", &["rust", "async"]),
+            bookmark(2, 10, "2024-01-20T00:00:00Z", &["rust", "async", "tokio"]),
+            bookmark(3, 20, "2024-02-01T00:00:00Z", &["rust", "wasm"]),
+            bookmark(4, 20, "2024-02-11T00:00:00Z", &[]),
+        ];
+
+        let analytics = tag_analytics(&bookmarks, 3);
+        assert_eq!(analytics.tagged_bookmarks, 3);
+        assert_eq!(analytics.distinct_tags, 4);
+        assert_eq!(
+            analytics.frequency[..2],
+            [
+                TagCount {
+                    tag: "rust".to_string(),
+                    count: 3
+                },
+                TagCount {
+                    tag: "async".to_string(),
+                    count: 2
+                },
+            ]
+        );
+        assert_eq!(
+            analytics.top_pairs[0],
+            TagPair {
+                a: "async".to_string(),
+                b: "rust".to_string(),
+                count: 2
+            }
+        );
+        // tokio and wasm tie at one use; tokio sorts first
+        assert_eq!(analytics.co_occurrence.tags, vec!["rust", "async", "tokio"]);
+        assert_eq!(analytics.co_occurrence.matrix[0], vec![3, 2, 1]);
+        assert_eq!(analytics.used_once, vec!["tokio", "wasm"]);
+        assert_eq!(analytics.by_collection[&20][0].tag, "rust");
+
+        assert_eq!(analytics.trend.len(), 2);
+        assert_eq!(analytics.trend[0].month, "2024-01");
+        assert_eq!(analytics.trend[1].bookmarks, 2);
+        assert_eq!(analytics.trend[1].tagged, 1);
+        assert_eq!(analytics.trend[1].tags.get("rust"), Some(&1));
+
+        let table = render_tag_table(&analytics);
+        assert!(table.starts_with("4 bookmarks, 3 tagged, 4 distinct tags, 2 used once"));
+        assert!(table.contains("rust       3  100.0%"));
+    }
+
+    #[test]
+    fn test_library_dashboard() {
+        let mut bookmarks = vec![
+            bookmark(1, 10, "2024-01-05T00:00:00Z", &[]),
+            bookmark(2, 10, "2024-05-20T00:00:00Z", &[]),
+            bookmark(3, 20, "2024-06-10T00:00:00Z", &[]),
+            bookmark(4, 10, "2024-06-25T00:00:00Z", &[]),
+        ];
+        bookmarks[0].important = true;
+        bookmarks[1].broken = true;
+        bookmarks[2].note = Some("Read it".to_string());
+        bookmarks[3].domain = "docs.rs".to_string();
+
+        let titles = HashMap::from([(10, "Reading".to_string())]);
+        let now = DateTime::parse_from_rfc3339("2024-07-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let dashboard = library_dashboard(&bookmarks, &titles, now, 5);
+
+        assert_eq!(dashboard.bookmark_count, 4);
+        assert_eq!((dashboard.important, dashboard.broken), (1, 1));
+        assert_eq!(
+            dashboard.by_collection[0],
+            CollectionCount {
+                id: 10,
+                title: Some("Reading".to_string()),
+                count: 3
+            }
+        );
+        assert_eq!(dashboard.by_domain[0].key, "example.com");
+        assert_eq!(dashboard.by_type[0].key, "link");
+        assert_eq!(dashboard.by_month.len(), 3);
+        assert_eq!(dashboard.by_month[0].key, "2024-01");
+
+        let unread: Vec<i64> = dashboard.oldest_unread.iter().map(|u| u.id).collect();
+        assert_eq!(unread, vec![1, 2, 4]);
+
+        assert_eq!(dashboard.growth.last_30_days, 2);
+        assert_eq!(dashboard.growth.previous_30_days, 1);
+        assert_eq!(dashboard.growth.change_percent, Some(100.0));
+
+        let text = render_dashboard(&dashboard);
+        assert!(text.starts_with("4 bookmarks, 1 important, 1 broken\n"));
+        assert!(text.contains("     3  Reading\n"));
+    }
+}
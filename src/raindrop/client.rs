@@ -1,22 +1,160 @@
-use reqwest::{Client, StatusCode};
+use chrono::{DateTime, Utc};
+use reqwest::{Client, IntoUrl, Method, RequestBuilder, Response, StatusCode, multipart::Form};
 use serde_json::json;
-use std::env;
-use tracing::{debug, info};
-
-use super::types::*;
-use crate::error::{RaindropMcpError, Result};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+use tracing::{debug, info, warn};
+
+use super::{
+    journal::{Journal, JournalEntry, JournalEntrySummary, Snapshot, UndoReport},
+    oauth::OAuthSession,
+    types::*,
+    urls::{normalize_url, url_variants},
+};
+use crate::{
+    config::Profile,
+    error::{RaindropMcpError, Result},
+};
 
 const BASE_URL: &str = "https://api.raindrop.io/rest/v1";
 
+/// Largest page size accepted by the Raindrop API.
+pub const MAX_PER_PAGE: i32 = 50;
+
+/// Search operator matching only bookmarks that have a reminder.
+const REMINDER_FILTER: &str = "reminder:true";
+
+/// Bookmarks sent per multi-create request.
+const MAX_CREATE_BATCH: usize = 100;
+
+/// URLs sent per existence-check request.
+const EXISTS_BATCH_SIZE: usize = 100;
+
+/// Largest single file Raindrop accepts for upload.
+pub const MAX_UPLOAD_BYTES: usize = 100 * 1024 * 1024;
+
+/// How requests are authenticated.
+#[derive(Clone)]
+enum Credentials {
+    Token(Arc<str>),
+    OAuth(Arc<OAuthSession>),
+}
+
+/// HTTP client that signs every request with the current access token.
+#[derive(Clone)]
+struct ApiClient {
+    http: Client,
+    credentials: Credentials,
+}
+
+impl ApiClient {
+    fn get(&self, url: impl IntoUrl) -> ApiRequest {
+        self.request(Method::GET, url)
+    }
+
+    fn post(&self, url: impl IntoUrl) -> ApiRequest {
+        self.request(Method::POST, url)
+    }
+
+    fn put(&self, url: impl IntoUrl) -> ApiRequest {
+        self.request(Method::PUT, url)
+    }
+
+    fn delete(&self, url: impl IntoUrl) -> ApiRequest {
+        self.request(Method::DELETE, url)
+    }
+
+    fn request(&self, method: Method, url: impl IntoUrl) -> ApiRequest {
+        ApiRequest {
+            builder: self.http.request(method, url),
+            credentials: self.credentials.clone(),
+        }
+    }
+}
+
+struct ApiRequest {
+    builder: RequestBuilder,
+    credentials: Credentials,
+}
+
+impl ApiRequest {
+    fn json<T: serde::Serialize + ?Sized>(self, json: &T) -> Self {
+        Self {
+            builder: self.builder.json(json),
+            ..self
+        }
+    }
+
+    fn query<T: serde::Serialize + ?Sized>(self, query: &T) -> Self {
+        Self {
+            builder: self.builder.query(query),
+            ..self
+        }
+    }
+
+    fn multipart(self, form: Form) -> Self {
+        Self {
+            builder: self.builder.multipart(form),
+            ..self
+        }
+    }
+
+    /// Sends with the current token. With OAuth, a 401 refreshes the token and retries once.
+    async fn send(self) -> Result<Response> {
+        let token = match &self.credentials {
+            Credentials::Token(token) => token.to_string(),
+            Credentials::OAuth(session) => session.access_token().await?,
+        };
+        let retry = self.builder.try_clone();
+        let response = self.builder.bearer_auth(&token).send().await?;
+
+        let Credentials::OAuth(session) = &self.credentials else {
+            return Ok(response);
+        };
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+        let token = session.refresh_rejected(&token).await?;
+        match retry {
+            Some(retry) => Ok(retry.bearer_auth(token).send().await?),
+            // Multipart bodies cannot be replayed; later requests use the new token
+            None => Ok(response),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct RaindropClient {
-    client: Client,
+    client: ApiClient,
     base_url: String,
+    journal: Option<Arc<Journal>>,
+    read_only: bool,
+    default_collection: Option<i64>,
 }
 
 impl RaindropClient {
     pub fn new() -> Result<Self> {
         let base_url = env::var("RAINDROP_BASE_URL").unwrap_or_else(|_| BASE_URL.to_string());
-        Self::with_base_url(base_url)
+        let client = Self::with_base_url(base_url)?;
+
+        match Journal::open_default() {
+            Ok(journal) => {
+                info!(
+                    "Recording undo journal at {}",
+                    journal.path().unwrap_or(Path::new("memory")).display()
+                );
+                Ok(client.with_journal(Arc::new(journal)))
+            }
+            Err(e) => {
+                warn!("Undo journal disabled: {}", e);
+                Ok(client)
+            }
+        }
     }
 
     pub fn with_base_url(base_url: String) -> Result<Self> {
@@ -25,22 +163,156 @@ impl RaindropClient {
                 "RAINDROP_ACCESS_TOKEN environment variable is required. Please check your .env file or environment settings.".to_string()
             ))?;
 
+        Self::with_token(base_url, &access_token)
+    }
+
+    /// Client for a config file profile, with its own journal unless it is read-only.
+    pub fn from_profile(name: &str, profile: &Profile) -> Result<Self> {
+        let base_url = profile.base_url().unwrap_or_else(|| BASE_URL.to_string());
+        let credentials = match profile.oauth() {
+            Some(settings) => Credentials::OAuth(Arc::new(OAuthSession::open(name, settings)?)),
+            None => Credentials::Token(profile.access_token()?.into()),
+        };
+        let mut client = Self::build(
+            base_url,
+            credentials,
+            profile.timeout(),
+            profile.connect_timeout(),
+        )?;
+        client.read_only = profile.read_only;
+        client.default_collection = profile.default_collection;
+        if profile.read_only {
+            return Ok(client);
+        }
+
+        match Journal::open_for_profile(name) {
+            Ok(journal) => {
+                info!(
+                    "Recording undo journal for profile '{}' at {}",
+                    name,
+                    journal.path().unwrap_or(Path::new("memory")).display()
+                );
+                Ok(client.with_journal(Arc::new(journal)))
+            }
+            Err(e) => {
+                warn!("Undo journal disabled for profile '{}': {}", name, e);
+                Ok(client)
+            }
+        }
+    }
+
+    /// Client acting as whoever `access_token` belongs to, with `profile`'s settings but not
+    /// its credentials, and an undo journal that lives only as long as the client.
+    pub fn for_session(profile: &Profile, access_token: &str) -> Result<Self> {
+        let base_url = profile.base_url().unwrap_or_else(|| BASE_URL.to_string());
+        let mut client = Self::build(
+            base_url,
+            Credentials::Token(access_token.into()),
+            profile.timeout(),
+            profile.connect_timeout(),
+        )?;
+        client.read_only = profile.read_only;
+        client.default_collection = profile.default_collection;
+        if profile.read_only {
+            return Ok(client);
+        }
+        Ok(client.with_journal(Arc::new(Journal::in_memory())))
+    }
+
+    pub fn with_token(base_url: String, access_token: &str) -> Result<Self> {
+        Self::build(
+            base_url,
+            Credentials::Token(access_token.into()),
+            None,
+            None,
+        )
+    }
+
+    /// Client authenticated by an OAuth session, refreshing its token when the API rejects it.
+    pub fn with_oauth(base_url: String, session: Arc<OAuthSession>) -> Result<Self> {
+        Self::build(base_url, Credentials::OAuth(session), None, None)
+    }
+
+    fn build(
+        base_url: String,
+        credentials: Credentials,
+        timeout: Option<Duration>,
+        connect_timeout: Option<Duration>,
+    ) -> Result<Self> {
         let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert(
-            reqwest::header::AUTHORIZATION,
-            format!("Bearer {access_token}").parse().unwrap(),
-        );
         headers.insert(
             reqwest::header::CONTENT_TYPE,
             "application/json".parse().unwrap(),
         );
 
-        let client = Client::builder()
-            .default_headers(headers)
-            .build()
-            .map_err(RaindropMcpError::HttpRequest)?;
+        let mut builder = Client::builder().default_headers(headers);
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(connect_timeout) = connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        let http = builder.build().map_err(RaindropMcpError::HttpRequest)?;
+
+        Ok(Self {
+            client: ApiClient { http, credentials },
+            base_url,
+            journal: None,
+            read_only: false,
+            default_collection: None,
+        })
+    }
+
+    /// Records the prior state of every mutation in `journal` so it can be undone.
+    pub fn with_journal(mut self, journal: Arc<Journal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    pub fn journal(&self) -> Option<&Arc<Journal>> {
+        self.journal.as_ref()
+    }
+
+    /// Rejects every change, for accounts that should only be read.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Collection new bookmarks go to when none is given.
+    pub fn with_default_collection(mut self, collection_id: Option<i64>) -> Self {
+        self.default_collection = collection_id;
+        self
+    }
+
+    pub fn default_collection(&self) -> i64 {
+        self.default_collection.unwrap_or(UNSORTED_COLLECTION_ID)
+    }
+
+    // Journals a write once it has succeeded, with the state captured before it
+    fn record(
+        &self,
+        operation: &str,
+        description: String,
+        prior: Option<Vec<Snapshot>>,
+    ) -> Result<()> {
+        if let (Some(journal), Some(snapshots)) = (&self.journal, prior) {
+            journal.record(operation, description, snapshots)?;
+        }
+        Ok(())
+    }
 
-        Ok(Self { client, base_url })
+    fn ensure_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(RaindropMcpError::ReadOnly(
+                "this profile is configured as read-only".to_string(),
+            ));
+        }
+        Ok(())
     }
 
     // Helper method to handle API errors
@@ -48,14 +320,27 @@ impl RaindropClient {
         &self,
         response: reqwest::Response,
     ) -> Result<T> {
+        let text = self.handle_text_response(response).await?;
+        serde_json::from_str::<T>(&text).map_err(RaindropMcpError::JsonSerialization)
+    }
+
+    // Accepts an empty success for writes that return no item
+    async fn handle_empty_response(&self, response: reqwest::Response) -> Result<()> {
+        match response.status() {
+            StatusCode::OK | StatusCode::NO_CONTENT => Ok(()),
+            _ => self
+                .handle_response::<serde_json::Value>(response)
+                .await
+                .map(|_| ()),
+        }
+    }
+
+    async fn handle_text_response(&self, response: reqwest::Response) -> Result<String> {
         let status = response.status();
         let url = response.url().to_string();
 
         match status {
-            StatusCode::OK | StatusCode::CREATED => {
-                let text = response.text().await?;
-                serde_json::from_str::<T>(&text).map_err(RaindropMcpError::JsonSerialization)
-            }
+            StatusCode::OK | StatusCode::CREATED => Ok(response.text().await?),
             StatusCode::UNAUTHORIZED => Err(RaindropMcpError::Unauthorized(format!(
                 "Invalid or expired access token for {url}"
             ))),
@@ -119,7 +404,45 @@ impl RaindropClient {
         Ok(result.items)
     }
 
+    /// Root collections followed by every nested collection.
+    pub async fn get_all_collections(&self) -> Result<Vec<Collection>> {
+        debug!("Fetching root and nested collections");
+        let mut collections = self.get_collections().await?;
+        let response = self
+            .client
+            .get(format!("{}/collections/childrens", self.base_url))
+            .send()
+            .await?;
+
+        let result: CollectionsResponse = self.handle_response(response).await?;
+        collections.extend(result.items);
+        Ok(collections)
+    }
+
+    /// Finds a collection by its title, ignoring case.
+    pub async fn find_collection(&self, name: &str) -> Result<Collection> {
+        let mut matches: Vec<Collection> = self
+            .get_all_collections()
+            .await?
+            .into_iter()
+            .filter(|c| c.title.trim().eq_ignore_ascii_case(name.trim()))
+            .collect();
+        match matches.len() {
+            0 => Err(RaindropMcpError::NotFound(format!("Collection '{name}'"))),
+            1 => Ok(matches.remove(0)),
+            _ => Err(RaindropMcpError::InvalidParameter(format!(
+                "Several collections are named '{name}' (IDs {}); pass an ID instead",
+                matches
+                    .iter()
+                    .map(|c| c.id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        }
+    }
+
     pub async fn create_collection(&self, title: String, is_public: bool) -> Result<Collection> {
+        self.ensure_writable()?;
         info!("Creating new collection: {}", title);
         let body = json!({
             "title": title,
@@ -134,6 +457,11 @@ impl RaindropClient {
             .await?;
 
         let result: CollectionResponse = self.handle_response(response).await?;
+        self.record(
+            "create_collection",
+            format!("Create collection '{}'", result.item.title),
+            Some(vec![Snapshot::CreatedCollection { id: result.item.id }]),
+        )?;
         Ok(result.item)
     }
 
@@ -142,7 +470,13 @@ impl RaindropClient {
         id: i64,
         updates: serde_json::Value,
     ) -> Result<Collection> {
+        self.ensure_writable()?;
         info!("Updating collection: {}", id);
+        let prior = match &self.journal {
+            Some(_) => Some(self.get_collection(id).await?),
+            None => None,
+        };
+
         let response = self
             .client
             .put(format!("{}/collection/{}", self.base_url, id))
@@ -151,24 +485,52 @@ impl RaindropClient {
             .await?;
 
         let result: CollectionResponse = self.handle_response(response).await?;
+        if let Some(state) = prior {
+            self.record(
+                "update_collection",
+                format!("Update collection '{}'", state.title),
+                Some(vec![Snapshot::Collection {
+                    state: Box::new(state),
+                }]),
+            )?;
+        }
         Ok(result.item)
     }
 
     pub async fn delete_collection(&self, id: i64) -> Result<()> {
+        self.ensure_writable()?;
         info!("Deleting collection: {}", id);
+        let prior = match &self.journal {
+            Some(_) => {
+                let state = self.get_collection(id).await?;
+                let description = format!("Delete collection '{}'", state.title);
+                // Bookmarks of a deleted collection are moved to the trash
+                let mut snapshots = vec![Snapshot::RemovedCollection {
+                    state: Box::new(state),
+                }];
+                snapshots.extend(
+                    self.snapshot_search(SearchParams {
+                        collection: Some(id),
+                        ..Default::default()
+                    })
+                    .await?,
+                );
+                Some((description, snapshots))
+            }
+            None => None,
+        };
+
         let response = self
             .client
             .delete(format!("{}/collection/{}", self.base_url, id))
             .send()
             .await?;
 
-        match response.status() {
-            StatusCode::OK | StatusCode::NO_CONTENT => Ok(()),
-            _ => self
-                .handle_response::<serde_json::Value>(response)
-                .await
-                .map(|_| ()),
+        self.handle_empty_response(response).await?;
+        if let Some((description, snapshots)) = prior {
+            self.record("delete_collection", description, Some(snapshots))?;
         }
+        Ok(())
     }
 
     pub async fn share_collection(
@@ -177,6 +539,7 @@ impl RaindropClient {
         level: &str,
         emails: Option<Vec<String>>,
     ) -> Result<serde_json::Value> {
+        self.ensure_writable()?;
         info!("Sharing collection {} with level: {}", id, level);
         let mut body = json!({
             "level": level
@@ -193,7 +556,15 @@ impl RaindropClient {
             .send()
             .await?;
 
-        self.handle_response(response).await
+        let result = self.handle_response(response).await?;
+        self.record(
+            "share_collection",
+            format!("Share collection {id} with level '{level}'"),
+            Some(vec![Snapshot::Irreversible {
+                reason: "Sharing invitations cannot be withdrawn automatically".to_string(),
+            }]),
+        )?;
+        Ok(result)
     }
 
     // Bookmarks API
@@ -203,7 +574,7 @@ impl RaindropClient {
         let mut query_params = vec![];
 
         if let Some(search) = &params.search {
-            query_params.push(("search", urlencoding::encode(search).to_string()));
+            query_params.push(("search", search.clone()));
         }
         if let Some(collection) = params.collection {
             query_params.push(("collection", collection.to_string()));
@@ -213,6 +584,11 @@ impl RaindropClient {
                 query_params.push(("tag", tag.clone()));
             }
         }
+        if let Some(ids) = &params.ids {
+            for id in ids {
+                query_params.push(("ids", id.to_string()));
+            }
+        }
         if let Some(page) = params.page {
             query_params.push(("page", page.to_string()));
         }
@@ -237,6 +613,43 @@ impl RaindropClient {
         self.handle_response(response).await
     }
 
+    /// Fetches every page of bookmarks matching `params`.
+    pub async fn get_all_bookmarks(&self, params: SearchParams) -> Result<Vec<Bookmark>> {
+        let (bookmarks, _) = self.get_bookmarks_until(params, |_| false).await?;
+        Ok(bookmarks)
+    }
+
+    /// Fetches pages of bookmarks matching `params` until `enough` is satisfied with the
+    /// bookmarks so far, returning them with the number of bookmarks that match in all.
+    pub async fn get_bookmarks_until(
+        &self,
+        params: SearchParams,
+        mut enough: impl FnMut(&[Bookmark]) -> bool,
+    ) -> Result<(Vec<Bookmark>, usize)> {
+        let per_page = params.per_page.unwrap_or(MAX_PER_PAGE).min(MAX_PER_PAGE);
+        let mut page = params.page.unwrap_or(0);
+        let mut bookmarks = vec![];
+
+        loop {
+            let result = self
+                .get_bookmarks(SearchParams {
+                    page: Some(page),
+                    per_page: Some(per_page),
+                    ..params.clone()
+                })
+                .await?;
+            let fetched = result.items.len();
+            let total = result.count.max(0) as usize;
+            bookmarks.extend(result.items);
+
+            if fetched < per_page as usize || bookmarks.len() >= total || enough(&bookmarks) {
+                let total = total.max(bookmarks.len());
+                return Ok((bookmarks, total));
+            }
+            page += 1;
+        }
+    }
+
     pub async fn get_bookmark(&self, id: i64) -> Result<Bookmark> {
         debug!("Fetching bookmark with id: {}", id);
         let response = self
@@ -258,38 +671,267 @@ impl RaindropClient {
         tags: Option<Vec<String>>,
         important: Option<bool>,
     ) -> Result<Bookmark> {
-        info!("Creating bookmark: {}", link);
-        let mut body = json!({
-            "link": link,
-            "collection": { "$id": collection_id }
-        });
+        self.create_bookmark_from(NewBookmark {
+            link,
+            collection: Some(CollectionRef { id: collection_id }),
+            title,
+            excerpt,
+            tags,
+            important,
+            ..Default::default()
+        })
+        .await
+    }
+
+    pub async fn create_bookmark_from(&self, bookmark: NewBookmark) -> Result<Bookmark> {
+        self.ensure_writable()?;
+        info!("Creating bookmark: {}", bookmark.link);
+        let response = self
+            .client
+            .post(format!("{}/raindrop", self.base_url))
+            .json(&bookmark)
+            .send()
+            .await?;
+
+        let result: BookmarkResponse = self.handle_response(response).await?;
+        self.record(
+            "create_bookmark",
+            format!("Create bookmark {}", result.item.link),
+            Some(vec![Snapshot::CreatedBookmark { id: result.item.id }]),
+        )?;
+        Ok(result.item)
+    }
 
-        if let Some(title) = title {
-            body["title"] = json!(title);
+    /// Creates many bookmarks through the multi-create endpoint, reporting each item's outcome.
+    ///
+    /// With `skip_duplicates`, links already in the library (or repeated within `items`)
+    /// are reported as duplicates instead of being saved again.
+    pub async fn create_bookmarks(
+        &self,
+        items: Vec<NewBookmark>,
+        skip_duplicates: bool,
+    ) -> Result<Vec<BulkCreateResult>> {
+        self.ensure_writable()?;
+        info!("Creating {} bookmarks", items.len());
+        let mut results: Vec<Option<BulkCreateResult>> = vec![None; items.len()];
+        let mut pending = vec![];
+        let mut first_by_key: HashMap<String, usize> = HashMap::new();
+        for (index, item) in items.iter().enumerate() {
+            if let Err(e) = url::Url::parse(&item.link) {
+                results[index] = Some(BulkCreateResult::failed(
+                    index,
+                    &item.link,
+                    format!("Invalid URL: {e}"),
+                ));
+                continue;
+            }
+            if skip_duplicates {
+                if let Some(first) = first_by_key.get(&normalize_url(&item.link)) {
+                    results[index] = Some(BulkCreateResult::duplicate(
+                        index,
+                        &item.link,
+                        vec![],
+                        format!("Same link as item {first}"),
+                    ));
+                    continue;
+                }
+                first_by_key.insert(normalize_url(&item.link), index);
+            }
+            pending.push(index);
         }
-        if let Some(excerpt) = excerpt {
-            body["excerpt"] = json!(excerpt);
+
+        if skip_duplicates && !pending.is_empty() {
+            let links: Vec<String> = pending.iter().map(|&i| items[i].link.clone()).collect();
+            let existence = self.exists(&links).await?;
+            let mut fresh = vec![];
+            for (index, existing) in pending.into_iter().zip(existence) {
+                if existing.exists {
+                    results[index] = Some(BulkCreateResult::duplicate(
+                        index,
+                        &items[index].link,
+                        existing.ids,
+                        "Already saved".to_string(),
+                    ));
+                } else {
+                    fresh.push(index);
+                }
+            }
+            pending = fresh;
         }
-        if let Some(tags) = tags {
-            body["tags"] = json!(tags);
+
+        let mut created_ids = vec![];
+        for chunk in pending.chunks(MAX_CREATE_BATCH) {
+            let body = json!({ "items": chunk.iter().map(|&i| &items[i]).collect::<Vec<_>>() });
+            let response = self
+                .client
+                .post(format!("{}/raindrops", self.base_url))
+                .json(&body)
+                .send()
+                .await;
+            let created = match response {
+                Ok(response) => self
+                    .handle_response::<BookmarksCreatedResponse>(response)
+                    .await
+                    .map(|r| r.items),
+                Err(e) => Err(e),
+            };
+
+            match created {
+                Ok(mut created) => {
+                    // Match by link rather than position, as Raindrop may drop items
+                    for &index in chunk {
+                        let key = normalize_url(&items[index].link);
+                        let link = &items[index].link;
+                        results[index] = Some(
+                            match created.iter().position(|b| normalize_url(&b.link) == key) {
+                                Some(pos) => {
+                                    let bookmark = created.remove(pos);
+                                    created_ids.push(bookmark.id);
+                                    BulkCreateResult::created(index, link, bookmark.id)
+                                }
+                                None => BulkCreateResult::failed(
+                                    index,
+                                    link,
+                                    "Not created by Raindrop".to_string(),
+                                ),
+                            },
+                        );
+                    }
+                }
+                Err(e) => {
+                    warn!("Failed to create {} bookmarks: {}", chunk.len(), e);
+                    for &index in chunk {
+                        results[index] = Some(BulkCreateResult::failed(
+                            index,
+                            &items[index].link,
+                            e.to_string(),
+                        ));
+                    }
+                }
+            }
         }
-        if let Some(important) = important {
-            body["important"] = json!(important);
+
+        if !created_ids.is_empty() {
+            self.record(
+                "create_bookmarks",
+                format!("Create {} bookmarks", created_ids.len()),
+                Some(
+                    created_ids
+                        .iter()
+                        .map(|&id| Snapshot::CreatedBookmark { id })
+                        .collect(),
+                ),
+            )?;
         }
+        Ok(results.into_iter().flatten().collect())
+    }
 
+    /// Asks Raindrop's parser for the title, excerpt, cover and media of a page.
+    pub async fn parse_url(&self, url: &str) -> Result<ParsedUrl> {
+        debug!("Parsing URL: {}", url);
         let response = self
             .client
-            .post(format!("{}/raindrop", self.base_url))
-            .json(&body)
+            .get(format!("{}/import/url/parse", self.base_url))
+            .query(&[("url", url)])
             .send()
             .await?;
 
-        let result: BookmarkResponse = self.handle_response(response).await?;
-        Ok(result.item)
+        let result: ParsedUrlResponse = self.handle_response(response).await?;
+        match result.item {
+            Some(mut parsed) if result.result => {
+                if parsed.cover.is_none() {
+                    parsed.cover = parsed
+                        .media
+                        .iter()
+                        .find(|m| matches!(m.media_type, MediaType::Image))
+                        .map(|m| m.link.clone());
+                }
+                Ok(parsed)
+            }
+            _ => Err(RaindropMcpError::RaindropApi(
+                format!(
+                    "Could not parse {url}: {}",
+                    result.error.unwrap_or_else(|| "unknown error".to_string())
+                ),
+                None,
+            )),
+        }
+    }
+
+    /// Looks up which URLs are already saved, matching trivially different spellings.
+    pub async fn exists(&self, urls: &[String]) -> Result<Vec<UrlExistence>> {
+        info!("Checking {} URLs for existing bookmarks", urls.len());
+        let keys: Vec<String> = urls.iter().map(|url| normalize_url(url)).collect();
+        let mut candidates: Vec<String> = urls.iter().flat_map(|url| url_variants(url)).collect();
+        let mut seen = HashSet::new();
+        candidates.retain(|c| seen.insert(c.clone()));
+
+        let mut ids_by_key: HashMap<String, Vec<i64>> = HashMap::new();
+        let mut unmatched: Vec<i64> = vec![];
+        for chunk in candidates.chunks(EXISTS_BATCH_SIZE) {
+            let response = self
+                .client
+                .post(format!("{}/import/url/exists", self.base_url))
+                .json(&json!({ "urls": chunk }))
+                .send()
+                .await?;
+            let result: UrlExistsResponse = self.handle_response(response).await?;
+
+            let mut linked = HashSet::new();
+            for duplicate in result.duplicates {
+                if let Some(link) = duplicate.link {
+                    linked.insert(duplicate.id);
+                    ids_by_key
+                        .entry(normalize_url(&link))
+                        .or_default()
+                        .push(duplicate.id);
+                }
+            }
+            unmatched.extend(result.ids.into_iter().filter(|id| !linked.contains(id)));
+        }
+        // Older responses list only IDs, so look their links up in one search
+        unmatched.sort_unstable();
+        unmatched.dedup();
+        if !unmatched.is_empty() {
+            let bookmarks = self
+                .get_all_bookmarks(SearchParams {
+                    ids: Some(unmatched),
+                    ..Default::default()
+                })
+                .await?;
+            for bookmark in bookmarks {
+                ids_by_key
+                    .entry(normalize_url(&bookmark.link))
+                    .or_default()
+                    .push(bookmark.id);
+            }
+        }
+
+        Ok(urls
+            .iter()
+            .zip(keys)
+            .map(|(url, key)| {
+                let mut ids = ids_by_key.get(&key).cloned().unwrap_or_default();
+                ids.sort_unstable();
+                ids.dedup();
+                UrlExistence {
+                    url: url.clone(),
+                    exists: !ids.is_empty(),
+                    normalized: key,
+                    ids,
+                }
+            })
+            .collect())
     }
 
     pub async fn update_bookmark(&self, id: i64, updates: serde_json::Value) -> Result<Bookmark> {
+        self.ensure_writable()?;
         info!("Updating bookmark: {}", id);
+        let prior = match &self.journal {
+            Some(_) => Some(self.snapshot_bookmarks(&[id], false).await?),
+            None => None,
+        };
+
         let response = self
             .client
             .put(format!("{}/raindrop/{}", self.base_url, id))
@@ -298,11 +940,17 @@ impl RaindropClient {
             .await?;
 
         let result: BookmarkResponse = self.handle_response(response).await?;
+        self.record("update_bookmark", format!("Update bookmark {id}"), prior)?;
         Ok(result.item)
     }
 
     pub async fn delete_bookmark(&self, id: i64, permanent: bool) -> Result<()> {
+        self.ensure_writable()?;
         info!("Deleting bookmark: {} (permanent: {})", id, permanent);
+        let prior = match &self.journal {
+            Some(_) => Some(self.snapshot_bookmarks(&[id], permanent).await?),
+            None => None,
+        };
 
         let url = if permanent {
             format!("{}/raindrop/{}", self.base_url, id)
@@ -312,40 +960,182 @@ impl RaindropClient {
 
         let response = self.client.delete(url).send().await?;
 
-        match response.status() {
-            StatusCode::OK | StatusCode::NO_CONTENT => Ok(()),
-            _ => self
-                .handle_response::<serde_json::Value>(response)
-                .await
-                .map(|_| ()),
-        }
+        self.handle_empty_response(response).await?;
+        self.record("delete_bookmark", format!("Delete bookmark {id}"), prior)
     }
 
+    /// Applies `updates` to `ids` within `collection_id` (0 for all) via the bulk endpoint.
     pub async fn batch_update_bookmarks(
         &self,
+        collection_id: i64,
         ids: Vec<i64>,
         updates: serde_json::Value,
     ) -> Result<serde_json::Value> {
+        self.ensure_writable()?;
         info!("Batch updating {} bookmarks", ids.len());
+        let prior = match &self.journal {
+            Some(_) => Some(self.snapshot_bookmarks(&ids, false).await?),
+            None => None,
+        };
+        let description = format!("Update {} bookmarks", ids.len());
         let mut body = updates;
         body["ids"] = json!(ids);
 
         let response = self
             .client
-            .put(format!("{}/raindrops", self.base_url))
+            .put(format!("{}/raindrops/{}", self.base_url, collection_id))
             .json(&body)
             .send()
             .await?;
 
-        self.handle_response(response).await
+        let result = self.handle_response(response).await?;
+        self.record("batch_update_bookmarks", description, prior)?;
+        Ok(result)
+    }
+
+    /// Applies tag, collection, flag, cover, media and note changes to a selection of bookmarks.
+    ///
+    /// Changes the bulk endpoint supports are sent in one request; removing tags and
+    /// appending to notes need each bookmark's current state and are applied per item.
+    pub async fn bulk_update(
+        &self,
+        selection: BookmarkSelection,
+        update: BulkUpdate,
+    ) -> Result<BulkUpdateReport> {
+        self.ensure_writable()?;
+        if update.is_empty() {
+            return Err(RaindropMcpError::InvalidParameter(
+                "No changes requested".to_string(),
+            ));
+        }
+        let bookmarks = self.select_bookmarks(&selection).await?;
+        let ids: Vec<i64> = bookmarks.iter().map(|b| b.id).collect();
+        info!("Bulk updating {} bookmarks", ids.len());
+        let mut report = BulkUpdateReport {
+            matched: ids.len(),
+            ids: ids.clone(),
+            ..Default::default()
+        };
+        if ids.is_empty() {
+            return Ok(report);
+        }
+
+        let mut prior = self.journal.is_some().then(|| bookmarks.clone());
+        // The whole operation is journaled once applied, not each write
+        let client = Self {
+            journal: None,
+            ..self.clone()
+        };
+
+        let mut bulk_applied = false;
+        if let Some(body) = update.bulk_body() {
+            client
+                .batch_update_bookmarks(selection.collection_id, ids.clone(), body)
+                .await?;
+            bulk_applied = true;
+        }
+        if !update.needs_per_item() {
+            report.updated = ids.len();
+            self.record_bulk_update(prior)?;
+            return Ok(report);
+        }
+
+        let mut changed = HashSet::new();
+        for bookmark in bookmarks {
+            let mut changes = serde_json::Map::new();
+            if !update.remove_tags.is_empty() {
+                let mut tags = bookmark.tags.clone();
+                for tag in &update.add_tags {
+                    if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                        tags.push(tag.clone());
+                    }
+                }
+                tags.retain(|t| !update.remove_tags.iter().any(|r| r.eq_ignore_ascii_case(t)));
+                changes.insert("tags".to_string(), json!(tags));
+            }
+            if let Some(text) = &update.append_note {
+                let existing = bookmark.note.as_deref().unwrap_or_default().trim_end();
+                let note = if existing.is_empty() {
+                    text.clone()
+                } else {
+                    format!("{existing}\n\n{text}")
+                };
+                changes.insert("note".to_string(), json!(note));
+            }
+
+            match client
+                .update_bookmark(bookmark.id, serde_json::Value::Object(changes))
+                .await
+            {
+                Ok(_) => {
+                    report.updated += 1;
+                    changed.insert(bookmark.id);
+                }
+                Err(e) => report.errors.push(format!("Bookmark {}: {e}", bookmark.id)),
+            }
+        }
+        // Without the bulk request, bookmarks whose own write failed were not changed
+        if !bulk_applied {
+            if let Some(prior) = &mut prior {
+                prior.retain(|b| changed.contains(&b.id));
+            }
+        }
+        self.record_bulk_update(prior)?;
+        Ok(report)
+    }
+
+    fn record_bulk_update(&self, prior: Option<Vec<Bookmark>>) -> Result<()> {
+        match prior {
+            Some(bookmarks) if !bookmarks.is_empty() => self.record(
+                "batch_update_bookmarks",
+                format!("Update {} bookmarks", bookmarks.len()),
+                Some(
+                    bookmarks
+                        .into_iter()
+                        .map(|b| Snapshot::Bookmark { state: Box::new(b) })
+                        .collect(),
+                ),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    // Resolves a selection to the bookmarks' current state
+    async fn select_bookmarks(&self, selection: &BookmarkSelection) -> Result<Vec<Bookmark>> {
+        match (&selection.ids, &selection.search) {
+            (Some(ids), None) if !ids.is_empty() => {
+                let mut bookmarks = Vec::with_capacity(ids.len());
+                for &id in ids {
+                    bookmarks.push(self.get_bookmark(id).await?);
+                }
+                Ok(bookmarks)
+            }
+            (None, Some(search)) => {
+                self.get_all_bookmarks(SearchParams {
+                    collection: Some(selection.collection_id),
+                    search: Some(search.clone()),
+                    ..Default::default()
+                })
+                .await
+            }
+            _ => Err(RaindropMcpError::InvalidParameter(
+                "Select bookmarks with either ids or a search filter".to_string(),
+            )),
+        }
     }
 
     pub async fn batch_delete_bookmarks(&self, ids: Vec<i64>, permanent: bool) -> Result<()> {
+        self.ensure_writable()?;
         info!(
             "Batch deleting {} bookmarks (permanent: {})",
             ids.len(),
             permanent
         );
+        let prior = match &self.journal {
+            Some(_) => Some(self.snapshot_bookmarks(&ids, permanent).await?),
+            None => None,
+        };
+        let description = format!("Delete {} bookmarks", ids.len());
 
         let body = json!({
             "ids": ids
@@ -359,13 +1149,314 @@ impl RaindropClient {
 
         let response = self.client.delete(url).json(&body).send().await?;
 
-        match response.status() {
-            StatusCode::OK | StatusCode::NO_CONTENT => Ok(()),
-            _ => self
-                .handle_response::<serde_json::Value>(response)
-                .await
-                .map(|_| ()),
+        self.handle_empty_response(response).await?;
+        self.record("batch_delete_bookmarks", description, prior)
+    }
+
+    // Trash API
+    pub async fn get_trash(
+        &self,
+        page: Option<i32>,
+        per_page: Option<i32>,
+    ) -> Result<BookmarksResponse> {
+        debug!(
+            "Fetching trash (page: {:?}, per_page: {:?})",
+            page, per_page
+        );
+        self.get_bookmarks(SearchParams {
+            collection: Some(TRASH_COLLECTION_ID),
+            page,
+            per_page,
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Moves bookmarks out of the trash, into `collection_id` when given, otherwise
+    /// into the collection recorded in the journal, falling back to Unsorted.
+    pub async fn restore_bookmarks(
+        &self,
+        ids: Vec<i64>,
+        collection_id: Option<i64>,
+    ) -> Result<Vec<RestoredBookmark>> {
+        info!("Restoring {} bookmarks from trash", ids.len());
+
+        let restored: Vec<RestoredBookmark> = ids
+            .into_iter()
+            .map(|id| {
+                let original = collection_id.is_none().then(|| {
+                    self.journal
+                        .as_ref()
+                        .and_then(|journal| journal.last_known_collection(id))
+                });
+                match (collection_id, original.flatten()) {
+                    (Some(collection), _) => RestoredBookmark {
+                        id,
+                        collection,
+                        original_collection: false,
+                    },
+                    (None, Some(collection)) => RestoredBookmark {
+                        id,
+                        collection,
+                        original_collection: true,
+                    },
+                    (None, None) => RestoredBookmark {
+                        id,
+                        collection: UNSORTED_COLLECTION_ID,
+                        original_collection: false,
+                    },
+                }
+            })
+            .collect();
+
+        // One batch move per target collection
+        let mut targets: Vec<i64> = restored.iter().map(|r| r.collection).collect();
+        targets.sort_unstable();
+        targets.dedup();
+        for target in targets {
+            let ids: Vec<i64> = restored
+                .iter()
+                .filter(|r| r.collection == target)
+                .map(|r| r.id)
+                .collect();
+            self.batch_update_bookmarks(
+                TRASH_COLLECTION_ID,
+                ids,
+                json!({ "collection": { "$id": target } }),
+            )
+            .await?;
         }
+
+        Ok(restored)
+    }
+
+    // Files API
+    /// Uploads a file as a new bookmark in `collection_id`.
+    pub async fn upload_file(
+        &self,
+        file_name: String,
+        data: Vec<u8>,
+        collection_id: i64,
+    ) -> Result<Bookmark> {
+        self.ensure_writable()?;
+        info!(
+            "Uploading file '{}' ({} bytes) to collection {}",
+            file_name,
+            data.len(),
+            collection_id
+        );
+        self.check_upload_quota(data.len()).await?;
+
+        let form = reqwest::multipart::Form::new()
+            .part("file", upload_part(file_name, data)?)
+            .text("collectionId", collection_id.to_string());
+        let response = self
+            .client
+            .put(format!("{}/raindrop/file", self.base_url))
+            .multipart(form)
+            .send()
+            .await?;
+
+        let result: BookmarkResponse = self.handle_response(response).await?;
+        self.record(
+            "upload_file",
+            format!("Upload file '{}'", result.item.title),
+            Some(vec![Snapshot::CreatedBookmark { id: result.item.id }]),
+        )?;
+        Ok(result.item)
+    }
+
+    /// Replaces the cover of an existing bookmark with an uploaded image.
+    pub async fn upload_cover(
+        &self,
+        id: i64,
+        file_name: String,
+        data: Vec<u8>,
+    ) -> Result<Bookmark> {
+        self.ensure_writable()?;
+        info!(
+            "Uploading cover '{}' ({} bytes) for bookmark {}",
+            file_name,
+            data.len(),
+            id
+        );
+        if !is_image(&data) {
+            return Err(RaindropMcpError::InvalidParameter(
+                "Cover must be a PNG, JPEG, GIF or WebP image".to_string(),
+            ));
+        }
+        self.check_upload_quota(data.len()).await?;
+
+        let prior = match &self.journal {
+            Some(_) => Some(self.snapshot_bookmarks(&[id], false).await?),
+            None => None,
+        };
+
+        let form = reqwest::multipart::Form::new().part("cover", upload_part(file_name, data)?);
+        let response = self
+            .client
+            .put(format!("{}/raindrop/{}/cover", self.base_url, id))
+            .multipart(form)
+            .send()
+            .await?;
+
+        let result: BookmarkResponse = self.handle_response(response).await?;
+        self.record(
+            "upload_cover",
+            format!("Upload cover for bookmark {id}"),
+            prior,
+        )?;
+        Ok(result.item)
+    }
+
+    // Rejects uploads above the per-file limit or the account's remaining file quota
+    async fn check_upload_quota(&self, size: usize) -> Result<()> {
+        if size == 0 {
+            return Err(RaindropMcpError::InvalidParameter(
+                "Cannot upload an empty file".to_string(),
+            ));
+        }
+        if size > MAX_UPLOAD_BYTES {
+            return Err(RaindropMcpError::InvalidParameter(format!(
+                "File is {size} bytes; the upload limit is {MAX_UPLOAD_BYTES} bytes"
+            )));
+        }
+
+        if let Some(files) = self.get_user_info().await?.files {
+            let remaining = (files.size - files.used).max(0);
+            if size as i64 > remaining {
+                return Err(RaindropMcpError::InvalidParameter(format!(
+                    "File is {size} bytes but only {remaining} bytes of file quota remain \
+                     ({} of {} used)",
+                    files.used, files.size
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Downloads the HTML of a bookmark's permanent copy (Pro accounts only).
+    pub async fn get_cached_copy(&self, id: i64) -> Result<String> {
+        debug!("Fetching permanent copy of bookmark: {}", id);
+        let response = self
+            .client
+            .get(format!("{}/raindrop/{}/cache", self.base_url, id))
+            .send()
+            .await?;
+
+        self.handle_text_response(response).await
+    }
+
+    // Reminders API
+    pub async fn set_reminder(
+        &self,
+        id: i64,
+        date: DateTime<Utc>,
+        note: Option<String>,
+    ) -> Result<Bookmark> {
+        info!("Setting reminder for bookmark {} at {}", id, date);
+        let reminder = Reminder {
+            date: Some(date.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+            note,
+        };
+        self.update_bookmark(id, json!({ "reminder": reminder }))
+            .await
+    }
+
+    pub async fn clear_reminder(&self, id: i64) -> Result<Bookmark> {
+        info!("Clearing reminder for bookmark {}", id);
+        self.update_bookmark(id, json!({ "reminder": null })).await
+    }
+
+    /// Newest bookmarks in a collection (0 = all), up to `limit`, stopping at the first one
+    /// created before `since`.
+    pub async fn get_recent_bookmarks(
+        &self,
+        collection_id: i64,
+        limit: usize,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Bookmark>> {
+        debug!(
+            "Fetching up to {} recent bookmarks in collection {} since {:?}",
+            limit, collection_id, since
+        );
+        let mut bookmarks = vec![];
+        let mut page = 0;
+        while bookmarks.len() < limit {
+            let result = self
+                .get_bookmarks(SearchParams {
+                    collection: Some(collection_id),
+                    sort: Some("-created".to_string()),
+                    page: Some(page),
+                    per_page: Some(MAX_PER_PAGE),
+                    ..Default::default()
+                })
+                .await?;
+            let fetched = result.items.len();
+            for bookmark in result.items {
+                let created = DateTime::parse_from_rfc3339(&bookmark.created)
+                    .ok()
+                    .map(|c| c.with_timezone(&Utc));
+                if matches!((since, created), (Some(since), Some(created)) if created < since) {
+                    return Ok(bookmarks);
+                }
+                if bookmarks.len() == limit {
+                    break;
+                }
+                bookmarks.push(bookmark);
+            }
+            if fetched < MAX_PER_PAGE as usize {
+                break;
+            }
+            page += 1;
+        }
+        Ok(bookmarks)
+    }
+
+    /// Finds the bookmarks whose reminder is due at or after `after`, sorted by reminder date.
+    pub async fn get_upcoming_reminders(
+        &self,
+        after: DateTime<Utc>,
+        limit: Option<usize>,
+    ) -> Result<Vec<UpcomingReminder>> {
+        debug!("Searching for reminders after {}", after);
+        // Only bookmarks with a reminder come back, so the library is never paged in full
+        let bookmarks = self
+            .get_all_bookmarks(SearchParams {
+                search: Some(REMINDER_FILTER.to_string()),
+                ..Default::default()
+            })
+            .await?;
+
+        let mut upcoming: Vec<(DateTime<Utc>, UpcomingReminder)> = bookmarks
+            .into_iter()
+            .filter_map(|b| {
+                let reminder = b.reminder?;
+                let date = DateTime::parse_from_rfc3339(reminder.date.as_deref()?)
+                    .ok()?
+                    .with_timezone(&Utc);
+                (date >= after).then(|| {
+                    (
+                        date,
+                        UpcomingReminder {
+                            bookmark_id: b.id,
+                            title: b.title,
+                            link: b.link,
+                            collection: b.collection.id,
+                            date: reminder.date.unwrap_or_default(),
+                            note: reminder.note,
+                        },
+                    )
+                })
+            })
+            .collect();
+        upcoming.sort_by_key(|(date, _)| *date);
+
+        Ok(upcoming
+            .into_iter()
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|(_, reminder)| reminder)
+            .collect())
     }
 
     // Tags API
@@ -390,7 +1481,16 @@ impl RaindropClient {
         new_name: String,
         collection_id: Option<i64>,
     ) -> Result<()> {
+        self.ensure_writable()?;
         info!("Renaming tag '{}' to '{}'", old_name, new_name);
+        let prior = match &self.journal {
+            Some(_) => Some(
+                self.snapshot_tagged(std::slice::from_ref(&old_name), collection_id)
+                    .await?,
+            ),
+            None => None,
+        };
+        let description = format!("Rename tag '{old_name}' to '{new_name}'");
 
         let url = if let Some(id) = collection_id {
             format!("{}/tags/{}", self.base_url, id)
@@ -405,17 +1505,45 @@ impl RaindropClient {
 
         let response = self.client.put(url).json(&body).send().await?;
 
-        match response.status() {
-            StatusCode::OK | StatusCode::NO_CONTENT => Ok(()),
-            _ => self
-                .handle_response::<serde_json::Value>(response)
-                .await
-                .map(|_| ()),
-        }
+        self.handle_empty_response(response).await?;
+        self.record("rename_tag", description, prior)
+    }
+
+    /// Replaces every tag in `tags` with `into`, on all bookmarks or within one collection.
+    pub async fn merge_tags(
+        &self,
+        tags: Vec<String>,
+        into: String,
+        collection_id: Option<i64>,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        info!("Merging {} tags into '{}'", tags.len(), into);
+        let prior = match &self.journal {
+            Some(_) => Some(self.snapshot_tagged(&tags, collection_id).await?),
+            None => None,
+        };
+        let description = format!("Merge tags {} into '{into}'", tags.join(", "));
+
+        let url = format!("{}/tags/{}", self.base_url, collection_id.unwrap_or(0));
+        let body = json!({
+            "tags": tags,
+            "replace": into
+        });
+
+        let response = self.client.put(url).json(&body).send().await?;
+
+        self.handle_empty_response(response).await?;
+        self.record("merge_tags", description, prior)
     }
 
     pub async fn delete_tags(&self, tags: Vec<String>, collection_id: Option<i64>) -> Result<()> {
+        self.ensure_writable()?;
         info!("Deleting {} tags", tags.len());
+        let prior = match &self.journal {
+            Some(_) => Some(self.snapshot_tagged(&tags, collection_id).await?),
+            None => None,
+        };
+        let description = format!("Delete tags {}", tags.join(", "));
 
         let url = if let Some(id) = collection_id {
             format!("{}/tags/{}", self.base_url, id)
@@ -429,13 +1557,8 @@ impl RaindropClient {
 
         let response = self.client.delete(url).json(&body).send().await?;
 
-        match response.status() {
-            StatusCode::OK | StatusCode::NO_CONTENT => Ok(()),
-            _ => self
-                .handle_response::<serde_json::Value>(response)
-                .await
-                .map(|_| ()),
-        }
+        self.handle_empty_response(response).await?;
+        self.record("delete_tags", description, prior)
     }
 
     // Highlights API
@@ -512,20 +1635,32 @@ impl RaindropClient {
 
     // Utility APIs
     pub async fn empty_trash(&self) -> Result<()> {
+        self.ensure_writable()?;
         info!("Emptying trash");
+        let prior = match &self.journal {
+            Some(_) => Some(
+                self.get_all_bookmarks(SearchParams {
+                    collection: Some(TRASH_COLLECTION_ID),
+                    ..Default::default()
+                })
+                .await?
+                .into_iter()
+                .map(|b| Snapshot::RemovedBookmark { state: Box::new(b) })
+                .collect(),
+            ),
+            None => None,
+        };
         let response = self
             .client
-            .delete(format!("{}/raindrops/-99", self.base_url))
+            .delete(format!(
+                "{}/raindrops/{}",
+                self.base_url, TRASH_COLLECTION_ID
+            ))
             .send()
             .await?;
 
-        match response.status() {
-            StatusCode::OK | StatusCode::NO_CONTENT => Ok(()),
-            _ => self
-                .handle_response::<serde_json::Value>(response)
-                .await
-                .map(|_| ()),
-        }
+        self.handle_empty_response(response).await?;
+        self.record("empty_trash", "Empty trash".to_string(), prior)
     }
 
     pub async fn export_bookmarks(&self, options: ExportOptions) -> Result<serde_json::Value> {
@@ -562,4 +1697,1373 @@ impl RaindropClient {
 
         self.handle_response(response).await
     }
+
+    // Undo journal
+    pub fn list_operations(&self, limit: Option<usize>) -> Result<Vec<JournalEntrySummary>> {
+        Ok(self.require_journal()?.list(limit))
+    }
+
+    pub async fn undo_last(&self) -> Result<UndoReport> {
+        self.ensure_writable()?;
+        let entry = self
+            .require_journal()?
+            .last_undoable()
+            .ok_or_else(|| RaindropMcpError::NotFound("No operation to undo".to_string()))?;
+        self.undo_entry(entry).await
+    }
+
+    pub async fn undo_operation(&self, id: u64) -> Result<UndoReport> {
+        self.ensure_writable()?;
+        let entry = self
+            .require_journal()?
+            .get(id)
+            .ok_or_else(|| RaindropMcpError::NotFound(format!("Journal entry {id}")))?;
+        if entry.undone {
+            return Err(RaindropMcpError::InvalidParameter(format!(
+                "Operation {id} has already been undone"
+            )));
+        }
+        self.undo_entry(entry).await
+    }
+
+    async fn undo_entry(&self, entry: JournalEntry) -> Result<UndoReport> {
+        info!("Undoing operation {}: {}", entry.id, entry.description);
+        let journal = self.require_journal()?.clone();
+        // Inverse writes are not journaled themselves
+        let client = Self {
+            journal: None,
+            ..self.clone()
+        };
+
+        let mut report = UndoReport {
+            operation_id: entry.id,
+            operation: entry.operation.clone(),
+            ..Default::default()
+        };
+        // Collections re-created during undo get new IDs
+        let mut remapped: HashMap<i64, i64> = HashMap::new();
+        // Snapshots whose inverse write failed, left in the entry for another attempt
+        let mut remaining = vec![];
+
+        for snapshot in &entry.snapshots {
+            let outcome = match snapshot {
+                Snapshot::Bookmark { state } => {
+                    let collection = remapped
+                        .get(&state.collection.id)
+                        .copied()
+                        .unwrap_or(state.collection.id);
+                    client
+                        .update_bookmark(state.id, bookmark_restore_body(state, collection))
+                        .await
+                        .map(|_| ())
+                }
+                Snapshot::CreatedBookmark { id } => client.delete_bookmark(*id, false).await,
+                Snapshot::RemovedBookmark { state } => {
+                    let collection = match state.collection.id {
+                        // Items removed from the trash come back as unsorted
+                        TRASH_COLLECTION_ID => UNSORTED_COLLECTION_ID,
+                        id => remapped.get(&id).copied().unwrap_or(id),
+                    };
+                    client
+                        .post_bookmark(bookmark_restore_body(state, collection))
+                        .await
+                        .map(|_| ())
+                }
+                Snapshot::Collection { state } => client
+                    .update_collection(state.id, collection_restore_body(state))
+                    .await
+                    .map(|_| ()),
+                Snapshot::CreatedCollection { id } => client.delete_collection(*id).await,
+                Snapshot::RemovedCollection { state } => client
+                    .post_collection(collection_restore_body(state))
+                    .await
+                    .map(|created| {
+                        remapped.insert(state.id, created.id);
+                    }),
+                Snapshot::Irreversible { reason } => {
+                    report.skipped.push(reason.clone());
+                    continue;
+                }
+            };
+
+            match outcome {
+                Ok(()) => report.restored += 1,
+                Err(e) => {
+                    report.errors.push(e.to_string());
+                    remaining.push(snapshot.clone());
+                }
+            }
+        }
+
+        if report.errors.is_empty() {
+            journal.mark_undone(entry.id)?;
+        } else {
+            // Retrying must not repeat what succeeded, such as re-creating a removed bookmark
+            for snapshot in &mut remaining {
+                if let Snapshot::Bookmark { state } | Snapshot::RemovedBookmark { state } = snapshot
+                {
+                    if let Some(&id) = remapped.get(&state.collection.id) {
+                        state.collection.id = id;
+                    }
+                }
+            }
+            journal.replace_snapshots(entry.id, remaining)?;
+        }
+        Ok(report)
+    }
+
+    fn require_journal(&self) -> Result<&Arc<Journal>> {
+        self.journal.as_ref().ok_or_else(|| {
+            RaindropMcpError::InvalidParameter("The undo journal is disabled".to_string())
+        })
+    }
+
+    // Captures bookmarks before they are updated, trashed, or permanently removed
+    async fn snapshot_bookmarks(&self, ids: &[i64], permanent: bool) -> Result<Vec<Snapshot>> {
+        let mut snapshots = Vec::with_capacity(ids.len());
+        for &id in ids {
+            let state = Box::new(self.get_bookmark(id).await?);
+            snapshots.push(if permanent {
+                Snapshot::RemovedBookmark { state }
+            } else {
+                Snapshot::Bookmark { state }
+            });
+        }
+        Ok(snapshots)
+    }
+
+    async fn snapshot_search(&self, params: SearchParams) -> Result<Vec<Snapshot>> {
+        Ok(self
+            .get_all_bookmarks(params)
+            .await?
+            .into_iter()
+            .map(|b| Snapshot::Bookmark { state: Box::new(b) })
+            .collect())
+    }
+
+    // Captures every bookmark carrying one of `tags`, optionally within a collection
+    async fn snapshot_tagged(
+        &self,
+        tags: &[String],
+        collection_id: Option<i64>,
+    ) -> Result<Vec<Snapshot>> {
+        let mut seen = std::collections::HashSet::new();
+        let mut snapshots = vec![];
+        for tag in tags {
+            let bookmarks = self
+                .get_all_bookmarks(SearchParams {
+                    collection: collection_id,
+                    tags: Some(vec![tag.clone()]),
+                    ..Default::default()
+                })
+                .await?;
+            snapshots.extend(
+                bookmarks
+                    .into_iter()
+                    .filter(|b| b.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+                    .filter(|b| seen.insert(b.id))
+                    .map(|b| Snapshot::Bookmark { state: Box::new(b) }),
+            );
+        }
+        Ok(snapshots)
+    }
+
+    async fn post_bookmark(&self, body: serde_json::Value) -> Result<Bookmark> {
+        self.ensure_writable()?;
+        let response = self
+            .client
+            .post(format!("{}/raindrop", self.base_url))
+            .json(&body)
+            .send()
+            .await?;
+
+        let result: BookmarkResponse = self.handle_response(response).await?;
+        Ok(result.item)
+    }
+
+    async fn post_collection(&self, body: serde_json::Value) -> Result<Collection> {
+        self.ensure_writable()?;
+        let response = self
+            .client
+            .post(format!("{}/collection", self.base_url))
+            .json(&body)
+            .send()
+            .await?;
+
+        let result: CollectionResponse = self.handle_response(response).await?;
+        Ok(result.item)
+    }
+}
+
+fn upload_part(file_name: String, data: Vec<u8>) -> Result<reqwest::multipart::Part> {
+    let mime = mime_guess::from_path(&file_name).first_or_octet_stream();
+    reqwest::multipart::Part::bytes(data)
+        .file_name(file_name)
+        .mime_str(mime.as_ref())
+        .map_err(RaindropMcpError::HttpRequest)
+}
+
+// Checks the magic bytes of common web image formats
+fn is_image(data: &[u8]) -> bool {
+    data.starts_with(b"\x89PNG\r\n\x1a\n")
+        || data.starts_with(&[0xff, 0xd8, 0xff])
+        || data.starts_with(b"GIF87a")
+        || data.starts_with(b"GIF89a")
+        || (data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP")
+}
+
+// Request body that puts a bookmark back into the captured state
+fn bookmark_restore_body(state: &Bookmark, collection_id: i64) -> serde_json::Value {
+    let mut body = json!({
+        "link": state.link,
+        "title": state.title,
+        "excerpt": state.excerpt.clone().unwrap_or_default(),
+        "note": state.note.clone().unwrap_or_default(),
+        "tags": state.tags,
+        "important": state.important,
+        "collection": { "$id": collection_id }
+    });
+    if let Some(cover) = &state.cover {
+        body["cover"] = json!(cover);
+    }
+    // Always sent so that undoing set_reminder also clears a reminder added later
+    body["reminder"] = json!(state.reminder);
+    body
+}
+
+fn collection_restore_body(state: &Collection) -> serde_json::Value {
+    let mut body = json!({
+        "title": state.title,
+        "view": state.view,
+    });
+    if let Some(description) = &state.description {
+        body["description"] = json!(description);
+    }
+    if let Some(public) = state.public {
+        body["public"] = json!(public);
+    }
+    if let Some(color) = &state.color {
+        body["color"] = json!(color);
+    }
+    if let Some(parent) = &state.parent {
+        body["parent"] = json!({ "$id": parent.id });
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::OAuthSettings,
+        raindrop::oauth::{OAuthClient, StoredTokens, TokenStore},
+    };
+    use pretty_assertions::assert_eq;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_partial_json, header, method, path, path_regex, query_param},
+    };
+
+    fn bookmark_json(id: i64, title: &str, tags: &[&str]) -> serde_json::Value {
+        json!({
+            "_id": id,
+            "title": title,
+            "excerpt": "",
+            "note": "",
+            "type": "link",
+            "tags": tags,
+            "cover": "",
+            "link": "https://example.com",
+            "domain": "example.com",
+            "created": "2024-01-01T00:00:00Z",
+            "lastUpdate": "2024-01-01T00:00:00Z",
+            "user": { "$id": 1 },
+            "collection": { "$id": 10 },
+            "important": false,
+            "broken": false
+        })
+    }
+
+    fn journaled_client(server: &MockServer, name: &str) -> RaindropClient {
+        let dir = env::temp_dir().join(format!(
+            "raindrop-client-test-{}-{name}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let journal = Journal::open(dir.join("journal.json")).unwrap();
+        RaindropClient::with_token(server.uri(), "test-token")
+            .unwrap()
+            .with_journal(Arc::new(journal))
+    }
+
+    fn read_only_client(server: &MockServer) -> RaindropClient {
+        let profile = Profile {
+            token: Some("test-token".to_string()),
+            base_url: Some(server.uri()),
+            read_only: true,
+            ..Default::default()
+        };
+        RaindropClient::from_profile("readonly", &profile).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_update_is_journaled_and_undone() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/raindrop/1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "item": bookmark_json(1, "Original", &["rust"]) })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/raindrop/1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "item": bookmark_json(1, "Changed", &["go"]) })),
+            )
+            .mount(&server)
+            .await;
+
+        let client = journaled_client(&server, "update");
+        client
+            .update_bookmark(1, json!({ "title": "Changed", "tags": ["go"] }))
+            .await
+            .unwrap();
+
+        let operations = client.list_operations(None).unwrap();
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].operation, "update_bookmark");
+        assert_eq!(operations[0].affected_items, 1);
+
+        let report = client.undo_last().await.unwrap();
+        assert_eq!(report.restored, 1);
+        assert!(report.errors.is_empty());
+
+        let requests = server.received_requests().await.unwrap();
+        let restore = requests
+            .iter()
+            .filter(|r| r.method.as_str() == "PUT")
+            .last()
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&restore.body).unwrap();
+        assert_eq!(body["title"], "Original");
+        assert_eq!(body["tags"], json!(["rust"]));
+        assert_eq!(body["collection"]["$id"], 10);
+
+        // The inverse write itself is not journaled, and the entry is now undone
+        assert_eq!(client.list_operations(None).unwrap().len(), 1);
+        assert!(client.undo_last().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_writes_are_not_journaled() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/raindrop/1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "item": bookmark_json(1, "Original", &["rust"]) })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/raindrops/0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": [bookmark_json(1, "Original", &["rust"])],
+                "count": 1
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("boom"))
+            .mount(&server)
+            .await;
+
+        let client = journaled_client(&server, "failed-write");
+        assert!(
+            client
+                .update_bookmark(1, json!({ "title": "Changed" }))
+                .await
+                .is_err()
+        );
+        assert!(
+            client
+                .rename_tag("rust".to_string(), "go".to_string(), None)
+                .await
+                .is_err()
+        );
+        assert!(
+            client
+                .bulk_update(
+                    BookmarkSelection {
+                        ids: Some(vec![1]),
+                        ..Default::default()
+                    },
+                    BulkUpdate {
+                        add_tags: vec!["go".to_string()],
+                        ..Default::default()
+                    },
+                )
+                .await
+                .is_err()
+        );
+
+        assert!(client.list_operations(None).unwrap().is_empty());
+        let path = client.journal().unwrap().path().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_partially_failed_undo_can_be_retried() {
+        let server = MockServer::start().await;
+        for id in [1, 2] {
+            Mock::given(method("GET"))
+                .and(path(format!("/raindrop/{id}")))
+                .respond_with(ResponseTemplate::new(200).set_body_json(
+                    json!({ "item": bookmark_json(id, &format!("Original {id}"), &[]) }),
+                ))
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("PUT"))
+            .and(path("/raindrops/0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "result": true })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/raindrop/2"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("boom"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        for id in [1, 2] {
+            Mock::given(method("PUT"))
+                .and(path(format!("/raindrop/{id}")))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(json!({ "item": bookmark_json(id, "Restored", &[]) })),
+                )
+                .mount(&server)
+                .await;
+        }
+
+        let client = journaled_client(&server, "partial-undo");
+        client
+            .batch_update_bookmarks(0, vec![1, 2], json!({ "important": true }))
+            .await
+            .unwrap();
+
+        let report = client.undo_last().await.unwrap();
+        assert_eq!(report.restored, 1);
+        assert_eq!(report.errors.len(), 1);
+        let operations = client.list_operations(None).unwrap();
+        assert!(!operations[0].undone);
+        assert_eq!(operations[0].affected_items, 1);
+
+        // The retry restores only the bookmark that failed
+        let report = client.undo_last().await.unwrap();
+        assert_eq!(report.restored, 1);
+        assert!(report.errors.is_empty());
+        assert!(client.list_operations(None).unwrap()[0].undone);
+
+        let restores: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|r| r.method.as_str() == "PUT" && r.url.path().starts_with("/raindrop/"))
+            .map(|r| r.url.path().to_string())
+            .collect();
+        assert_eq!(restores, vec!["/raindrop/1", "/raindrop/2", "/raindrop/2"]);
+    }
+
+    #[tokio::test]
+    async fn test_upcoming_reminders_sorted_and_filtered() {
+        let server = MockServer::start().await;
+        let with_reminder = |id: i64, date: &str| {
+            let mut bookmark = bookmark_json(id, &format!("Bookmark {id}"), &[]);
+            bookmark["reminder"] = json!({ "date": date });
+            bookmark
+        };
+        Mock::given(method("GET"))
+            .and(path("/raindrops/0"))
+            .and(query_param("search", REMINDER_FILTER))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": [
+                    with_reminder(1, "2030-03-01T00:00:00.000Z"),
+                    with_reminder(2, "2020-01-01T00:00:00.000Z"),
+                    bookmark_json(3, "No reminder", &[]),
+                    with_reminder(4, "2030-01-15T08:30:00.000Z"),
+                ],
+                "count": 4
+            })))
+            .mount(&server)
+            .await;
+
+        let client = RaindropClient::with_token(server.uri(), "test-token").unwrap();
+        let after = DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let upcoming = client.get_upcoming_reminders(after, None).await.unwrap();
+
+        let ids: Vec<i64> = upcoming.iter().map(|r| r.bookmark_id).collect();
+        assert_eq!(ids, vec![4, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_recent_bookmarks_stop_at_cutoff() {
+        let server = MockServer::start().await;
+        let created = |id: i64, date: &str| {
+            let mut bookmark = bookmark_json(id, &format!("Bookmark {id}"), &[]);
+            bookmark["created"] = json!(date);
+            bookmark
+        };
+        Mock::given(method("GET"))
+            .and(path("/raindrops/-1"))
+            .and(query_param("sort", "-created"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": [
+                    created(3, "2024-06-10T00:00:00.000Z"),
+                    created(2, "2024-06-05T00:00:00.000Z"),
+                    created(1, "2024-05-01T00:00:00.000Z"),
+                ],
+                "count": 3
+            })))
+            .mount(&server)
+            .await;
+
+        let client = RaindropClient::with_token(server.uri(), "test-token").unwrap();
+        let since = DateTime::parse_from_rfc3339("2024-06-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let ids = |bookmarks: Vec<Bookmark>| bookmarks.iter().map(|b| b.id).collect::<Vec<_>>();
+
+        let recent = client.get_recent_bookmarks(-1, 10, Some(since)).await;
+        assert_eq!(ids(recent.unwrap()), vec![3, 2]);
+        let recent = client.get_recent_bookmarks(-1, 1, None).await;
+        assert_eq!(ids(recent.unwrap()), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_paging_stops_when_enough() {
+        let server = MockServer::start().await;
+        let page = |first: i64| {
+            (first..first + MAX_PER_PAGE as i64)
+                .map(|id| bookmark_json(id, &format!("Bookmark {id}"), &[]))
+                .collect::<Vec<_>>()
+        };
+        for (number, first) in [("0", 0), ("1", 50)] {
+            Mock::given(method("GET"))
+                .and(path("/raindrops/0"))
+                .and(query_param("page", number))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "items": page(first),
+                    "count": 500
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let client = RaindropClient::with_token(server.uri(), "test-token").unwrap();
+        let (bookmarks, total) = client
+            .get_bookmarks_until(SearchParams::default(), |fetched| fetched.len() >= 60)
+            .await
+            .unwrap();
+        assert_eq!(bookmarks.len(), 100);
+        assert_eq!(total, 500);
+    }
+
+    #[tokio::test]
+    async fn test_search_is_encoded_once() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/raindrops/0"))
+            .and(query_param("search", "#rust type:article café"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": [bookmark_json(1, "Found", &["rust"])],
+                "count": 1
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = RaindropClient::with_token(server.uri(), "test-token").unwrap();
+        let result = client
+            .get_bookmarks(SearchParams {
+                search: Some("#rust type:article café".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.items[0].title, "Found");
+    }
+
+    #[tokio::test]
+    async fn test_upload_rejected_over_quota() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/user"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "user": {
+                    "_id": 1,
+                    "pro": true,
+                    "files": { "used": 990, "size": 1000, "lastCheckPoint": "" }
+                }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/raindrop/file"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let client = RaindropClient::with_token(server.uri(), "test-token").unwrap();
+        let err = client
+            .upload_file("notes.pdf".to_string(), vec![0; 64], UNSORTED_COLLECTION_ID)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RaindropMcpError::InvalidParameter(_)));
+        assert!(err.to_string().contains("10 bytes of file quota remain"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_uploads_are_reported() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/user"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "user": {
+                    "_id": 1,
+                    "pro": true,
+                    "files": { "used": 0, "size": 1000, "lastCheckPoint": "" }
+                }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/raindrop/file"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("storage down"))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/raindrop/1/cover"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let client = journaled_client(&server, "failed-upload");
+        let err = client
+            .upload_file("notes.pdf".to_string(), vec![1; 64], UNSORTED_COLLECTION_ID)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("storage down"), "{err}");
+        let err = client
+            .upload_file("empty.pdf".to_string(), vec![], UNSORTED_COLLECTION_ID)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RaindropMcpError::InvalidParameter(_)));
+        // Only images can become covers
+        let err = client
+            .upload_cover(1, "cover.txt".to_string(), b"plain text".to_vec())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RaindropMcpError::InvalidParameter(_)));
+        assert!(client.list_operations(None).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_only_profile_rejects_uploads() {
+        let server = MockServer::start().await;
+        let client = read_only_client(&server);
+        assert!(matches!(
+            client
+                .upload_file("notes.pdf".to_string(), vec![1; 64], UNSORTED_COLLECTION_ID)
+                .await,
+            Err(RaindropMcpError::ReadOnly(_))
+        ));
+        assert!(matches!(
+            client
+                .upload_cover(1, "cover.png".to_string(), b"\x89PNG\r\n\x1a\n".to_vec())
+                .await,
+            Err(RaindropMcpError::ReadOnly(_))
+        ));
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_parse_url_uses_first_image_as_cover() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/import/url/parse"))
+            .and(query_param("url", "https://example.com/post"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "result": true,
+                "item": {
+                    "title": "A post",
+                    "excerpt": "About things",
+                    "type": "article",
+                    "media": [{ "type": "image", "link": "https://example.com/cover.png" }],
+                    "meta": { "canonical": "https://example.com/post" }
+                }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/import/url/parse"))
+            .and(query_param("url", "https://example.com/missing"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "result": false,
+                "error": "not_found"
+            })))
+            .mount(&server)
+            .await;
+
+        let client = RaindropClient::with_token(server.uri(), "test-token").unwrap();
+        let parsed = client.parse_url("https://example.com/post").await.unwrap();
+        assert_eq!(parsed.title.as_deref(), Some("A post"));
+        assert_eq!(
+            parsed.cover.as_deref(),
+            Some("https://example.com/cover.png")
+        );
+        assert!(matches!(parsed.bookmark_type, Some(BookmarkType::Article)));
+
+        let err = client
+            .parse_url("https://example.com/missing")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not_found"));
+    }
+
+    #[tokio::test]
+    async fn test_exists_maps_ids_back_to_normalized_urls() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/import/url/exists"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "result": true,
+                "ids": [7, 8],
+                "duplicates": [
+                    { "_id": 7, "link": "https://www.example.com/post/" },
+                    { "_id": 8, "link": "http://example.com/post?utm_source=feed" }
+                ]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = RaindropClient::with_token(server.uri(), "test-token").unwrap();
+        let results = client
+            .exists(&[
+                "https://example.com/post".to_string(),
+                "https://example.com/new".to_string(),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].exists);
+        assert_eq!(results[0].ids, vec![7, 8]);
+        assert_eq!(results[0].normalized, "example.com/post");
+        assert!(!results[1].exists);
+        assert!(results[1].ids.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_exists_looks_up_bare_ids_in_one_search() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/import/url/exists"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "result": true,
+                "ids": [3, 4]
+            })))
+            .mount(&server)
+            .await;
+        let linked = |id: i64, link: &str| {
+            let mut bookmark = bookmark_json(id, &format!("Bookmark {id}"), &[]);
+            bookmark["link"] = json!(link);
+            bookmark
+        };
+        Mock::given(method("GET"))
+            .and(path("/raindrops/0"))
+            .and(query_param("ids", "3"))
+            .and(query_param("ids", "4"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": [linked(3, "https://a.example/"), linked(4, "https://b.example/")],
+                "count": 2
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex(r"^/raindrop/\d+$"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let client = RaindropClient::with_token(server.uri(), "test-token").unwrap();
+        let results = client
+            .exists(&[
+                "https://b.example".to_string(),
+                "https://a.example".to_string(),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(results[0].ids, vec![4]);
+        assert_eq!(results[1].ids, vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_create_bookmarks_reports_per_item_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/import/url/exists"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "result": true,
+                "ids": [5],
+                "duplicates": [{ "_id": 5, "link": "https://example.com/saved" }]
+            })))
+            .mount(&server)
+            .await;
+        let mut created = bookmark_json(100, "New", &[]);
+        created["link"] = json!("https://example.com/new");
+        Mock::given(method("POST"))
+            .and(path("/raindrops"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "result": true, "items": [created] })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = journaled_client(&server, "bulk-create");
+        let item = |link: &str| NewBookmark {
+            link: link.to_string(),
+            ..Default::default()
+        };
+        let results = client
+            .create_bookmarks(
+                vec![
+                    item("https://example.com/new"),
+                    item("https://www.example.com/new/"),
+                    item("https://example.com/saved"),
+                    item("not a url"),
+                ],
+                true,
+            )
+            .await
+            .unwrap();
+
+        let statuses: Vec<_> = results.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                BulkCreateStatus::Created,
+                BulkCreateStatus::Duplicate,
+                BulkCreateStatus::Duplicate,
+                BulkCreateStatus::Failed,
+            ]
+        );
+        assert_eq!(results[0].id, Some(100));
+        assert_eq!(results[2].existing_ids, vec![5]);
+
+        let journal = client.journal().unwrap();
+        assert_eq!(journal.list(None)[0].operation, "create_bookmarks");
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_reports_failed_items() {
+        let server = MockServer::start().await;
+        for id in [1, 2] {
+            Mock::given(method("GET"))
+                .and(path(format!("/raindrop/{id}")))
+                .respond_with(ResponseTemplate::new(200).set_body_json(
+                    json!({ "item": bookmark_json(id, "Tagged", &["old", "keep"]) }),
+                ))
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("PUT"))
+            .and(path("/raindrop/1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "item": bookmark_json(1, "Tagged", &["keep"]) })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/raindrop/2"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("locked"))
+            .mount(&server)
+            .await;
+
+        let client = journaled_client(&server, "bulk-partial");
+        let selection = BookmarkSelection {
+            ids: Some(vec![1, 2]),
+            ..Default::default()
+        };
+        let remove_old = BulkUpdate {
+            remove_tags: vec!["old".to_string()],
+            ..Default::default()
+        };
+        let report = client
+            .bulk_update(selection.clone(), remove_old)
+            .await
+            .unwrap();
+        assert_eq!(report.matched, 2);
+        assert_eq!(report.updated, 1);
+        assert_eq!(report.errors.len(), 1);
+        assert!(
+            report.errors[0].starts_with("Bookmark 2:"),
+            "{:?}",
+            report.errors
+        );
+
+        // Only the bookmark that changed is journaled
+        let operations = client.list_operations(None).unwrap();
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].affected_items, 1);
+
+        assert!(matches!(
+            client.bulk_update(selection, BulkUpdate::default()).await,
+            Err(RaindropMcpError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            client
+                .bulk_update(
+                    BookmarkSelection::default(),
+                    BulkUpdate {
+                        important: Some(true),
+                        ..Default::default()
+                    },
+                )
+                .await,
+            Err(RaindropMcpError::InvalidParameter(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_profile_rejects_bulk_update() {
+        let server = MockServer::start().await;
+        let client = read_only_client(&server);
+        let result = client
+            .bulk_update(
+                BookmarkSelection {
+                    ids: Some(vec![1]),
+                    ..Default::default()
+                },
+                BulkUpdate {
+                    remove_tags: vec!["old".to_string()],
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert!(matches!(result, Err(RaindropMcpError::ReadOnly(_))));
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_create_bookmarks_reports_a_failed_batch() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/raindrops"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("overloaded"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = journaled_client(&server, "failed-create");
+        let new = |link: &str| NewBookmark {
+            link: link.to_string(),
+            ..Default::default()
+        };
+        let results = client
+            .create_bookmarks(
+                vec![new("https://a.example"), new("https://b.example")],
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        for result in &results {
+            assert_eq!(result.status, BulkCreateStatus::Failed);
+            assert!(result.message.as_deref().unwrap().contains("overloaded"));
+        }
+        assert!(client.list_operations(None).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_only_profile_rejects_bulk_create() {
+        let server = MockServer::start().await;
+        let client = read_only_client(&server);
+        let result = client
+            .create_bookmarks(
+                vec![NewBookmark {
+                    link: "https://a.example".to_string(),
+                    ..Default::default()
+                }],
+                true,
+            )
+            .await;
+        assert!(matches!(result, Err(RaindropMcpError::ReadOnly(_))));
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_adds_and_removes_tags() {
+        let server = MockServer::start().await;
+        for (id, tags) in [(1, vec!["old", "rust"]), (2, vec!["go"])] {
+            Mock::given(method("GET"))
+                .and(path(format!("/raindrop/{id}")))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(json!({ "item": bookmark_json(id, "Post", &tags) })),
+                )
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("PUT"))
+            .and(path("/raindrops/0"))
+            .and(body_partial_json(json!({
+                "ids": [1, 2],
+                "tags": ["new"],
+                "collection": { "$id": 5 }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "result": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/raindrop/1"))
+            .and(body_partial_json(json!({ "tags": ["rust", "new"] })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "item": bookmark_json(1, "Post", &["rust", "new"]) })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/raindrop/2"))
+            .and(body_partial_json(json!({ "tags": ["go", "new"] })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "item": bookmark_json(2, "Post", &["go", "new"]) })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = journaled_client(&server, "bulk-update");
+        let report = client
+            .bulk_update(
+                BookmarkSelection {
+                    ids: Some(vec![1, 2]),
+                    ..Default::default()
+                },
+                BulkUpdate {
+                    add_tags: vec!["new".to_string()],
+                    remove_tags: vec!["OLD".to_string()],
+                    collection: Some(5),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(report.matched, 2);
+        assert_eq!(report.updated, 2);
+        assert!(report.errors.is_empty());
+        // One journal entry for the whole operation
+        assert_eq!(client.journal().unwrap().list(None).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_profile_rejects_writes() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/raindrop/1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "item": bookmark_json(1, "Original", &[]) })),
+            )
+            .mount(&server)
+            .await;
+
+        let profile = Profile {
+            token: Some("test-token".to_string()),
+            base_url: Some(server.uri()),
+            read_only: true,
+            default_collection: Some(42),
+            ..Default::default()
+        };
+        let client = RaindropClient::from_profile("readonly", &profile).unwrap();
+        assert!(client.journal().is_none());
+        assert_eq!(client.default_collection(), 42);
+
+        assert_eq!(client.get_bookmark(1).await.unwrap().title, "Original");
+        assert!(matches!(
+            client
+                .update_bookmark(1, json!({ "title": "Changed" }))
+                .await,
+            Err(RaindropMcpError::ReadOnly(_))
+        ));
+        assert!(matches!(
+            client
+                .create_bookmark_from(NewBookmark {
+                    link: "https://example.com".to_string(),
+                    ..Default::default()
+                })
+                .await,
+            Err(RaindropMcpError::ReadOnly(_))
+        ));
+
+        // Nothing but the read reached the API
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_profile_can_only_list_trash() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/raindrops/-99"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": [bookmark_json(1, "Trashed", &[])],
+                "count": 1
+            })))
+            .mount(&server)
+            .await;
+
+        let client = read_only_client(&server);
+        let trash = client.get_trash(None, None).await.unwrap();
+        assert_eq!(trash.items[0].title, "Trashed");
+        assert!(matches!(
+            client.delete_bookmark(1, true).await,
+            Err(RaindropMcpError::ReadOnly(_))
+        ));
+        assert!(matches!(
+            client.batch_delete_bookmarks(vec![1, 2], true).await,
+            Err(RaindropMcpError::ReadOnly(_))
+        ));
+        assert!(matches!(
+            client.restore_bookmarks(vec![1], Some(5)).await,
+            Err(RaindropMcpError::ReadOnly(_))
+        ));
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_trash_writes_are_reported() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/raindrop/1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "item": bookmark_json(1, "Original", &[]) })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/raindrop/1"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/raindrops/-99"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("boom"))
+            .mount(&server)
+            .await;
+
+        let client = journaled_client(&server, "failed-trash");
+        assert!(matches!(
+            client.delete_bookmark(1, true).await,
+            Err(RaindropMcpError::NotFound(_))
+        ));
+        let restored = client.restore_bookmarks(vec![1], None).await;
+        assert!(
+            matches!(&restored, Err(RaindropMcpError::RaindropApi(message, _)) if message.contains("boom")),
+            "{restored:?}"
+        );
+        assert!(client.list_operations(None).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_scoped_tag_changes_are_reported() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/raindrops/5"))
+            .and(query_param("tag", "old"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": [bookmark_json(1, "Tagged", &["old"])],
+                "count": 1
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/tags/5"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/tags/5"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("busy"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = journaled_client(&server, "failed-scoped-tags");
+        assert!(matches!(
+            client
+                .rename_tag("old".to_string(), "new".to_string(), Some(5))
+                .await,
+            Err(RaindropMcpError::NotFound(_))
+        ));
+        let deleted = client.delete_tags(vec!["old".to_string()], Some(5)).await;
+        assert!(
+            matches!(&deleted, Err(RaindropMcpError::RaindropApi(message, _)) if message.contains("busy")),
+            "{deleted:?}"
+        );
+        assert!(client.list_operations(None).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_only_profile_rejects_tag_changes() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/tags/5"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": [{ "_id": "old", "count": 2 }]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = read_only_client(&server);
+        assert_eq!(client.get_tags(Some(5)).await.unwrap()[0].count, 2);
+        assert!(matches!(
+            client
+                .rename_tag("old".to_string(), "new".to_string(), Some(5))
+                .await,
+            Err(RaindropMcpError::ReadOnly(_))
+        ));
+        assert!(matches!(
+            client.delete_tags(vec!["old".to_string()], Some(5)).await,
+            Err(RaindropMcpError::ReadOnly(_))
+        ));
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_oauth_token_refreshed_on_unauthorized() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/raindrop/1"))
+            .and(header("authorization", "Bearer expired"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/raindrop/1"))
+            .and(header("authorization", "Bearer fresh"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "item": bookmark_json(1, "Saved", &[]) })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/access_token"))
+            .and(body_partial_json(
+                json!({ "grant_type": "refresh_token", "refresh_token": "refresh" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                json!({ "access_token": "fresh", "refresh_token": "refresh-2", "expires_in": 3600 }),
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let token_file = env::temp_dir().join(format!(
+            "raindrop-client-test-{}-oauth/tokens.json",
+            std::process::id()
+        ));
+        let settings = OAuthSettings {
+            client_id: "app".to_string(),
+            client_secret: Some("secret".to_string()),
+            client_secret_env: None,
+            redirect_uri: None,
+            token_file: Some(token_file.clone()),
+            authorize_url: None,
+            token_url: Some(format!("{}/oauth/access_token", server.uri())),
+        };
+        let store = TokenStore::for_profile("test", &settings).unwrap();
+        let session = OAuthSession::new(
+            OAuthClient::new(&settings).unwrap(),
+            store.clone(),
+            StoredTokens {
+                access_token: "expired".to_string(),
+                refresh_token: Some("refresh".to_string()),
+                expires_at: None,
+            },
+        );
+        let client = RaindropClient::with_oauth(server.uri(), Arc::new(session)).unwrap();
+
+        assert_eq!(client.get_bookmark(1).await.unwrap().title, "Saved");
+        assert_eq!(client.get_bookmark(1).await.unwrap().title, "Saved");
+        let saved = store.load().unwrap().unwrap();
+        assert_eq!(saved.access_token, "fresh");
+        assert_eq!(saved.refresh_token.as_deref(), Some("refresh-2"));
+    }
+
+    #[tokio::test]
+    async fn test_find_collection_by_name() {
+        let collection = |id: i64, title: &str| {
+            json!({
+                "_id": id,
+                "title": title,
+                "view": "list",
+                "sort": 0,
+                "count": 0,
+                "user": { "$id": 1 },
+                "created": "2024-01-01T00:00:00Z",
+                "lastUpdate": "2024-01-01T00:00:00Z"
+            })
+        };
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/collections"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                json!({ "items": [collection(1, "Reading"), collection(2, "Work")] }),
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/collections/childrens"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(
+                    json!({ "items": [collection(3, "Rust"), collection(4, "rust")] }),
+                ),
+            )
+            .mount(&server)
+            .await;
+
+        let client = RaindropClient::with_token(server.uri(), "test-token").unwrap();
+        assert_eq!(client.find_collection(" reading ").await.unwrap().id, 1);
+        assert!(matches!(
+            client.find_collection("Missing").await,
+            Err(RaindropMcpError::NotFound(_))
+        ));
+        let ambiguous = client.find_collection("RUST").await.unwrap_err();
+        assert!(ambiguous.to_string().contains("IDs 3, 4"));
+    }
 }
@@ -0,0 +1,527 @@
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+use tracing::{debug, warn};
+
+use super::types::{Bookmark, Collection, TRASH_COLLECTION_ID};
+use crate::{
+    config::{default_data_dir, profile_path},
+    error::{RaindropMcpError, Result},
+};
+
+/// Maximum number of operations kept in the journal file.
+pub const MAX_JOURNAL_ENTRIES: usize = 500;
+
+/// Most items one entry keeps for undo; larger operations are recorded as irreversible.
+pub const MAX_ENTRY_SNAPSHOTS: usize = 1000;
+
+const JOURNAL_FILE_NAME: &str = "journal.json";
+
+/// State captured before a mutation, sufficient to reverse it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Snapshot {
+    /// Bookmark that existed before the write; undo restores these fields.
+    Bookmark { state: Box<Bookmark> },
+    /// Bookmark created by the operation; undo moves it to the trash.
+    CreatedBookmark { id: i64 },
+    /// Bookmark permanently removed by the operation; undo re-creates it.
+    RemovedBookmark { state: Box<Bookmark> },
+    /// Collection that existed before the write; undo restores its settings.
+    Collection { state: Box<Collection> },
+    /// Collection created by the operation; undo deletes it.
+    CreatedCollection { id: i64 },
+    /// Collection removed by the operation; undo re-creates it under a new ID.
+    RemovedCollection { state: Box<Collection> },
+    /// Part of the operation that cannot be reversed.
+    Irreversible { reason: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub id: u64,
+    pub timestamp: String,
+    pub operation: String,
+    pub description: String,
+    pub snapshots: Vec<Snapshot>,
+    #[serde(default)]
+    pub undone: bool,
+}
+
+impl JournalEntry {
+    pub fn is_reversible(&self) -> bool {
+        self.snapshots
+            .iter()
+            .any(|s| !matches!(s, Snapshot::Irreversible { .. }))
+    }
+
+    pub fn summary(&self) -> JournalEntrySummary {
+        JournalEntrySummary {
+            id: self.id,
+            timestamp: self.timestamp.clone(),
+            operation: self.operation.clone(),
+            description: self.description.clone(),
+            affected_items: self
+                .snapshots
+                .iter()
+                .filter(|s| !matches!(s, Snapshot::Irreversible { .. }))
+                .count(),
+            reversible: self.is_reversible(),
+            undone: self.undone,
+        }
+    }
+}
+
+/// Compact view of a journal entry, without the captured state.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntrySummary {
+    pub id: u64,
+    pub timestamp: String,
+    pub operation: String,
+    pub description: String,
+    pub affected_items: usize,
+    pub reversible: bool,
+    pub undone: bool,
+}
+
+/// Outcome of replaying the inverse of a journal entry.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoReport {
+    pub operation_id: u64,
+    pub operation: String,
+    pub restored: usize,
+    pub skipped: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// Persistent log of mutations performed through `RaindropClient`.
+#[derive(Debug)]
+pub struct Journal {
+    path: Option<PathBuf>,
+    entries: Mutex<Vec<JournalEntry>>,
+    /// Bumped whenever an entry is recorded or undone
+    revision: AtomicU64,
+}
+
+impl Journal {
+    /// Opens the journal at `RAINDROP_JOURNAL_PATH`, or in the user's data directory.
+    pub fn open_default() -> Result<Self> {
+        Self::open(default_path()?)
+    }
+
+    /// Opens the default journal for the default profile, and `journal-<profile>.json` beside it
+    /// otherwise. `RAINDROP_JOURNAL_PATH` moves every profile's journal, not just the default one.
+    pub fn open_for_profile(profile: &str) -> Result<Self> {
+        // Separate files, so undo never replays one account's changes with another's credentials
+        Self::open(profile_path(&default_path()?, profile))
+    }
+
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let entries = read_entries(&path)?;
+        debug!(
+            "Opened journal at {} with {} entries",
+            path.display(),
+            entries.len()
+        );
+
+        Ok(Self {
+            path: Some(path),
+            entries: Mutex::new(entries),
+            revision: AtomicU64::new(0),
+        })
+    }
+
+    /// Journal that is never written to disk, lasting as long as the value.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            entries: Mutex::new(vec![]),
+            revision: AtomicU64::new(0),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Appends a new entry and persists the journal, returning the entry ID.
+    ///
+    /// Bookmark snapshots keep only what undo restores, and an entry with more than
+    /// `MAX_ENTRY_SNAPSHOTS` items is recorded as irreversible instead.
+    pub fn record(
+        &self,
+        operation: &str,
+        description: String,
+        snapshots: Vec<Snapshot>,
+    ) -> Result<u64> {
+        let snapshots = if snapshots.len() > MAX_ENTRY_SNAPSHOTS {
+            vec![Snapshot::Irreversible {
+                reason: format!(
+                    "{} items exceed the undo limit of {MAX_ENTRY_SNAPSHOTS}",
+                    snapshots.len()
+                ),
+            }]
+        } else {
+            snapshots.into_iter().map(Snapshot::compact).collect()
+        };
+        self.update(|entries| {
+            let id = entries.last().map(|e| e.id + 1).unwrap_or(1);
+            entries.push(JournalEntry {
+                id,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                operation: operation.to_string(),
+                description,
+                snapshots,
+                undone: false,
+            });
+            if entries.len() > MAX_JOURNAL_ENTRIES {
+                let excess = entries.len() - MAX_JOURNAL_ENTRIES;
+                entries.drain(..excess);
+            }
+            Ok(id)
+        })
+    }
+
+    /// Most recent entries first.
+    pub fn list(&self, limit: Option<usize>) -> Vec<JournalEntrySummary> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .iter()
+            .rev()
+            .take(limit.unwrap_or(usize::MAX))
+            .map(JournalEntry::summary)
+            .collect()
+    }
+
+    pub fn get(&self, id: u64) -> Option<JournalEntry> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.iter().find(|e| e.id == id).cloned()
+    }
+
+    /// Collection a bookmark was last seen in outside the trash, according to the journal.
+    pub fn last_known_collection(&self, bookmark_id: i64) -> Option<i64> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .iter()
+            .rev()
+            .flat_map(|e| e.snapshots.iter().rev())
+            .find_map(|s| match s {
+                Snapshot::Bookmark { state } | Snapshot::RemovedBookmark { state }
+                    if state.id == bookmark_id && state.collection.id != TRASH_COLLECTION_ID =>
+                {
+                    Some(state.collection.id)
+                }
+                _ => None,
+            })
+    }
+
+    /// Latest entry that has not been undone yet and can be reversed.
+    pub fn last_undoable(&self) -> Option<JournalEntry> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .iter()
+            .rev()
+            .find(|e| !e.undone && e.is_reversible())
+            .cloned()
+    }
+
+    pub fn mark_undone(&self, id: u64) -> Result<()> {
+        self.update(|entries| {
+            find_entry(entries, id)?.undone = true;
+            Ok(())
+        })
+    }
+
+    /// Leaves only `snapshots` to undo in entry `id`, after the rest of it was undone.
+    pub fn replace_snapshots(&self, id: u64, snapshots: Vec<Snapshot>) -> Result<()> {
+        self.update(|entries| {
+            find_entry(entries, id)?.snapshots = snapshots;
+            Ok(())
+        })
+    }
+
+    /// Changes whenever a mutation is recorded or undone during this process.
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+
+    // Applies `change` to the entries and persists them. Other processes may use the same
+    // file, so it is re-read under a lock first rather than overwritten from memory.
+    fn update<T>(&self, change: impl FnOnce(&mut Vec<JournalEntry>) -> Result<T>) -> Result<T> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let result = match &self.path {
+            Some(path) => {
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    fs::create_dir_all(parent)?;
+                }
+                let lock = fs::OpenOptions::new()
+                    .create(true)
+                    .truncate(false)
+                    .write(true)
+                    .open(path.with_extension("json.lock"))?;
+                lock.lock_exclusive()?;
+                *entries = read_entries(path)?;
+                let result = change(&mut entries)?;
+                persist(path, &entries)?;
+                result
+            }
+            None => change(&mut entries)?,
+        };
+        self.revision.fetch_add(1, Ordering::Relaxed);
+        Ok(result)
+    }
+}
+
+impl Snapshot {
+    // Drops bookmark fields undo never restores, such as highlights and the page cache
+    fn compact(self) -> Self {
+        let strip = |mut state: Box<Bookmark>| {
+            state.highlights = None;
+            state.media = None;
+            state.cache = None;
+            state
+        };
+        match self {
+            Self::Bookmark { state } => Self::Bookmark {
+                state: strip(state),
+            },
+            Self::RemovedBookmark { state } => Self::RemovedBookmark {
+                state: strip(state),
+            },
+            other => other,
+        }
+    }
+}
+
+fn find_entry(entries: &mut [JournalEntry], id: u64) -> Result<&mut JournalEntry> {
+    entries
+        .iter_mut()
+        .find(|e| e.id == id)
+        .ok_or_else(|| RaindropMcpError::NotFound(format!("Journal entry {id}")))
+}
+
+fn read_entries(path: &Path) -> Result<Vec<JournalEntry>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let text = fs::read_to_string(path)?;
+    if text.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    Ok(serde_json::from_str(&text)?)
+}
+
+fn persist(path: &Path, entries: &[JournalEntry]) -> Result<()> {
+    // Write to a sibling file first so a crash never leaves a truncated journal
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec_pretty(entries)?)?;
+    fs::rename(&tmp, path).inspect_err(|e| {
+        warn!("Failed to replace journal {}: {}", path.display(), e);
+    })?;
+    Ok(())
+}
+
+// `RAINDROP_JOURNAL_PATH`, or `journal.json` in the user's data directory
+fn default_path() -> Result<PathBuf> {
+    match env::var("RAINDROP_JOURNAL_PATH") {
+        Ok(path) if !path.is_empty() => Ok(PathBuf::from(path)),
+        _ => Ok(default_data_dir()
+            .ok_or_else(|| {
+                RaindropMcpError::EnvironmentVariable(
+                    "Cannot determine a data directory for the journal; set RAINDROP_JOURNAL_PATH"
+                        .to_string(),
+                )
+            })?
+            .join(JOURNAL_FILE_NAME)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_PROFILE;
+    use pretty_assertions::assert_eq;
+
+    fn temp_journal_path(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!(
+            "raindrop-journal-test-{}-{name}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join(JOURNAL_FILE_NAME)
+    }
+
+    #[test]
+    fn test_profile_paths_follow_the_default_journal() {
+        let default = Path::new("/data/raindrop-mcp-server/journal.json");
+        assert_eq!(profile_path(default, DEFAULT_PROFILE), default);
+        assert_eq!(
+            profile_path(default, "work"),
+            Path::new("/data/raindrop-mcp-server/journal-work.json")
+        );
+        assert_eq!(
+            profile_path(Path::new("/tmp/undo"), "work"),
+            Path::new("/tmp/undo-work")
+        );
+    }
+
+    #[test]
+    fn test_record_and_reload() {
+        let path = temp_journal_path("reload");
+        let journal = Journal::open(&path).unwrap();
+        let id = journal
+            .record(
+                "create_bookmark",
+                "Create bookmark".to_string(),
+                vec![Snapshot::CreatedBookmark { id: 42 }],
+            )
+            .unwrap();
+        assert_eq!(id, 1);
+
+        let reopened = Journal::open(&path).unwrap();
+        let entry = reopened.get(1).unwrap();
+        assert_eq!(entry.operation, "create_bookmark");
+        assert!(matches!(
+            entry.snapshots[0],
+            Snapshot::CreatedBookmark { id: 42 }
+        ));
+    }
+
+    #[test]
+    fn test_journals_sharing_a_file_keep_each_others_entries() {
+        let path = temp_journal_path("shared");
+        // Two processes that opened the journal before either recorded anything
+        let first = Journal::open(&path).unwrap();
+        let second = Journal::open(&path).unwrap();
+        let created = |id| vec![Snapshot::CreatedBookmark { id }];
+
+        assert_eq!(first.record("a", "a".to_string(), created(1)).unwrap(), 1);
+        assert_eq!(second.record("b", "b".to_string(), created(2)).unwrap(), 2);
+        first.mark_undone(2).unwrap();
+
+        let reopened = Journal::open(&path).unwrap();
+        let operations: Vec<(String, bool)> = reopened
+            .list(None)
+            .into_iter()
+            .map(|e| (e.operation, e.undone))
+            .collect();
+        assert_eq!(
+            operations,
+            vec![("b".to_string(), true), ("a".to_string(), false)]
+        );
+    }
+
+    #[test]
+    fn test_large_entries_are_not_kept_for_undo() {
+        let journal = Journal::in_memory();
+        let snapshots = (0..=MAX_ENTRY_SNAPSHOTS as i64)
+            .map(|id| Snapshot::CreatedBookmark { id })
+            .collect();
+        let id = journal
+            .record("create_bookmarks", "many".to_string(), snapshots)
+            .unwrap();
+        let entry = journal.get(id).unwrap();
+        assert_eq!(entry.snapshots.len(), 1);
+        assert!(!entry.is_reversible());
+    }
+
+    #[test]
+    fn test_last_undoable_skips_undone_and_irreversible() {
+        let path = temp_journal_path("undoable");
+        let journal = Journal::open(&path).unwrap();
+        journal
+            .record(
+                "create_collection",
+                "first".to_string(),
+                vec![Snapshot::CreatedCollection { id: 1 }],
+            )
+            .unwrap();
+        let second = journal
+            .record(
+                "create_collection",
+                "second".to_string(),
+                vec![Snapshot::CreatedCollection { id: 2 }],
+            )
+            .unwrap();
+        journal
+            .record(
+                "share_collection",
+                "third".to_string(),
+                vec![Snapshot::Irreversible {
+                    reason: "sharing".to_string(),
+                }],
+            )
+            .unwrap();
+
+        assert_eq!(journal.last_undoable().unwrap().id, second);
+        assert_eq!(journal.revision(), 3);
+        journal.mark_undone(second).unwrap();
+        assert_eq!(journal.last_undoable().unwrap().id, 1);
+        assert_eq!(journal.revision(), 4);
+
+        let listed = journal.list(Some(2));
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].id, 3);
+        assert!(!listed[0].reversible);
+        assert!(listed[1].undone);
+    }
+
+    #[test]
+    fn test_last_known_collection_ignores_trash() {
+        let bookmark = |collection: i64| -> Box<Bookmark> {
+            Box::new(
+                serde_json::from_value(serde_json::json!({
+                    "_id": 7,
+                    "title": "Example",
+                    "type": "link",
+                    "tags": [],
+                    "link": "https://example.com",
+                    "domain": "example.com",
+                    "created": "2024-01-01T00:00:00Z",
+                    "lastUpdate": "2024-01-01T00:00:00Z",
+                    "user": { "$id": 1 },
+                    "collection": { "$id": collection },
+                    "important": false,
+                    "broken": false
+                }))
+                .unwrap(),
+            )
+        };
+
+        let path = temp_journal_path("known-collection");
+        let journal = Journal::open(&path).unwrap();
+        assert_eq!(journal.last_known_collection(7), None);
+
+        journal
+            .record(
+                "delete_bookmark",
+                "trash".to_string(),
+                vec![Snapshot::Bookmark {
+                    state: bookmark(10),
+                }],
+            )
+            .unwrap();
+        journal
+            .record(
+                "update_bookmark",
+                "edit in trash".to_string(),
+                vec![Snapshot::Bookmark {
+                    state: bookmark(TRASH_COLLECTION_ID),
+                }],
+            )
+            .unwrap();
+
+        assert_eq!(journal.last_known_collection(7), Some(10));
+    }
+}
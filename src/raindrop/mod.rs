@@ -1,5 +1,10 @@
+pub mod analytics;
 pub mod client;
+pub mod journal;
+pub mod oauth;
+pub mod tags;
 pub mod types;
+pub mod urls;
 
 pub use client::RaindropClient;
 pub use types::*;
@@ -0,0 +1,601 @@
+use chrono::{DateTime, Duration, Utc};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    sync::Mutex,
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    config::{OAuthSettings, default_data_dir},
+    error::{RaindropMcpError, Result},
+};
+
+pub const AUTHORIZE_URL: &str = "https://raindrop.io/oauth/authorize";
+pub const TOKEN_URL: &str = "https://raindrop.io/oauth/access_token";
+
+/// Redirect URI used unless a profile's `oauth.redirect_uri` sets one; register it with the app.
+pub const DEFAULT_REDIRECT_URI: &str = "http://localhost:8976/callback";
+
+const TOKEN_DIR: &str = "oauth";
+
+// Access tokens this close to expiry are refreshed before use
+const EXPIRY_MARGIN_SECS: i64 = 60;
+
+// Largest redirect request read from the browser
+const MAX_CALLBACK_BYTES: usize = 16 * 1024;
+
+// How long the handshake waits for the browser's redirect
+const AUTHORIZE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Tokens persisted between runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredTokens {
+    pub access_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl StoredTokens {
+    fn expires_soon(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at
+            .is_some_and(|at| at - Duration::seconds(EXPIRY_MARGIN_SECS) <= now)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<i64>,
+}
+
+/// Token file readable only by the current user.
+#[derive(Debug, Clone)]
+pub struct TokenStore {
+    path: PathBuf,
+}
+
+impl TokenStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `oauth.token_file`, or `oauth/<profile>.json` in the user's data directory.
+    pub fn for_profile(profile: &str, settings: &OAuthSettings) -> Result<Self> {
+        if let Some(path) = &settings.token_file {
+            return Ok(Self::new(path));
+        }
+        let dir = default_data_dir().ok_or_else(|| {
+            RaindropMcpError::Config(
+                "Cannot determine a data directory for OAuth tokens; set oauth.token_file"
+                    .to_string(),
+            )
+        })?;
+        Ok(Self::new(
+            dir.join(TOKEN_DIR).join(format!("{profile}.json")),
+        ))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn load(&self) -> Result<Option<StoredTokens>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(
+            &self.path,
+        )?)?))
+    }
+
+    pub fn save(&self, tokens: &StoredTokens) -> Result<()> {
+        // Only a directory created here is restricted; an existing one (such as
+        // $HOME for a configured token_file) keeps its permissions
+        if let Some(parent) = self
+            .path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty() && !p.exists())
+        {
+            let mut builder = fs::DirBuilder::new();
+            builder.recursive(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::DirBuilderExt;
+                builder.mode(0o700);
+            }
+            builder.create(parent)?;
+        }
+        // Write to a sibling file first so a crash never leaves truncated tokens.
+        // A stale one is removed so the file is always created with the mode below.
+        let tmp = self.path.with_extension("json.tmp");
+        match fs::remove_file(&tmp) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options
+            .open(&tmp)?
+            .write_all(&serde_json::to_vec_pretty(tokens)?)?;
+        fs::rename(&tmp, &self.path)?;
+        debug!("Saved OAuth tokens to {}", self.path.display());
+        Ok(())
+    }
+}
+
+/// Raindrop OAuth2 endpoints for one registered app.
+#[derive(Debug, Clone)]
+pub struct OAuthClient {
+    http: Client,
+    client_id: String,
+    client_secret: String,
+    redirect_uri: String,
+    authorize_url: String,
+    token_url: String,
+}
+
+impl OAuthClient {
+    pub fn new(settings: &OAuthSettings) -> Result<Self> {
+        Ok(Self {
+            http: Client::builder()
+                .build()
+                .map_err(RaindropMcpError::HttpRequest)?,
+            client_id: settings.client_id.clone(),
+            client_secret: settings.client_secret()?,
+            redirect_uri: settings
+                .redirect_uri
+                .clone()
+                .unwrap_or_else(|| DEFAULT_REDIRECT_URI.to_string()),
+            authorize_url: settings
+                .authorize_url
+                .clone()
+                .unwrap_or_else(|| AUTHORIZE_URL.to_string()),
+            token_url: settings
+                .token_url
+                .clone()
+                .unwrap_or_else(|| TOKEN_URL.to_string()),
+        })
+    }
+
+    pub fn redirect_uri(&self) -> &str {
+        &self.redirect_uri
+    }
+
+    /// Page the user opens to grant access; `state` comes back on the redirect.
+    pub fn authorization_url(&self, state: &str) -> Result<String> {
+        let mut url = url::Url::parse(&self.authorize_url).map_err(|e| {
+            RaindropMcpError::Config(format!(
+                "Invalid authorize URL '{}': {e}",
+                self.authorize_url
+            ))
+        })?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", &self.redirect_uri)
+            .append_pair("state", state);
+        Ok(url.into())
+    }
+
+    pub async fn exchange_code(&self, code: &str) -> Result<StoredTokens> {
+        self.request_tokens(json!({
+            "grant_type": "authorization_code",
+            "code": code,
+            "client_id": self.client_id,
+            "client_secret": self.client_secret,
+            "redirect_uri": self.redirect_uri,
+        }))
+        .await
+    }
+
+    /// New tokens for `refresh_token`, which is kept if the response carries no new one.
+    pub async fn refresh(&self, refresh_token: &str) -> Result<StoredTokens> {
+        let mut tokens = self
+            .request_tokens(json!({
+                "grant_type": "refresh_token",
+                "refresh_token": refresh_token,
+                "client_id": self.client_id,
+                "client_secret": self.client_secret,
+            }))
+            .await?;
+        tokens
+            .refresh_token
+            .get_or_insert_with(|| refresh_token.to_string());
+        Ok(tokens)
+    }
+
+    async fn request_tokens(&self, body: serde_json::Value) -> Result<StoredTokens> {
+        let response = self.http.post(&self.token_url).json(&body).send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if status != StatusCode::OK {
+            return Err(RaindropMcpError::Unauthorized(format!(
+                "Token request failed ({status}): {text}"
+            )));
+        }
+        let tokens: TokenResponse = serde_json::from_str(&text)?;
+        Ok(StoredTokens {
+            access_token: tokens.access_token,
+            refresh_token: tokens.refresh_token,
+            expires_at: tokens
+                .expires_in
+                .map(|secs| Utc::now() + Duration::seconds(secs)),
+        })
+    }
+
+    /// Runs the authorization-code handshake: hands the authorization URL to `show_url`,
+    /// waits on `listener` for the browser's redirect and exchanges the code for tokens.
+    pub async fn authorize(
+        &self,
+        listener: TcpListener,
+        show_url: impl FnOnce(&str),
+    ) -> Result<StoredTokens> {
+        let state = random_state()?;
+        show_url(&self.authorization_url(&state)?);
+        tokio::time::timeout(AUTHORIZE_TIMEOUT, self.await_redirect(listener, &state))
+            .await
+            .map_err(|_| {
+                RaindropMcpError::Unauthorized(format!(
+                    "No authorization redirect arrived within {} seconds",
+                    AUTHORIZE_TIMEOUT.as_secs()
+                ))
+            })?
+    }
+
+    // Answers requests on `listener` until one brings back `state`
+    async fn await_redirect(&self, listener: TcpListener, state: &str) -> Result<StoredTokens> {
+        let callback_path = url::Url::parse(&self.redirect_uri)
+            .map(|url| url.path().to_string())
+            .unwrap_or_else(|_| "/".to_string());
+
+        loop {
+            let (mut stream, peer) = listener.accept().await?;
+            let mut buffer = vec![0; MAX_CALLBACK_BYTES];
+            let mut read = 0;
+            // Only the request line and headers are needed
+            while read < buffer.len() && !buffer[..read].windows(4).any(|w| w == b"\r\n\r\n") {
+                match stream.read(&mut buffer[read..]).await? {
+                    0 => break,
+                    n => read += n,
+                }
+            }
+            let request = String::from_utf8_lossy(&buffer[..read]);
+            let target = request
+                .lines()
+                .next()
+                .and_then(|line| line.split_whitespace().nth(1))
+                .unwrap_or("/");
+            let url = url::Url::parse(&format!("http://localhost{target}"))
+                .map_err(|e| RaindropMcpError::InvalidParameter(e.to_string()))?;
+            if url.path() != callback_path {
+                // Browsers also ask for /favicon.ico and the like
+                debug!("Ignoring request for {} from {}", url.path(), peer);
+                respond(&mut stream, "404 Not Found", "Not found").await;
+                continue;
+            }
+
+            let param = |name: &str| {
+                url.query_pairs()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.into_owned())
+            };
+            // Another page may hit the callback, so only our own redirect ends the wait
+            if param("state").as_deref() != Some(state) {
+                warn!("Ignoring redirect with a mismatched state from {}", peer);
+                respond(
+                    &mut stream,
+                    "400 Bad Request",
+                    "Authorization state did not match",
+                )
+                .await;
+                continue;
+            }
+            let result = match (param("code"), param("error")) {
+                (_, Some(error)) => Err(RaindropMcpError::Unauthorized(format!(
+                    "Authorization was denied: {error}"
+                ))),
+                (Some(code), None) => self.exchange_code(&code).await,
+                (None, None) => Err(RaindropMcpError::InvalidParameter(
+                    "Redirect carried no authorization code".to_string(),
+                )),
+            };
+            match &result {
+                Ok(_) => {
+                    respond(
+                        &mut stream,
+                        "200 OK",
+                        "Raindrop authorization complete. You can close this window.",
+                    )
+                    .await
+                }
+                Err(e) => respond(&mut stream, "400 Bad Request", &e.to_string()).await,
+            }
+            return result;
+        }
+    }
+}
+
+/// Listens on the host and port of `redirect_uri`, which must be a loopback `http` URL.
+pub async fn bind_redirect(redirect_uri: &str) -> Result<TcpListener> {
+    let url = url::Url::parse(redirect_uri).map_err(|e| {
+        RaindropMcpError::Config(format!("Invalid redirect URI '{redirect_uri}': {e}"))
+    })?;
+    let host = url.host_str().unwrap_or_default();
+    if url.scheme() != "http" || !matches!(host, "localhost" | "127.0.0.1" | "[::1]") {
+        return Err(RaindropMcpError::Config(format!(
+            "Redirect URI '{redirect_uri}' must be http://localhost, http://127.0.0.1 or http://[::1]"
+        )));
+    }
+    let port = url.port().unwrap_or(80);
+    Ok(TcpListener::bind((host.trim_matches(['[', ']']), port)).await?)
+}
+
+/// Tokens for an OAuth profile, refreshed and re-saved as they expire.
+#[derive(Debug)]
+pub struct OAuthSession {
+    client: OAuthClient,
+    store: TokenStore,
+    tokens: Mutex<StoredTokens>,
+}
+
+impl OAuthSession {
+    pub fn new(client: OAuthClient, store: TokenStore, tokens: StoredTokens) -> Self {
+        Self {
+            client,
+            store,
+            tokens: Mutex::new(tokens),
+        }
+    }
+
+    /// Session from the tokens saved by the `auth` command for `profile`.
+    pub fn open(profile: &str, settings: &OAuthSettings) -> Result<Self> {
+        let store = TokenStore::for_profile(profile, settings)?;
+        let tokens = store.load()?.ok_or_else(|| {
+            RaindropMcpError::Unauthorized(format!(
+                "No OAuth tokens for profile '{profile}'; run `raindrop-mcp-server auth --profile {profile}`"
+            ))
+        })?;
+        info!("Using OAuth tokens from {}", store.path().display());
+        Ok(Self::new(OAuthClient::new(settings)?, store, tokens))
+    }
+
+    /// Current access token, refreshed first if it is about to expire.
+    pub async fn access_token(&self) -> Result<String> {
+        let mut tokens = self.tokens.lock().await;
+        if tokens.expires_soon(Utc::now()) && tokens.refresh_token.is_some() {
+            debug!("OAuth access token expires soon; refreshing");
+            self.refresh_locked(&mut tokens).await?;
+        }
+        Ok(tokens.access_token.clone())
+    }
+
+    /// Refreshes after the API rejected `rejected`, unless another request already did.
+    pub async fn refresh_rejected(&self, rejected: &str) -> Result<String> {
+        let mut tokens = self.tokens.lock().await;
+        if tokens.access_token == rejected {
+            info!("Access token was rejected; refreshing");
+            self.refresh_locked(&mut tokens).await?;
+        }
+        Ok(tokens.access_token.clone())
+    }
+
+    async fn refresh_locked(&self, tokens: &mut StoredTokens) -> Result<()> {
+        let refresh_token = tokens.refresh_token.clone().ok_or_else(|| {
+            RaindropMcpError::Unauthorized(
+                "Access token expired and there is no refresh token; run the auth command again"
+                    .to_string(),
+            )
+        })?;
+        *tokens = self.client.refresh(&refresh_token).await?;
+        if let Err(e) = self.store.save(tokens) {
+            warn!(
+                "Refreshed tokens could not be saved to {}: {}",
+                self.store.path().display(),
+                e
+            );
+        }
+        Ok(())
+    }
+}
+
+// Unpredictable value tying the redirect to this handshake
+fn random_state() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(std::io::Error::from)?;
+    Ok(format!("{:032x}", u128::from_be_bytes(bytes)))
+}
+
+async fn respond(stream: &mut tokio::net::TcpStream, status: &str, message: &str) {
+    let message = message.replace('&', "&amp;").replace('<', "&lt;");
+    let body = format!("<!doctype html><title>Raindrop</title><p>{message}</p>");
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    if let Err(e) = stream.write_all(response.as_bytes()).await {
+        debug!("Failed to answer the browser: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_partial_json, method, path},
+    };
+
+    fn settings(server: &MockServer, token_file: PathBuf) -> OAuthSettings {
+        OAuthSettings {
+            client_id: "app".to_string(),
+            client_secret: Some("secret".to_string()),
+            client_secret_env: None,
+            redirect_uri: Some("http://127.0.0.1:0/callback".to_string()),
+            token_file: Some(token_file),
+            authorize_url: Some(format!("{}/oauth/authorize", server.uri())),
+            token_url: Some(format!("{}/oauth/access_token", server.uri())),
+        }
+    }
+
+    fn temp_token_file(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("raindrop-oauth-test-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("tokens.json")
+    }
+
+    #[tokio::test]
+    async fn test_redirect_handshake_exchanges_code() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/access_token"))
+            .and(body_partial_json(json!({
+                "grant_type": "authorization_code",
+                "code": "the-code",
+                "client_secret": "secret",
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "access-1",
+                "refresh_token": "refresh-1",
+                "expires_in": 1209599,
+                "token_type": "Bearer",
+            })))
+            .mount(&server)
+            .await;
+
+        let client = OAuthClient::new(&settings(&server, temp_token_file("handshake"))).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let (url_tx, url_rx) = tokio::sync::oneshot::channel::<String>();
+        let browser = tokio::spawn(async move {
+            let url = url::Url::parse(&url_rx.await.unwrap()).unwrap();
+            let state = url
+                .query_pairs()
+                .find(|(k, _)| k == "state")
+                .unwrap()
+                .1
+                .into_owned();
+            let base = format!("http://127.0.0.1:{port}");
+            let favicon = reqwest::get(format!("{base}/favicon.ico")).await.unwrap();
+            assert_eq!(favicon.status(), StatusCode::NOT_FOUND);
+            // A forged redirect is turned away without ending the handshake
+            let forged = reqwest::get(format!("{base}/callback?code=evil&state=wrong"))
+                .await
+                .unwrap();
+            assert_eq!(forged.status(), StatusCode::BAD_REQUEST);
+            reqwest::get(format!("{base}/callback?code=the-code&state={state}"))
+                .await
+                .unwrap()
+                .status()
+        });
+
+        let tokens = client
+            .authorize(listener, |url| {
+                assert!(url.contains("client_id=app"));
+                url_tx.send(url.to_string()).unwrap();
+            })
+            .await
+            .unwrap();
+        assert_eq!(tokens.access_token, "access-1");
+        assert_eq!(tokens.refresh_token.as_deref(), Some("refresh-1"));
+        assert!(tokens.expires_at.is_some());
+        assert_eq!(browser.await.unwrap(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_saves_tokens() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/access_token"))
+            .and(body_partial_json(json!({
+                "grant_type": "refresh_token",
+                "refresh_token": "refresh-1",
+            })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({ "access_token": "access-2" })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let token_file = temp_token_file("refresh");
+        let settings = settings(&server, token_file.clone());
+        let store = TokenStore::for_profile("test", &settings).unwrap();
+        store
+            .save(&StoredTokens {
+                access_token: "access-1".to_string(),
+                refresh_token: Some("refresh-1".to_string()),
+                expires_at: None,
+            })
+            .unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&token_file).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let session = OAuthSession::open("test", &settings).unwrap();
+        assert_eq!(session.access_token().await.unwrap(), "access-1");
+        assert_eq!(
+            session.refresh_rejected("access-1").await.unwrap(),
+            "access-2"
+        );
+        // A second request rejected with the old token does not refresh again
+        assert_eq!(
+            session.refresh_rejected("access-1").await.unwrap(),
+            "access-2"
+        );
+
+        let saved = store.load().unwrap().unwrap();
+        assert_eq!(saved.access_token, "access-2");
+        assert_eq!(saved.refresh_token.as_deref(), Some("refresh-1"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_only_restricts_created_directories() {
+        use std::os::unix::fs::PermissionsExt;
+        let tokens = StoredTokens {
+            access_token: "access-1".to_string(),
+            refresh_token: None,
+            expires_at: None,
+        };
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        let created = temp_token_file("created-dir");
+        TokenStore::new(&created).save(&tokens).unwrap();
+        assert_eq!(mode(created.parent().unwrap()), 0o700);
+        assert_eq!(mode(&created), 0o600);
+
+        let existing = temp_token_file("existing-dir");
+        let dir = existing.parent().unwrap();
+        fs::create_dir_all(dir).unwrap();
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o755)).unwrap();
+        TokenStore::new(&existing).save(&tokens).unwrap();
+        assert_eq!(mode(dir), 0o755);
+        assert_eq!(mode(&existing), 0o600);
+    }
+}
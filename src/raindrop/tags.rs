@@ -0,0 +1,529 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    path::PathBuf,
+    sync::Mutex,
+};
+use tracing::debug;
+
+use super::types::Tag;
+use crate::{
+    config::{default_data_dir, profile_path},
+    error::{RaindropMcpError, Result},
+};
+
+const ALIASES_FILE_NAME: &str = "tag-aliases.json";
+
+// Characters treated as interchangeable word separators inside a tag
+const WORD_SEPARATORS: &[char] = &[' ', '-', '_', '.'];
+
+/// Separator used by hierarchical tags such as `lang/rust`.
+pub const DEFAULT_HIERARCHY_SEPARATOR: char = '/';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SuggestionReason {
+    /// Tags differ only in letter case
+    Case,
+    /// Tags differ in word separators, e.g. `web-dev` and `web_dev`
+    Separator,
+    /// Tags differ in singular/plural form
+    Plural,
+    /// Tags are within a small edit distance of each other
+    Spelling,
+    /// Tag is a user-defined alias of another tag
+    Alias,
+}
+
+/// Proposal to merge `merge` into `into`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagSuggestion {
+    pub into: String,
+    pub merge: Vec<String>,
+    pub reason: SuggestionReason,
+    /// Bookmarks carrying one of the tags to merge
+    pub affected_bookmarks: i64,
+}
+
+/// Finds tags that probably mean the same thing, using only local, deterministic rules.
+///
+/// `max_distance` bounds the Levenshtein distance for spelling suggestions; by default
+/// it is 1 for short tags and 2 for tags of eight characters or more.
+pub fn suggest_merges(
+    tags: &[Tag],
+    aliases: &BTreeMap<String, String>,
+    max_distance: Option<usize>,
+) -> Vec<TagSuggestion> {
+    let counts: HashMap<&str, i64> = tags
+        .iter()
+        .map(|t| (t.id.as_str(), t.count as i64))
+        .collect();
+    let mut suggestions = vec![];
+    let mut merged: Vec<&str> = vec![];
+
+    // Aliases first: they express the user's intent
+    let mut by_target: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for tag in tags {
+        if let Some(target) = aliases.get(&tag.id.to_lowercase()) {
+            if !target.eq_ignore_ascii_case(&tag.id) {
+                by_target.entry(target.as_str()).or_default().push(&tag.id);
+            }
+        }
+    }
+    for (target, sources) in by_target {
+        merged.extend(&sources);
+        suggestions.push(suggestion(
+            target,
+            sources,
+            SuggestionReason::Alias,
+            &counts,
+        ));
+    }
+
+    // Case, separator and plural variants share a folded key
+    let mut groups: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for tag in tags.iter().filter(|t| !merged.contains(&t.id.as_str())) {
+        groups.entry(fold_key(&tag.id)).or_default().push(&tag.id);
+    }
+    let mut representatives = vec![];
+    for members in groups.into_values() {
+        let into = preferred(&members, &counts);
+        representatives.push(into);
+        if members.len() < 2 {
+            continue;
+        }
+        let reason = if members
+            .iter()
+            .all(|m| m.to_lowercase() == into.to_lowercase())
+        {
+            SuggestionReason::Case
+        } else if members
+            .iter()
+            .all(|m| separator_key(m) == separator_key(into))
+        {
+            SuggestionReason::Separator
+        } else {
+            SuggestionReason::Plural
+        };
+        let sources = members.into_iter().filter(|m| *m != into).collect();
+        suggestions.push(suggestion(into, sources, reason, &counts));
+    }
+
+    // Near-identical spellings among the remaining distinct tags
+    representatives.sort_unstable();
+    for (i, a) in representatives.iter().enumerate() {
+        for b in &representatives[i + 1..] {
+            let (la, lb) = (a.chars().count(), b.chars().count());
+            let limit = max_distance.unwrap_or(if la.min(lb) >= 8 { 2 } else { 1 });
+            // Short tags and versioned tags (python2/python3) are too easy to confuse
+            if la.min(lb) < 4 || la.abs_diff(lb) > limit || (has_digit(a) && has_digit(b)) {
+                continue;
+            }
+            if levenshtein(&a.to_lowercase(), &b.to_lowercase()) <= limit {
+                let into = preferred(&[a, b], &counts);
+                let source = if into == *a { *b } else { *a };
+                suggestions.push(suggestion(
+                    into,
+                    vec![source],
+                    SuggestionReason::Spelling,
+                    &counts,
+                ));
+            }
+        }
+    }
+
+    suggestions
+}
+
+/// Edit distance between two strings, counted in characters.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+// Lowercase, separator-free, with each word reduced to a singular form
+fn fold_key(tag: &str) -> String {
+    tag.to_lowercase()
+        .split(|c: char| WORD_SEPARATORS.contains(&c))
+        .map(|word| {
+            word.split(DEFAULT_HIERARCHY_SEPARATOR)
+                .map(singular)
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .collect()
+}
+
+fn separator_key(tag: &str) -> String {
+    tag.to_lowercase()
+        .chars()
+        .filter(|c| !WORD_SEPARATORS.contains(c))
+        .collect()
+}
+
+fn singular(word: &str) -> String {
+    if word.len() > 4 && word.ends_with("ies") {
+        format!("{}y", &word[..word.len() - 3])
+    } else if word.ends_with("sses")
+        || ["xes", "zes", "ches", "shes"]
+            .iter()
+            .any(|s| word.ends_with(s))
+    {
+        word[..word.len() - 2].to_string()
+    } else if word.len() > 3 && word.ends_with('s') && !word.ends_with("ss") {
+        word[..word.len() - 1].to_string()
+    } else {
+        word.to_string()
+    }
+}
+
+fn has_digit(tag: &str) -> bool {
+    tag.chars().any(|c| c.is_ascii_digit())
+}
+
+// Most used tag wins; ties go to the lexicographically smallest name
+fn preferred<'a>(tags: &[&'a str], counts: &HashMap<&str, i64>) -> &'a str {
+    tags.iter()
+        .copied()
+        .max_by(|a, b| {
+            let count = |t: &str| counts.get(t).copied().unwrap_or_default();
+            count(a).cmp(&count(b)).then_with(|| b.cmp(a))
+        })
+        .unwrap_or_default()
+}
+
+fn suggestion(
+    into: &str,
+    mut merge: Vec<&str>,
+    reason: SuggestionReason,
+    counts: &HashMap<&str, i64>,
+) -> TagSuggestion {
+    merge.sort_unstable();
+    TagSuggestion {
+        into: into.to_string(),
+        affected_bookmarks: merge
+            .iter()
+            .map(|t| counts.get(t).copied().unwrap_or_default())
+            .sum(),
+        merge: merge.into_iter().map(str::to_string).collect(),
+        reason,
+    }
+}
+
+/// Node of a hierarchical tag tree, e.g. `rust` under `lang` for `lang/rust`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagTreeNode {
+    pub name: String,
+    /// Full tag for this node
+    pub path: String,
+    /// Bookmarks tagged with exactly this path
+    pub count: i64,
+    /// Bookmarks tagged with this path or any descendant
+    pub total: i64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TagTreeNode>,
+}
+
+/// Arranges tags into trees by splitting them on `separator`, sorted by name.
+pub fn tag_tree(tags: &[Tag], separator: char) -> Vec<TagTreeNode> {
+    #[derive(Default)]
+    struct Builder {
+        count: i64,
+        children: BTreeMap<String, Builder>,
+    }
+
+    fn build(name: String, path: String, builder: Builder, separator: char) -> TagTreeNode {
+        let children: Vec<TagTreeNode> = builder
+            .children
+            .into_iter()
+            .map(|(child, b)| {
+                let child_path = format!("{path}{separator}{child}");
+                build(child, child_path, b, separator)
+            })
+            .collect();
+        TagTreeNode {
+            total: builder.count + children.iter().map(|c| c.total).sum::<i64>(),
+            name,
+            path,
+            count: builder.count,
+            children,
+        }
+    }
+
+    let mut roots: BTreeMap<String, Builder> = BTreeMap::new();
+    for tag in tags {
+        let mut segments = tag
+            .id
+            .split(separator)
+            .map(str::trim)
+            .filter(|s| !s.is_empty());
+        let Some(first) = segments.next() else {
+            continue;
+        };
+        let mut node = roots.entry(first.to_string()).or_default();
+        for segment in segments {
+            node = node.children.entry(segment.to_string()).or_default();
+        }
+        node.count += tag.count as i64;
+    }
+
+    roots
+        .into_iter()
+        .map(|(name, builder)| build(name.clone(), name, builder, separator))
+        .collect()
+}
+
+/// Indented text rendering of a tag tree, one `name (total)` line per node.
+pub fn render_tag_tree(nodes: &[TagTreeNode]) -> String {
+    fn render(node: &TagTreeNode, depth: usize, out: &mut String) {
+        out.push_str(&format!(
+            "{}{} ({})\n",
+            "  ".repeat(depth),
+            node.name,
+            node.total
+        ));
+        for child in &node.children {
+            render(child, depth + 1, out);
+        }
+    }
+
+    let mut out = String::new();
+    for node in nodes {
+        render(node, 0, &mut out);
+    }
+    out
+}
+
+/// User-defined map from alias tags to their canonical tag, persisted as JSON.
+#[derive(Debug)]
+pub struct TagAliases {
+    path: Option<PathBuf>,
+    aliases: Mutex<BTreeMap<String, String>>,
+}
+
+impl TagAliases {
+    /// Opens the alias map of `profile`: the one at `RAINDROP_TAG_ALIASES_PATH` (or in the
+    /// user's data directory) for the default profile, and `tag-aliases-<profile>.json`
+    /// beside it otherwise.
+    pub fn open_for_profile(profile: &str) -> Result<Self> {
+        let path = match env::var("RAINDROP_TAG_ALIASES_PATH") {
+            Ok(path) if !path.is_empty() => PathBuf::from(path),
+            _ => default_data_dir()
+                .ok_or_else(|| {
+                    RaindropMcpError::EnvironmentVariable(
+                        "Cannot determine a data directory for tag aliases; set RAINDROP_TAG_ALIASES_PATH"
+                            .to_string(),
+                    )
+                })?
+                .join(ALIASES_FILE_NAME),
+        };
+        Self::open(profile_path(&path, profile))
+    }
+
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let aliases = match fs::read_to_string(&path) {
+            Ok(text) if !text.trim().is_empty() => serde_json::from_str(&text)?,
+            Ok(_) => BTreeMap::new(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        debug!("Loaded tag aliases from {}", path.display());
+        Ok(Self {
+            path: Some(path),
+            aliases: Mutex::new(aliases),
+        })
+    }
+
+    /// Alias map that is never written to disk.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            aliases: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn list(&self) -> BTreeMap<String, String> {
+        self.aliases
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Canonical tag for `tag`, or `tag` itself when it is not an alias.
+    pub fn resolve(&self, tag: &str) -> String {
+        let aliases = self.aliases.lock().unwrap_or_else(|e| e.into_inner());
+        aliases
+            .get(&tag.to_lowercase())
+            .cloned()
+            .unwrap_or_else(|| tag.to_string())
+    }
+
+    pub fn set(&self, alias: &str, canonical: &str) -> Result<()> {
+        let alias = alias.trim().to_lowercase();
+        let canonical = canonical.trim();
+        if alias.is_empty() || canonical.is_empty() {
+            return Err(RaindropMcpError::InvalidParameter(
+                "Alias and canonical tag must not be empty".to_string(),
+            ));
+        }
+        if alias == canonical.to_lowercase() {
+            return Err(RaindropMcpError::InvalidParameter(format!(
+                "'{canonical}' cannot be an alias of itself"
+            )));
+        }
+        let mut aliases = self.aliases.lock().unwrap_or_else(|e| e.into_inner());
+        // Keep the map flat so resolving never needs more than one lookup
+        if aliases.contains_key(&canonical.to_lowercase()) {
+            return Err(RaindropMcpError::InvalidParameter(format!(
+                "'{canonical}' is itself an alias; point '{alias}' at its canonical tag instead"
+            )));
+        }
+        for target in aliases.values_mut() {
+            if target.to_lowercase() == alias {
+                *target = canonical.to_string();
+            }
+        }
+        aliases.insert(alias, canonical.to_string());
+        self.persist(&aliases)
+    }
+
+    /// Removes an alias, returning whether it existed.
+    pub fn remove(&self, alias: &str) -> Result<bool> {
+        let mut aliases = self.aliases.lock().unwrap_or_else(|e| e.into_inner());
+        let removed = aliases.remove(&alias.trim().to_lowercase()).is_some();
+        if removed {
+            self.persist(&aliases)?;
+        }
+        Ok(removed)
+    }
+
+    fn persist(&self, aliases: &BTreeMap<String, String>) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(aliases)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn tags(items: &[(&str, i32)]) -> Vec<Tag> {
+        items
+            .iter()
+            .map(|(id, count)| Tag {
+                id: id.to_string(),
+                count: *count,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("rust", "rust"), 0);
+        assert_eq!(levenshtein("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_suggest_merges() {
+        let library = tags(&[
+            ("Rust", 2),
+            ("rust", 10),
+            ("web-dev", 4),
+            ("web_dev", 1),
+            ("article", 6),
+            ("articles", 3),
+            ("javascript", 8),
+            ("javasript", 1),
+            ("python2", 1),
+            ("python3", 5),
+            ("js", 2),
+        ]);
+        let aliases = BTreeMap::from([("js".to_string(), "javascript".to_string())]);
+
+        let suggestions = suggest_merges(&library, &aliases, None);
+        let find = |into: &str| {
+            suggestions
+                .iter()
+                .find(|s| s.into == into)
+                .unwrap_or_else(|| panic!("no suggestion for {into}"))
+        };
+
+        assert_eq!(find("rust").merge, vec!["Rust"]);
+        assert_eq!(find("rust").reason, SuggestionReason::Case);
+        assert_eq!(find("web-dev").reason, SuggestionReason::Separator);
+        assert_eq!(find("article").merge, vec!["articles"]);
+        assert_eq!(find("article").reason, SuggestionReason::Plural);
+
+        let javascript: Vec<_> = suggestions
+            .iter()
+            .filter(|s| s.into == "javascript")
+            .map(|s| (s.reason, s.merge.clone()))
+            .collect();
+        assert_eq!(
+            javascript,
+            vec![
+                (SuggestionReason::Alias, vec!["js".to_string()]),
+                (SuggestionReason::Spelling, vec!["javasript".to_string()]),
+            ]
+        );
+        assert!(!suggestions.iter().any(|s| s.into.starts_with("python")));
+    }
+
+    #[test]
+    fn test_tag_tree() {
+        let library = tags(&[("lang/rust", 3), ("lang/go", 2), ("lang", 1), ("misc", 4)]);
+        let tree = tag_tree(&library, DEFAULT_HIERARCHY_SEPARATOR);
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].path, "lang");
+        assert_eq!(tree[0].count, 1);
+        assert_eq!(tree[0].total, 6);
+        assert_eq!(tree[0].children[1].path, "lang/rust");
+        assert_eq!(
+            render_tag_tree(&tree),
+            "lang (6)\n  go (2)\n  rust (3)\nmisc (4)\n"
+        );
+    }
+
+    #[test]
+    fn test_aliases_persist_and_stay_flat() {
+        let path = env::temp_dir().join(format!(
+            "raindrop-tag-aliases-test-{}.json",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        let aliases = TagAliases::open(&path).unwrap();
+        aliases.set("JS", "javascript").unwrap();
+        aliases.set("javascript", "ecmascript").unwrap();
+        assert!(aliases.set("es", "js").is_err());
+
+        let reopened = TagAliases::open(&path).unwrap();
+        assert_eq!(reopened.resolve("js"), "ecmascript");
+        assert_eq!(reopened.resolve("rust"), "rust");
+        assert!(reopened.remove("js").unwrap());
+        assert!(!reopened.remove("js").unwrap());
+    }
+}
@@ -1,5 +1,10 @@
 use serde::{Deserialize, Serialize};
 
+/// System collection holding bookmarks that have not been sorted yet.
+pub const UNSORTED_COLLECTION_ID: i64 = -1;
+/// System collection holding trashed bookmarks.
+pub const TRASH_COLLECTION_ID: i64 = -99;
+
 // User types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -239,6 +244,83 @@ pub struct FileInfo {
     pub file_type: String,
 }
 
+/// Fields for a bookmark to be created; unset fields are left to Raindrop.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewBookmark {
+    pub link: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collection: Option<CollectionRef>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub important: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media: Option<Vec<Media>>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub bookmark_type: Option<BookmarkType>,
+    /// Set to an empty object to have Raindrop parse the page in the background
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub please_parse: Option<serde_json::Value>,
+}
+
+impl NewBookmark {
+    /// Fills fields that are still unset from parsed page metadata.
+    pub fn apply_metadata(&mut self, parsed: &ParsedUrl) {
+        self.title = self.title.take().or_else(|| parsed.title.clone());
+        self.excerpt = self.excerpt.take().or_else(|| parsed.excerpt.clone());
+        self.cover = self.cover.take().or_else(|| parsed.cover.clone());
+        if self.media.is_none() && !parsed.media.is_empty() {
+            self.media = Some(parsed.media.clone());
+        }
+        self.bookmark_type = self
+            .bookmark_type
+            .take()
+            .or_else(|| parsed.bookmark_type.clone());
+    }
+}
+
+/// Page metadata used to pre-fill a bookmark before saving it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedUrl {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub media: Vec<Media>,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub bookmark_type: Option<BookmarkType>,
+}
+
+impl ParsedUrl {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.excerpt.is_none() && self.cover.is_none()
+    }
+
+    /// Fills fields that are still unset from another source.
+    pub fn merge(&mut self, other: ParsedUrl) {
+        self.title = self.title.take().or(other.title);
+        self.excerpt = self.excerpt.take().or(other.excerpt);
+        self.cover = self.cover.take().or(other.cover);
+        if self.media.is_empty() {
+            self.media = other.media;
+        }
+        self.bookmark_type = self.bookmark_type.take().or(other.bookmark_type);
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CollectionRef {
     #[serde(rename = "$id")]
@@ -247,7 +329,22 @@ pub struct CollectionRef {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Reminder {
-    pub data: Option<String>,
+    #[serde(alias = "data")]
+    pub date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// Bookmark with a pending reminder, as listed by `list_upcoming_reminders`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpcomingReminder {
+    pub bookmark_id: i64,
+    pub title: String,
+    pub link: String,
+    pub collection: i64,
+    pub date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub note: Option<String>,
 }
 
@@ -352,6 +449,8 @@ pub struct SearchParams {
     pub noparse: Option<bool>,
     pub since: Option<String>, // ISO string date
     pub created: Option<DateRange>,
+    /// Only these bookmarks
+    pub ids: Option<Vec<i64>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -494,6 +593,32 @@ pub struct BookmarksResponse {
     pub count: i32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedUrlResponse {
+    pub result: bool,
+    #[serde(default)]
+    pub item: Option<ParsedUrl>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlExistsResponse {
+    pub result: bool,
+    #[serde(default)]
+    pub ids: Vec<i64>,
+    #[serde(default)]
+    pub duplicates: Vec<UrlDuplicate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlDuplicate {
+    #[serde(rename = "_id")]
+    pub id: i64,
+    #[serde(default)]
+    pub link: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TagsResponse {
     pub items: Vec<Tag>,
@@ -511,6 +636,169 @@ pub struct HighlightsResponse {
     pub items: Vec<Highlight>,
 }
 
+/// Whether a URL is already saved, as returned by `RaindropClient::exists`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlExistence {
+    pub url: String,
+    pub normalized: String,
+    pub exists: bool,
+    pub ids: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookmarksCreatedResponse {
+    pub result: bool,
+    #[serde(default)]
+    pub items: Vec<Bookmark>,
+}
+
+// Bulk create types
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkCreateStatus {
+    Created,
+    Duplicate,
+    Failed,
+}
+
+/// Outcome for one item of `RaindropClient::create_bookmarks`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkCreateResult {
+    /// Position of the item in the request
+    pub index: usize,
+    pub link: String,
+    pub status: BulkCreateStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    /// Bookmarks that already point at the link
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub existing_ids: Vec<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl BulkCreateResult {
+    fn new(index: usize, link: &str, status: BulkCreateStatus) -> Self {
+        Self {
+            index,
+            link: link.to_string(),
+            status,
+            id: None,
+            existing_ids: vec![],
+            message: None,
+        }
+    }
+
+    pub fn created(index: usize, link: &str, id: i64) -> Self {
+        Self {
+            id: Some(id),
+            ..Self::new(index, link, BulkCreateStatus::Created)
+        }
+    }
+
+    pub fn duplicate(index: usize, link: &str, existing_ids: Vec<i64>, message: String) -> Self {
+        Self {
+            existing_ids,
+            message: Some(message),
+            ..Self::new(index, link, BulkCreateStatus::Duplicate)
+        }
+    }
+
+    pub fn failed(index: usize, link: &str, message: String) -> Self {
+        Self {
+            message: Some(message),
+            ..Self::new(index, link, BulkCreateStatus::Failed)
+        }
+    }
+}
+
+// Bulk update types
+/// Bookmarks targeted by a bulk update: explicit IDs, or a search within a collection.
+#[derive(Debug, Clone, Default)]
+pub struct BookmarkSelection {
+    /// Collection the bookmarks live in (0 for all collections)
+    pub collection_id: i64,
+    pub ids: Option<Vec<i64>>,
+    pub search: Option<String>,
+}
+
+/// Changes applied by `RaindropClient::bulk_update`; unset fields are left alone.
+#[derive(Debug, Clone, Default)]
+pub struct BulkUpdate {
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+    /// Collection to move the bookmarks to
+    pub collection: Option<i64>,
+    pub important: Option<bool>,
+    pub cover: Option<String>,
+    /// Media links to append; an empty list removes all media
+    pub media: Option<Vec<String>>,
+    /// Text appended to each bookmark's note on a new paragraph
+    pub append_note: Option<String>,
+}
+
+impl BulkUpdate {
+    /// Body for Raindrop's bulk endpoint, or `None` if only per-item changes were requested.
+    pub fn bulk_body(&self) -> Option<serde_json::Value> {
+        let mut body = serde_json::Map::new();
+        // The bulk endpoint appends tags; an empty list would clear them instead
+        if !self.add_tags.is_empty() {
+            body.insert("tags".to_string(), serde_json::json!(self.add_tags));
+        }
+        if let Some(collection) = self.collection {
+            body.insert(
+                "collection".to_string(),
+                serde_json::json!({ "$id": collection }),
+            );
+        }
+        if let Some(important) = self.important {
+            body.insert("important".to_string(), important.into());
+        }
+        if let Some(cover) = &self.cover {
+            body.insert("cover".to_string(), cover.clone().into());
+        }
+        if let Some(media) = &self.media {
+            let media: Vec<_> = media
+                .iter()
+                .map(|link| serde_json::json!({ "link": link }))
+                .collect();
+            body.insert("media".to_string(), media.into());
+        }
+        (!body.is_empty()).then_some(serde_json::Value::Object(body))
+    }
+
+    /// Whether some changes need each bookmark's current state.
+    pub fn needs_per_item(&self) -> bool {
+        !self.remove_tags.is_empty() || self.append_note.is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bulk_body().is_none() && !self.needs_per_item()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkUpdateReport {
+    pub matched: usize,
+    pub updated: usize,
+    pub ids: Vec<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+// Trash types
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoredBookmark {
+    pub id: i64,
+    pub collection: i64,
+    /// Whether the target is the collection the bookmark was trashed from
+    pub original_collection: bool,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -628,6 +916,16 @@ mod tests {
         matches!(bookmark.bookmark_type, BookmarkType::Link);
     }
 
+    #[test]
+    fn test_reminder_date_field() {
+        let reminder: Reminder =
+            serde_json::from_value(json!({ "date": "2024-05-01T09:00:00.000Z" })).unwrap();
+        assert_eq!(reminder.date.as_deref(), Some("2024-05-01T09:00:00.000Z"));
+
+        let json = serde_json::to_value(&reminder).unwrap();
+        assert_eq!(json, json!({ "date": "2024-05-01T09:00:00.000Z" }));
+    }
+
     #[test]
     fn test_search_params_default() {
         let params = SearchParams::default();
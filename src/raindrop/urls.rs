@@ -0,0 +1,111 @@
+use url::Url;
+
+// Query parameters that only track where a click came from
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid", "ref_src",
+];
+
+/// Key under which trivially different URLs compare equal.
+///
+/// Ignores the scheme, a leading `www.`, default ports, fragments, trailing slashes,
+/// tracking parameters and the order of the remaining query parameters.
+pub fn normalize_url(url: &str) -> String {
+    match Parts::parse(url) {
+        Some(parts) => format!("{}{}{}", parts.host, parts.path, parts.query),
+        None => url.trim().to_lowercase(),
+    }
+}
+
+/// Spellings of `url` that normalize to the same key, for exact-match lookups.
+pub fn url_variants(url: &str) -> Vec<String> {
+    let mut variants = vec![url.trim().to_string()];
+    if let Some(parts) = Parts::parse(url) {
+        let paths = if parts.path.is_empty() {
+            vec!["/".to_string()]
+        } else {
+            vec![parts.path.clone(), format!("{}/", parts.path)]
+        };
+        for scheme in ["https", "http"] {
+            for host in [parts.host.clone(), format!("www.{}", parts.host)] {
+                for path in &paths {
+                    variants.push(format!("{scheme}://{host}{path}{}", parts.query));
+                }
+            }
+        }
+    }
+    let mut seen = std::collections::HashSet::new();
+    variants.retain(|v| seen.insert(v.clone()));
+    variants
+}
+
+struct Parts {
+    /// Host without `www.`, plus a non-default port
+    host: String,
+    /// Path without a trailing slash
+    path: String,
+    /// Sorted query including the leading `?`, or empty
+    query: String,
+}
+
+impl Parts {
+    fn parse(url: &str) -> Option<Self> {
+        let parsed = Url::parse(url.trim()).ok()?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return None;
+        }
+        let host = parsed.host_str()?;
+        let mut host = host.strip_prefix("www.").unwrap_or(host).to_string();
+        if let Some(port) = parsed.port() {
+            host.push_str(&format!(":{port}"));
+        }
+
+        let path = parsed.path().trim_end_matches('/').to_string();
+
+        let mut params: Vec<(String, String)> = parsed
+            .query_pairs()
+            .filter(|(key, _)| !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_ref()))
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        params.sort();
+        let query = if params.is_empty() {
+            String::new()
+        } else {
+            let encoded = url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(params)
+                .finish();
+            format!("?{encoded}")
+        };
+
+        Some(Self { host, path, query })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_normalize_url_ignores_trivial_differences() {
+        let key = normalize_url("https://example.com/post?b=2&a=1");
+        for url in [
+            "http://www.example.com/post/?a=1&b=2",
+            "https://EXAMPLE.com:443/post?utm_source=x&a=1&b=2#comments",
+            "https://example.com/post?a=1&fbclid=abc&b=2",
+        ] {
+            assert_eq!(normalize_url(url), key, "{url}");
+        }
+        assert_ne!(normalize_url("https://example.com/other"), key);
+        assert_ne!(normalize_url("https://example.com:8080/post?a=1&b=2"), key);
+    }
+
+    #[test]
+    fn test_url_variants() {
+        let variants = url_variants("https://www.example.com/post/");
+        assert_eq!(variants[0], "https://www.example.com/post/");
+        assert!(variants.contains(&"http://example.com/post".to_string()));
+        assert!(variants.contains(&"https://www.example.com/post".to_string()));
+        assert_eq!(variants.len(), 8);
+        assert_eq!(url_variants("not a url"), vec!["not a url".to_string()]);
+    }
+}